@@ -1,26 +1,173 @@
+use std::collections::{BTreeSet, HashSet};
+use std::ops::Range;
 use std::path::PathBuf;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
 use color_eyre::eyre::Result;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::backend::Backend;
+use ratatui::layout::{Position, Rect};
+use ratatui::Terminal;
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
-use crate::config::types::Config;
-use crate::event::{Event, EventHandler};
+use crate::config::keys::key_matches;
+use crate::config::loader::ConfigLoader;
+use crate::config::types::{Config, SortMode};
+use crate::event::{ChangeScope, Event, EventHandler};
+use crate::file_tree::{self, FileRow, FileView};
+use crate::filter::{self, FileFilter};
 use crate::git::service::GitService;
-use crate::git::types::GitStatus;
+use crate::git::types::{
+    path_is_within, FileStatus, FileStatusType, GitStatus, ProgressLine, RemoteOperation, Snapshot,
+    SnapshotChanges, StatusOptions, StatusUpdate, UntrackedMode,
+};
+use crate::highlights::ChangeHighlights;
+use crate::search::{DiffSearch, SearchMatch};
+use crate::sort;
 use crate::theme::themes::Theme;
+use crate::timeline::Timeline;
+use crate::toast::{Notifications, Severity, Toast};
 use crate::tui;
-use crate::ui;
-use crate::watcher::service::FileWatcher;
+use crate::ui::{self, PaneLayout};
+use crate::watcher::service::{FileWatcher, WatcherStatus};
 
 /// Which section is currently selected
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Section {
     Staged,
     Unstaged,
 }
 
+/// What a line of the file list widget shows (for mouse hit-testing)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileListLine {
+    /// A section header
+    Header(Section),
+    /// A row of a section
+    Row(Section, usize),
+    /// Separators and messages
+    Blank,
+}
+
+/// Narrowest the file list or preview can be dragged, in percent of the width
+const MIN_SPLIT_PERCENT: u16 = 15;
+
+/// Which pane receives navigation keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Files,
+    Preview,
+}
+
+/// Lines scrolled per mouse wheel step
+const WHEEL_SCROLL_LINES: isize = 3;
+
+/// Lines kept above a search match when scrolling to it
+const SEARCH_CONTEXT_LINES: usize = 3;
+
+/// How long a successful push/pull panel stays visible after finishing
+const REMOTE_PANEL_LINGER: Duration = Duration::from_secs(3);
+
+/// Maximum number of progress lines kept for the remote panel
+const REMOTE_PANEL_MAX_LINES: usize = 200;
+
+/// State of the transient panel shown while a push/pull runs
+#[derive(Debug, Clone)]
+pub struct RemotePanel {
+    /// The operation being run
+    pub operation: RemoteOperation,
+    /// Progress lines streamed from git
+    pub lines: Vec<ProgressLine>,
+    /// Outcome once finished (summary or readable error)
+    pub outcome: Option<std::result::Result<String, String>>,
+    /// When the operation finished
+    pub finished_at: Option<Instant>,
+}
+
+impl RemotePanel {
+    /// Create a panel for a newly started operation
+    fn new(operation: RemoteOperation) -> Self {
+        Self {
+            operation,
+            lines: Vec::new(),
+            outcome: None,
+            finished_at: None,
+        }
+    }
+
+    /// Whether the operation is still running
+    pub fn is_running(&self) -> bool {
+        self.outcome.is_none()
+    }
+
+    /// Append a progress line, replacing the previous one if it was transient
+    fn push_line(&mut self, line: ProgressLine) {
+        if self.lines.last().is_some_and(|last| last.transient) {
+            self.lines.pop();
+        }
+        self.lines.push(line);
+        if self.lines.len() > REMOTE_PANEL_MAX_LINES {
+            self.lines.remove(0);
+        }
+    }
+}
+
+/// What the snapshot browser is waiting for confirmation of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotAction {
+    /// Restore the selected file from the selected snapshot
+    Restore,
+    /// Delete the snapshots older than the selected one
+    Prune,
+}
+
+/// State of the snapshot browser overlay
+#[derive(Debug, Default)]
+pub struct SnapshotBrowser {
+    /// Recorded snapshots, newest first (None while loading)
+    pub snapshots: Option<Vec<Snapshot>>,
+    /// Selected snapshot
+    pub selected: usize,
+    /// Files changed since the selected snapshot (None while loading)
+    pub changes: Option<SnapshotChanges>,
+    /// Selected changed file, while browsing the files of the snapshot
+    pub file_selected: Option<usize>,
+    /// Diff of the selected file since the snapshot (None while loading)
+    pub diff: Option<String>,
+    /// Action waiting for confirmation
+    pub confirm: Option<SnapshotAction>,
+}
+
+impl SnapshotBrowser {
+    /// The selected snapshot, once the list is loaded
+    pub fn snapshot(&self) -> Option<&Snapshot> {
+        self.snapshots.as_ref()?.get(self.selected)
+    }
+
+    /// The selected changed file, while browsing the files of the snapshot
+    pub fn file(&self) -> Option<&FileStatus> {
+        self.changes.as_ref()?.files.get(self.file_selected?)
+    }
+}
+
+/// Frames of the spinner shown while git commands or a push/pull run
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Above this many changed paths, a full refresh is cheaper than a pathspec-limited one
+const MAX_INCREMENTAL_PATHS: usize = 100;
+
+/// An in-flight status refresh
+#[derive(Debug)]
+struct StatusRequest {
+    /// Generation the result will be tagged with
+    generation: u64,
+    /// What the request covers (kept so a superseding request can include it)
+    scope: ChangeScope,
+    /// Task running the git commands (aborting it kills the git processes)
+    task: JoinHandle<()>,
+}
+
 /// Application actions
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Action {
@@ -31,78 +178,346 @@ pub enum Action {
     PageDown,
     TogglePreview,
     ToggleHelp,
+    ToggleConsole,
+    ToggleTimeline,
+    ToggleSnapshots,
+    OpenSnapshotFiles,
+    CloseSnapshotFiles,
+    RestoreSnapshotFile,
+    PruneSnapshots,
+    ConfirmSnapshotPrompt,
+    CancelSnapshotPrompt,
+    ToggleNotificationHistory,
+    DismissToasts,
     Refresh,
     SwitchSection,
     StageUnstage,
+    Push,
+    Pull,
+    DismissRemotePanel,
+    CycleUntrackedMode,
+    ToggleExpand,
+    TogglePause,
+    ToggleFileView,
+    ToggleZoom,
+    ToggleMark,
+    MarkRange,
+    MarkAll,
+    ClearMarks,
+    StageAll,
+    UnstageAll,
+    Discard,
+    ConfirmDiscard,
+    CancelDiscard,
+    CycleSort,
+    ToggleFollow,
+    OpenFilter,
+    FilterChar(char),
+    FilterBackspace,
+    CloseFilter,
+    ClearFilter,
+    CycleStatusFilter,
+    CycleExtensionFilter,
+    FocusFiles,
+    FocusPreview,
+    ScrollPreviewUp,
+    ScrollPreviewDown,
+    ScrollPreviewPageUp,
+    ScrollPreviewPageDown,
+    ScrollPreviewHalfPageUp,
+    ScrollPreviewHalfPageDown,
+    ScrollPreviewTop,
+    ScrollPreviewBottom,
+    ScrollPreviewWheelUp,
+    ScrollPreviewWheelDown,
+    NextHunk,
+    PreviousHunk,
+    OpenSearch,
+    SearchChar(char),
+    SearchBackspace,
+    CloseSearch,
+    CancelSearch,
+    ToggleSearchRegex,
+    ToggleSearchCase,
+    NextMatch,
+    PreviousMatch,
     None,
 }
 
+impl Action {
+    /// Whether the action is the user moving the selection or the preview by hand
+    fn is_navigation(self) -> bool {
+        matches!(
+            self,
+            Action::MoveUp
+                | Action::MoveDown
+                | Action::PageUp
+                | Action::PageDown
+                | Action::SwitchSection
+                | Action::ScrollPreviewUp
+                | Action::ScrollPreviewDown
+                | Action::ScrollPreviewPageUp
+                | Action::ScrollPreviewPageDown
+                | Action::ScrollPreviewHalfPageUp
+                | Action::ScrollPreviewHalfPageDown
+                | Action::ScrollPreviewTop
+                | Action::ScrollPreviewBottom
+                | Action::ScrollPreviewWheelUp
+                | Action::ScrollPreviewWheelDown
+                | Action::NextHunk
+                | Action::PreviousHunk
+                | Action::NextMatch
+                | Action::PreviousMatch
+        )
+    }
+}
+
 /// Application state
 pub struct App {
     /// Path to the Git repository
     pub repo_path: PathBuf,
+    /// Git service used for all git commands (clones share the command log)
+    pub git_service: GitService,
     /// Current Git status
     pub git_status: Option<GitStatus>,
     /// Current diff content for preview
     pub diff_content: Option<String>,
-    /// Currently selected file index within the current section
+    /// File (path, staged) the current diff content belongs to
+    diff_key: Option<(String, bool)>,
+    /// Generation of the latest diff request
+    diff_generation: u64,
+    /// Task fetching the latest diff (aborted when superseded)
+    diff_task: Option<JoinHandle<()>>,
+    /// Which untracked files are listed (starts from the config, can be cycled)
+    pub untracked_mode: UntrackedMode,
+    /// Collapsed untracked directories (with trailing `/`) the user expanded
+    pub expanded_dirs: BTreeSet<String>,
+    /// Generation of the latest status refresh
+    status_generation: u64,
+    /// The status refresh currently running, if any
+    status_request: Option<StatusRequest>,
+    /// Flat list or directory tree
+    pub file_view: FileView,
+    /// Order of the files within each section
+    pub sort_mode: SortMode,
+    /// Whether the selection follows the most recently changed file
+    pub follow: bool,
+    /// Changed paths to pick the file to select from once their status refresh
+    /// arrives (in follow mode)
+    follow_changes: Option<Vec<String>>,
+    /// Directories (with trailing `/`) collapsed in the tree view
+    pub collapsed_dirs: BTreeSet<String>,
+    /// Narrows both file lists (selection and staging act on what is visible)
+    pub filter: FileFilter,
+    /// Whether the filter prompt is capturing typed characters
+    pub filter_input: bool,
+    /// Currently selected row within the current section
     pub selected_index: usize,
     /// Currently selected section (staged or unstaged)
     pub selected_section: Section,
+    /// Pane receiving navigation keys
+    pub focus: Focus,
     /// Preview scroll offset
     pub preview_scroll: u16,
+    /// Where the preview was drawn last (for paging and mouse hit-testing)
+    pub preview_area: Rect,
+    /// Where the file list was drawn last
+    pub file_list_area: Rect,
+    /// Lines of the file list widget as last drawn
+    pub file_list_lines: Vec<FileListLine>,
+    /// First file list line visible in the last draw
+    pub file_list_offset: usize,
+    /// Placement of the file list and the preview in the last draw
+    pub pane_layout: PaneLayout,
+    /// Share of the content area given to the file list in percent
+    pub split_percent: u16,
+    /// Whether the focused pane takes the whole content area
+    pub zoomed: bool,
+    /// Whether the divider between the list and the preview is being dragged
+    dragging_divider: bool,
+    /// Sections whose files are hidden (clicking a header toggles this)
+    pub collapsed_sections: HashSet<Section>,
+    /// Marked files by section and path (kept across refreshes while the file stays in its section)
+    pub marks: HashSet<(Section, String)>,
+    /// Row last marked (start of a range selection), by section and path
+    mark_anchor: Option<(Section, String)>,
+    /// Files waiting for confirmation before their changes are discarded
+    pub pending_discard: Option<Vec<FileStatus>>,
+    /// Search within the diff preview
+    pub diff_search: DiffSearch,
+    /// Whether the search prompt is capturing typed characters
+    pub search_input: bool,
+    /// Preview line the search started from (incremental matches begin here)
+    search_origin: usize,
     /// Whether to show the file preview panel
     pub show_preview: bool,
     /// Whether to show the help menu
     pub show_help: bool,
+    /// Whether to show the git command console
+    pub show_console: bool,
+    /// Selected entry in the command console (0 = most recent)
+    pub console_selected: usize,
+    /// Refreshes of this session in which the status changed
+    pub timeline: Timeline,
+    /// Whether to show the session timeline
+    pub show_timeline: bool,
+    /// Selected row of the timeline (0 = most recent)
+    pub timeline_selected: usize,
+    /// Diff of the selected timeline row, once loaded
+    pub timeline_diff: Option<String>,
+    /// Generation of the latest timeline diff request
+    timeline_diff_generation: u64,
+    /// Snapshot browser (if open)
+    pub snapshot_browser: Option<SnapshotBrowser>,
+    /// Generation of the latest snapshot browser request
+    snapshot_generation: u64,
+    /// When files will have settled enough to take a snapshot
+    snapshot_due: Option<Instant>,
+    /// When the last snapshot was started
+    last_snapshot_at: Instant,
+    /// Whether a snapshot is being taken
+    snapshot_running: bool,
+    /// Toast notifications and their history
+    pub notifications: Notifications,
+    /// Fading highlights for files and hunks that just changed
+    pub highlights: ChangeHighlights,
+    /// Whether to show the notification history
+    pub show_notification_history: bool,
     /// Application configuration
     pub config: Config,
+    /// Where settings changed in the app (like the sort order) are saved
+    config_path: Option<PathBuf>,
     /// Current theme
     pub theme: Theme,
     /// Whether watch mode is enabled
     pub watch_mode: bool,
+    /// Whether file changes are queued instead of applied (frozen view)
+    pub paused: bool,
+    /// Changes received while paused, merged into one scope
+    pending_scope: Option<ChangeScope>,
+    /// Number of change notifications received while paused
+    pending_changes: usize,
+    /// Health of the file watcher (None until it reports)
+    pub watcher_status: Option<WatcherStatus>,
     /// Whether the application should quit
     pub should_quit: bool,
+    /// Push/pull progress panel (if an operation is running or just finished)
+    pub remote_panel: Option<RemotePanel>,
     /// Event sender for async operations
     event_tx: Option<mpsc::UnboundedSender<Event>>,
+    /// Whether the screen is out of date and must be drawn again
+    needs_redraw: bool,
+    /// Current spinner frame (advances on ticks while something is animating)
+    animation_frame: usize,
+    /// Git pool (running, queued) counts shown by the last draw
+    drawn_activity: (usize, usize),
 }
 
 impl App {
     /// Create a new application instance
     pub fn new(repo_path: PathBuf, config: Config, theme: Theme, watch_mode: bool) -> Self {
         Self {
+            git_service: GitService::with_limits(repo_path.clone(), config.git.runner_limits()),
             repo_path,
             git_status: None,
             diff_content: None,
+            diff_key: None,
+            diff_generation: 0,
+            diff_task: None,
+            untracked_mode: config.git.untracked_files,
+            expanded_dirs: BTreeSet::new(),
+            status_generation: 0,
+            status_request: None,
+            file_view: if config.display.tree_view {
+                FileView::Tree
+            } else {
+                FileView::Flat
+            },
+            collapsed_dirs: BTreeSet::new(),
+            sort_mode: config.display.sort_by,
+            follow: false,
+            follow_changes: None,
+            filter: FileFilter::default(),
+            filter_input: false,
             selected_index: 0,
             selected_section: Section::Unstaged,
+            focus: Focus::Files,
             preview_scroll: 0,
+            preview_area: Rect::default(),
+            file_list_area: Rect::default(),
+            file_list_lines: Vec::new(),
+            file_list_offset: 0,
+            pane_layout: PaneLayout::default(),
+            split_percent: config
+                .ui
+                .split_percent
+                .clamp(MIN_SPLIT_PERCENT, 100 - MIN_SPLIT_PERCENT),
+            zoomed: false,
+            dragging_divider: false,
+            collapsed_sections: HashSet::new(),
+            marks: HashSet::new(),
+            mark_anchor: None,
+            pending_discard: None,
+            diff_search: DiffSearch::default(),
+            search_input: false,
+            search_origin: 0,
             show_preview: config.display.show_file_preview,
             show_help: false,
+            show_console: false,
+            console_selected: 0,
+            timeline: Timeline::default(),
+            show_timeline: false,
+            timeline_selected: 0,
+            timeline_diff: None,
+            timeline_diff_generation: 0,
+            snapshot_browser: None,
+            snapshot_generation: 0,
+            snapshot_due: None,
+            last_snapshot_at: Instant::now(),
+            snapshot_running: false,
+            notifications: Notifications::new(Duration::from_millis(
+                config.ui.toast_timeout_ms as u64,
+            )),
+            show_notification_history: false,
+            highlights: ChangeHighlights::new(Duration::from_millis(config.ui.change_highlight_ms)),
             config,
+            config_path: None,
             theme,
             watch_mode,
+            watcher_status: None,
+            paused: false,
+            pending_scope: None,
+            pending_changes: 0,
             should_quit: false,
+            remote_panel: None,
             event_tx: None,
+            needs_redraw: true,
+            animation_frame: 0,
+            drawn_activity: (0, 0),
         }
     }
 
+    /// Save settings changed in the app (like the sort order) to this config file
+    pub fn with_config_path(mut self, path: PathBuf) -> Self {
+        self.config_path = Some(path);
+        self
+    }
+
     /// Run the application main loop
     pub async fn run(&mut self) -> Result<()> {
         // Install panic hook
         tui::install_panic_hook();
 
         // Initialize terminal
-        let mut terminal = tui::init()?;
+        let mut terminal = tui::init(self.config.ui.mouse)?;
 
-        // Create event handler with tick rate from config
-        let tick_rate = Duration::from_millis(self.config.ui.refresh_debounce_ms as u64);
+        // Ticks only drive timers and animations; the screen is drawn when something changed
+        let tick_rate = Duration::from_millis(self.config.ui.tick_rate_ms.max(1) as u64);
         let mut events = EventHandler::new(tick_rate);
         self.event_tx = Some(events.sender());
 
         // Verify this is a git repository
-        let git_service = GitService::new(self.repo_path.clone());
-        if !git_service.is_git_repo().await? {
+        if !self.git_service.is_git_repo().await? {
             tui::restore()?;
             return Err(color_eyre::eyre::eyre!(
                 "Not a git repository: {}",
@@ -111,54 +526,426 @@ impl App {
         }
 
         // Initial git status fetch
-        self.refresh_git_status(&git_service).await?;
+        self.request_status_refresh(ChangeScope::Full);
+
+        // Start the session with a snapshot to go back to
+        if self.config.snapshots.enabled {
+            self.snapshot_due = Some(Instant::now());
+        }
 
         // Start file watcher if watch mode is enabled
+        // A watcher failure is not fatal: keep running and let `r` refresh manually
         let _watcher = if self.watch_mode {
-            let watcher = FileWatcher::new(
+            match FileWatcher::new(
                 self.repo_path.clone(),
                 events.sender(),
-                Duration::from_millis(self.config.ui.refresh_debounce_ms as u64),
-            )?;
-            Some(watcher)
+                &self.config.watcher,
+            ) {
+                Ok(watcher) => Some(watcher),
+                Err(report) => {
+                    self.set_watcher_status(WatcherStatus::Failed(report.to_string()));
+                    None
+                }
+            }
         } else {
             None
         };
 
-        // Main event loop
+        // Periodic full refresh, for filesystems where change events are unreliable
+        let periodic_refresh = self.config.watcher.periodic_refresh_ms;
+        if self.watch_mode && periodic_refresh > 0 {
+            let tx = events.sender();
+            tokio::spawn(async move {
+                let period = Duration::from_millis(periodic_refresh);
+                let mut interval =
+                    tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                loop {
+                    interval.tick().await;
+                    // Stops once the event loop is gone
+                    if tx.send(Event::FileChange(ChangeScope::Full)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        let result = self.event_loop(&mut terminal, &mut events).await;
+
+        // Restore terminal
+        tui::restore()?;
+
+        result
+    }
+
+    /// Handle events until the user quits, drawing only when the screen is out of date
+    async fn event_loop<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        events: &mut EventHandler,
+    ) -> Result<()> {
         loop {
-            // Render the UI
-            terminal.draw(|frame| ui::render(frame, self))?;
+            if self.needs_redraw {
+                terminal.draw(|frame| ui::render(frame, self))?;
+                self.needs_redraw = false;
+                self.drawn_activity = self.git_activity();
+            }
 
-            // Handle events
+            // Ticks decide for themselves whether anything visible changed
             let event = events.next().await?;
+            if !matches!(event, Event::Tick) {
+                self.needs_redraw = true;
+            }
             let action = self.handle_event(event);
-            self.handle_action(action, &git_service).await?;
+            if action != Action::None {
+                self.needs_redraw = true;
+            }
+
+            // Git failures are recoverable: surface them as notifications and keep running
+            if let Err(report) = self.handle_action(action) {
+                self.notifications.error(&report);
+            }
 
             if self.should_quit {
-                break;
+                return Ok(());
             }
         }
+    }
 
-        // Restore terminal
-        tui::restore()?;
+    /// Number of (running, queued) git commands
+    fn git_activity(&self) -> (usize, usize) {
+        let pool = self.git_service.pool();
+        (pool.running(), pool.queued())
+    }
 
-        Ok(())
+    /// Whether something on screen animates (git commands or a push/pull running)
+    fn is_animating(&self) -> bool {
+        self.git_activity() != (0, 0)
+            || self
+                .remote_panel
+                .as_ref()
+                .is_some_and(RemotePanel::is_running)
+    }
+
+    /// Current frame of the activity spinner
+    pub fn spinner(&self) -> &'static str {
+        SPINNER[self.animation_frame % SPINNER.len()]
     }
 
     /// Handle an incoming event and return the appropriate action
     fn handle_event(&mut self, event: Event) -> Action {
         match event {
             Event::Key(key) => self.handle_key_event(key),
-            Event::FileChange => Action::Refresh,
-            Event::DiffReady(diff) => {
-                self.diff_content = Some(diff);
+            Event::FileChange(scope) => {
+                // Snapshots are taken once the files stop changing, even while paused
+                if self.config.snapshots.enabled {
+                    let settle = Duration::from_millis(self.config.snapshots.settle_ms);
+                    self.snapshot_due = Some(Instant::now() + settle);
+                }
+                if self.paused {
+                    // Keep the view frozen; apply everything when resuming
+                    self.pending_changes += 1;
+                    let pending = self.pending_scope.take();
+                    self.pending_scope = Some(match pending {
+                        Some(pending) => pending.merge(scope),
+                        None => scope,
+                    });
+                } else {
+                    if let (true, ChangeScope::Paths(paths)) = (self.follow, &scope) {
+                        self.follow_changes
+                            .get_or_insert_with(Vec::new)
+                            .extend(paths.iter().cloned());
+                    }
+                    self.request_status_refresh(scope);
+                }
+                Action::None
+            }
+            Event::DiffReady {
+                generation,
+                path,
+                staged,
+                result,
+            } => {
+                // Drop results for requests that have since been superseded
+                if generation == self.diff_generation {
+                    self.diff_task = None;
+                    match result {
+                        Ok(diff) => {
+                            let key = Some((path, staged));
+                            let new_file = self.diff_key != key;
+                            let changed_line = match &self.diff_content {
+                                Some(old) if !new_file => {
+                                    if let Some(key) = key.clone() {
+                                        self.highlights.record_diff(
+                                            key,
+                                            old,
+                                            &diff,
+                                            Instant::now(),
+                                        );
+                                    }
+                                    changed_hunk_start(old, &diff)
+                                }
+                                _ => None,
+                            };
+                            self.diff_content = Some(diff);
+                            self.diff_key = key;
+                            // Keep searching in the new diff; a refreshed one keeps its scroll position
+                            if new_file {
+                                self.search_origin = 0;
+                            }
+                            self.refresh_search(new_file);
+                            // Following shows the hunk that just changed in the followed file
+                            if let (true, Some(line)) = (self.follow, changed_line) {
+                                self.set_preview_scroll(line);
+                            }
+                        }
+                        Err(toast) => self.notifications.push(toast),
+                    }
+                }
+                Action::None
+            }
+            Event::StatusReady { generation, result } => {
+                if self
+                    .status_request
+                    .as_ref()
+                    .is_some_and(|request| request.generation == generation)
+                {
+                    self.status_request = None;
+                    match result {
+                        Ok(update) => self.apply_status_update(update),
+                        Err(toast) => self.notifications.push(toast),
+                    }
+                }
+                Action::None
+            }
+            Event::TimelineBlobs { id, result } => {
+                // A failed capture leaves the entry without diffs; it is not worth a toast
+                let blobs = result.unwrap_or_default();
+                self.timeline.set_blobs(id, blobs);
+                if self.show_timeline {
+                    self.update_timeline_diff();
+                }
+                Action::None
+            }
+            Event::TimelineDiffReady { generation, result } => {
+                if generation == self.timeline_diff_generation {
+                    match result {
+                        Ok(diff) => self.timeline_diff = Some(diff),
+                        Err(toast) => self.notifications.push(toast),
+                    }
+                }
+                Action::None
+            }
+            Event::SnapshotTaken(result) => {
+                self.snapshot_running = false;
+                match result {
+                    Ok(Some(_)) if self.snapshot_browser.is_some() => self.load_snapshots(),
+                    Ok(_) => {}
+                    Err(toast) => self.notifications.push(toast),
+                }
+                Action::None
+            }
+            Event::SnapshotsLoaded { generation, result } => {
+                if generation == self.snapshot_generation {
+                    match result {
+                        Ok(snapshots) => self.show_snapshots(snapshots),
+                        Err(toast) => self.notifications.push(toast),
+                    }
+                }
+                Action::None
+            }
+            Event::SnapshotChangesReady { generation, result } => {
+                if generation == self.snapshot_generation {
+                    match result {
+                        Ok(changes) => self.show_snapshot_changes(changes),
+                        Err(toast) => self.notifications.push(toast),
+                    }
+                }
+                Action::None
+            }
+            Event::SnapshotDiffReady { generation, result } => {
+                if generation == self.snapshot_generation {
+                    match result {
+                        Ok(diff) => {
+                            if let Some(browser) = &mut self.snapshot_browser {
+                                browser.diff = Some(diff);
+                            }
+                        }
+                        Err(toast) => self.notifications.push(toast),
+                    }
+                }
+                Action::None
+            }
+            Event::SnapshotActionFinished(result) => {
+                self.notifications.push(match result {
+                    Ok(summary) => Toast::new(Severity::Success, summary),
+                    Err(toast) => toast,
+                });
+                if self.snapshot_browser.is_some() {
+                    self.load_snapshots();
+                }
+                self.request_status_refresh(ChangeScope::Full);
+                Action::None
+            }
+            Event::StageFinished(result) => {
+                if let Err(toast) = result {
+                    self.notifications.push(toast);
+                }
+                // Refresh the status to reflect the changes
+                self.request_status_refresh(ChangeScope::Full);
+                Action::None
+            }
+            Event::WatcherStatus(status) => {
+                self.set_watcher_status(status);
+                Action::None
+            }
+            Event::RemoteProgress(line) => {
+                if let Some(panel) = &mut self.remote_panel {
+                    panel.push_line(line);
+                }
+                Action::None
+            }
+            Event::RemoteFinished(outcome) => {
+                let succeeded = outcome.is_ok();
+                self.notifications.push(match &outcome {
+                    Ok(summary) => Toast::new(Severity::Success, summary.clone()),
+                    Err(message) => Toast::new(Severity::Error, message.clone()),
+                });
+                if let Some(panel) = &mut self.remote_panel {
+                    panel.outcome = Some(outcome);
+                    panel.finished_at = Some(Instant::now());
+                }
+                if succeeded {
+                    Action::Refresh
+                } else {
+                    Action::None
+                }
+            }
+            Event::Tick => {
+                let now = Instant::now();
+                if self.notifications.expire(now) {
+                    self.needs_redraw = true;
+                }
+                if self.snapshot_is_due(now) {
+                    self.take_snapshot();
+                }
+                // Change highlights fade on every tick until they are gone
+                if self.highlights.expire(now) || self.highlights.is_active() {
+                    self.needs_redraw = true;
+                }
+                if self.is_animating() {
+                    self.animation_frame = self.animation_frame.wrapping_add(1);
+                    self.needs_redraw = true;
+                } else if self.git_activity() != self.drawn_activity {
+                    // Clear the activity indicator once the last command finished
+                    self.needs_redraw = true;
+                }
+
+                // Hide the remote panel a little while after a successful operation
+                let expired = self.remote_panel.as_ref().is_some_and(|panel| {
+                    matches!(panel.outcome, Some(Ok(_)))
+                        && panel
+                            .finished_at
+                            .is_some_and(|t| t.elapsed() >= REMOTE_PANEL_LINGER)
+                });
+                if expired {
+                    Action::DismissRemotePanel
+                } else {
+                    Action::None
+                }
+            }
+            Event::Mouse(mouse) => self.handle_mouse_event(mouse),
+            Event::Resize(_, _) => Action::None,
+        }
+    }
+
+    /// Handle mouse events
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Action {
+        // Overlays take the whole screen's attention
+        if self.show_help
+            || self.show_console
+            || self.show_timeline
+            || self.snapshot_browser.is_some()
+            || self.show_notification_history
+        {
+            return Action::None;
+        }
+
+        let position = Position::new(mouse.column, mouse.row);
+        let over_preview = self.preview_area.contains(position);
+        let over_list = self.file_list_area.contains(position);
+        let on_divider = self.pane_layout.is_on_divider(position);
+
+        match mouse.kind {
+            MouseEventKind::ScrollUp if over_preview => Action::ScrollPreviewWheelUp,
+            MouseEventKind::ScrollDown if over_preview => Action::ScrollPreviewWheelDown,
+            MouseEventKind::ScrollUp if over_list => Action::MoveUp,
+            MouseEventKind::ScrollDown if over_list => Action::MoveDown,
+            MouseEventKind::Down(MouseButton::Left) if on_divider => {
+                self.dragging_divider = true;
+                Action::None
+            }
+            MouseEventKind::Drag(MouseButton::Left) if self.dragging_divider => {
+                self.drag_divider_to(position);
+                Action::None
+            }
+            MouseEventKind::Up(MouseButton::Left) => {
+                self.dragging_divider = false;
+                Action::None
+            }
+            MouseEventKind::Down(MouseButton::Left) if over_preview => Action::FocusPreview,
+            MouseEventKind::Down(MouseButton::Left) if over_list => {
+                self.click_file_list(mouse.row);
                 Action::None
             }
-            Event::Tick | Event::Resize(_, _) => Action::None,
+            _ => Action::None,
+        }
+    }
+
+    /// Select the row, or toggle the section, at a screen row of the file list
+    fn click_file_list(&mut self, row: u16) {
+        self.focus = Focus::Files;
+
+        // Skip the top border
+        let Some(line) = row.checked_sub(self.file_list_area.y + 1).and_then(|line| {
+            self.file_list_lines
+                .get(self.file_list_offset + line as usize)
+        }) else {
+            return;
+        };
+
+        match *line {
+            FileListLine::Header(section) => {
+                if !self.collapsed_sections.remove(&section) {
+                    self.collapsed_sections.insert(section);
+                }
+                self.clamp_selection();
+                self.update_diff_for_selected();
+            }
+            FileListLine::Row(section, index) => {
+                if self.follow {
+                    self.stop_following();
+                }
+                if (section, index) != (self.selected_section, self.selected_index) {
+                    self.selected_section = section;
+                    self.selected_index = index;
+                    self.preview_scroll = 0;
+                    self.update_diff_for_selected();
+                }
+            }
+            FileListLine::Blank => {}
         }
     }
 
+    /// Resize the list/preview split so the divider sits at a screen position
+    fn drag_divider_to(&mut self, position: Position) {
+        if self.pane_layout.area.is_empty() {
+            return;
+        }
+        let percent = self.pane_layout.list_percent_at(position);
+        self.split_percent = percent.clamp(MIN_SPLIT_PERCENT, 100 - MIN_SPLIT_PERCENT);
+    }
+
     /// Handle keyboard events
     fn handle_key_event(&self, key: KeyEvent) -> Action {
         // Handle Ctrl+C for quit
@@ -168,75 +955,263 @@ impl App {
 
         // Match against configured keybindings
         let kb = &self.config.keybindings;
+        let bound = |binding: &str| key_matches(binding, &key);
 
-        match key.code {
-            KeyCode::Char(c) => {
-                if c.to_string() == kb.quit {
-                    Action::Quit
-                } else if c.to_string() == kb.refresh {
-                    Action::Refresh
-                } else if c.to_string() == kb.toggle_preview {
-                    Action::TogglePreview
-                } else if c.to_string() == kb.help {
-                    Action::ToggleHelp
-                } else if c.to_string() == kb.stage {
-                    Action::StageUnstage
-                } else if c == 'j' {
-                    Action::MoveDown
-                } else if c == 'k' {
-                    Action::MoveUp
-                } else if c == '\t' {
-                    Action::SwitchSection
-                } else {
-                    Action::None
-                }
-            }
-            KeyCode::Up => {
-                if kb.up == "up" {
-                    Action::MoveUp
+        // The command console captures navigation while it is open
+        if self.show_console {
+            return match key.code {
+                KeyCode::Esc => Action::ToggleConsole,
+                _ if bound(&kb.console) => Action::ToggleConsole,
+                _ if bound(&kb.quit) => Action::Quit,
+                KeyCode::Up | KeyCode::Char('k') => Action::MoveUp,
+                KeyCode::Down | KeyCode::Char('j') => Action::MoveDown,
+                KeyCode::PageUp => Action::PageUp,
+                KeyCode::PageDown => Action::PageDown,
+                _ => Action::None,
+            };
+        }
+
+        // The timeline captures navigation while it is open
+        if self.show_timeline {
+            return match key.code {
+                KeyCode::Esc => Action::ToggleTimeline,
+                _ if bound(&kb.timeline) => Action::ToggleTimeline,
+                _ if bound(&kb.quit) => Action::Quit,
+                KeyCode::Up | KeyCode::Char('k') => Action::MoveUp,
+                KeyCode::Down | KeyCode::Char('j') => Action::MoveDown,
+                KeyCode::PageUp => Action::PageUp,
+                KeyCode::PageDown => Action::PageDown,
+                _ => Action::None,
+            };
+        }
+
+        // The snapshot browser captures navigation while it is open
+        if let Some(browser) = &self.snapshot_browser {
+            if browser.confirm.is_some() {
+                return if bound(&kb.confirm) {
+                    Action::ConfirmSnapshotPrompt
                 } else {
-                    Action::None
-                }
+                    Action::CancelSnapshotPrompt
+                };
             }
-            KeyCode::Down => {
-                if kb.down == "down" {
-                    Action::MoveDown
-                } else {
-                    Action::None
+            let in_files = browser.file_selected.is_some();
+            return match key.code {
+                KeyCode::Esc | KeyCode::Left | KeyCode::Char('h') if in_files => {
+                    Action::CloseSnapshotFiles
                 }
-            }
-            KeyCode::PageUp => {
-                if kb.page_up == "pageup" {
-                    Action::PageUp
-                } else {
-                    Action::None
+                KeyCode::Esc => Action::ToggleSnapshots,
+                _ if bound(&kb.snapshots) => Action::ToggleSnapshots,
+                _ if bound(&kb.quit) => Action::Quit,
+                KeyCode::Up | KeyCode::Char('k') => Action::MoveUp,
+                KeyCode::Down | KeyCode::Char('j') => Action::MoveDown,
+                KeyCode::PageUp => Action::PageUp,
+                KeyCode::PageDown => Action::PageDown,
+                KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => Action::OpenSnapshotFiles,
+                _ if in_files && bound(&kb.restore_snapshot) => Action::RestoreSnapshotFile,
+                _ if !in_files && bound(&kb.prune_snapshots) => Action::PruneSnapshots,
+                _ => Action::None,
+            };
+        }
+
+        // A pending discard takes the next key as the answer
+        if self.pending_discard.is_some() {
+            return if bound(&kb.confirm) {
+                Action::ConfirmDiscard
+            } else {
+                Action::CancelDiscard
+            };
+        }
+
+        // The filter prompt captures typing while it is open
+        if self.filter_input {
+            return match key.code {
+                KeyCode::Esc => Action::ClearFilter,
+                KeyCode::Enter => Action::CloseFilter,
+                KeyCode::Backspace => Action::FilterBackspace,
+                KeyCode::Up => Action::MoveUp,
+                KeyCode::Down => Action::MoveDown,
+                KeyCode::PageUp => Action::PageUp,
+                KeyCode::PageDown => Action::PageDown,
+                KeyCode::Char(c) => Action::FilterChar(c),
+                _ => Action::None,
+            };
+        }
+
+        // The search prompt captures typing while it is open
+        if self.search_input {
+            return match key.code {
+                KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::ALT) => {
+                    Action::ToggleSearchRegex
                 }
-            }
-            KeyCode::PageDown => {
-                if kb.page_down == "pagedown" {
-                    Action::PageDown
-                } else {
-                    Action::None
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::ALT) => {
+                    Action::ToggleSearchCase
                 }
+                KeyCode::Esc => Action::CancelSearch,
+                KeyCode::Enter => Action::CloseSearch,
+                KeyCode::Backspace => Action::SearchBackspace,
+                KeyCode::Char(c) => Action::SearchChar(c),
+                _ => Action::None,
+            };
+        }
+
+        // The focused preview scrolls and searches; other keys work as usual
+        if self.focus == Focus::Preview {
+            let overlay = self.show_help || self.show_notification_history;
+            let action = if bound(&kb.filter) {
+                Action::OpenSearch
+            } else if bound("n") {
+                Action::NextMatch
+            } else if bound("N") {
+                Action::PreviousMatch
+            } else if bound(&kb.up) || bound("k") {
+                Action::ScrollPreviewUp
+            } else if bound(&kb.down) || bound("j") {
+                Action::ScrollPreviewDown
+            } else if bound(&kb.page_up) {
+                Action::ScrollPreviewPageUp
+            } else if bound(&kb.page_down) {
+                Action::ScrollPreviewPageDown
+            } else if bound(&kb.scroll_half_page_up) {
+                Action::ScrollPreviewHalfPageUp
+            } else if bound(&kb.scroll_half_page_down) {
+                Action::ScrollPreviewHalfPageDown
+            } else if bound(&kb.scroll_top) || bound("home") {
+                Action::ScrollPreviewTop
+            } else if bound(&kb.scroll_bottom) || bound("end") {
+                Action::ScrollPreviewBottom
+            } else if bound(&kb.next_hunk) {
+                Action::NextHunk
+            } else if bound(&kb.previous_hunk) {
+                Action::PreviousHunk
+            } else if bound(&kb.focus_files) || bound("h") {
+                Action::FocusFiles
+            } else if bound("esc") && !overlay && self.diff_search.is_active() {
+                Action::CancelSearch
+            } else if bound("esc") && !overlay {
+                Action::FocusFiles
+            } else {
+                Action::None
+            };
+            if action != Action::None {
+                return action;
             }
-            KeyCode::Tab => Action::SwitchSection,
-            KeyCode::Esc => {
-                if self.show_help {
-                    Action::ToggleHelp
-                } else {
-                    Action::Quit
-                }
+        } else if bound(&kb.focus_preview) {
+            return Action::FocusPreview;
+        }
+
+        if bound(&kb.quit) {
+            Action::Quit
+        } else if bound(&kb.refresh) {
+            Action::Refresh
+        } else if bound(&kb.toggle_preview) {
+            Action::TogglePreview
+        } else if bound(&kb.help) {
+            Action::ToggleHelp
+        } else if bound(&kb.console) {
+            Action::ToggleConsole
+        } else if bound(&kb.timeline) {
+            Action::ToggleTimeline
+        } else if bound(&kb.snapshots) {
+            Action::ToggleSnapshots
+        } else if bound(&kb.notifications) {
+            Action::ToggleNotificationHistory
+        } else if bound(&kb.stage) {
+            Action::StageUnstage
+        } else if bound(&kb.push) {
+            Action::Push
+        } else if bound(&kb.pull) {
+            Action::Pull
+        } else if bound(&kb.untracked) {
+            Action::CycleUntrackedMode
+        } else if bound(&kb.pause) {
+            Action::TogglePause
+        } else if bound(&kb.tree) {
+            Action::ToggleFileView
+        } else if bound(&kb.zoom) {
+            Action::ToggleZoom
+        } else if bound(&kb.mark) {
+            Action::ToggleMark
+        } else if bound(&kb.mark_range) {
+            Action::MarkRange
+        } else if bound(&kb.mark_all) {
+            Action::MarkAll
+        } else if bound(&kb.stage_all) {
+            Action::StageAll
+        } else if bound(&kb.unstage_all) {
+            Action::UnstageAll
+        } else if bound(&kb.discard) {
+            Action::Discard
+        } else if bound(&kb.sort) {
+            Action::CycleSort
+        } else if bound(&kb.follow) {
+            Action::ToggleFollow
+        } else if bound(&kb.filter) {
+            Action::OpenFilter
+        } else if bound(&kb.filter_status) {
+            Action::CycleStatusFilter
+        } else if bound(&kb.filter_extension) {
+            Action::CycleExtensionFilter
+        } else if bound(&kb.up) || bound("k") {
+            Action::MoveUp
+        } else if bound(&kb.down) || bound("j") {
+            Action::MoveDown
+        } else if bound(&kb.page_up) {
+            Action::PageUp
+        } else if bound(&kb.page_down) {
+            Action::PageDown
+        } else if bound("l") {
+            Action::FocusPreview
+        } else if bound("tab") {
+            Action::SwitchSection
+        } else if bound("enter") {
+            Action::ToggleExpand
+        } else if bound("esc") {
+            if self.show_help {
+                Action::ToggleHelp
+            } else if self.show_notification_history {
+                Action::ToggleNotificationHistory
+            } else if self.remote_panel.as_ref().is_some_and(|p| !p.is_running()) {
+                Action::DismissRemotePanel
+            } else if !self.notifications.active().is_empty() {
+                Action::DismissToasts
+            } else if !self.marks.is_empty() {
+                Action::ClearMarks
+            } else if self.filter.is_active() {
+                Action::ClearFilter
+            } else {
+                Action::Quit
             }
-            _ => Action::None,
+        } else {
+            Action::None
         }
     }
 
     /// Handle an action
-    async fn handle_action(&mut self, action: Action, git_service: &GitService) -> Result<()> {
+    fn handle_action(&mut self, action: Action) -> Result<()> {
+        let overlay = self.show_console || self.show_timeline || self.snapshot_browser.is_some();
+        if self.follow && action.is_navigation() && !overlay {
+            self.stop_following();
+        }
+
         match action {
             Action::Quit => {
                 self.should_quit = true;
             }
+            Action::MoveUp | Action::MoveDown | Action::PageUp | Action::PageDown
+                if self.show_console =>
+            {
+                self.move_console_selection(action);
+            }
+            Action::MoveUp | Action::MoveDown | Action::PageUp | Action::PageDown
+                if self.show_timeline =>
+            {
+                self.move_timeline_selection(action);
+            }
+            Action::MoveUp | Action::MoveDown | Action::PageUp | Action::PageDown
+                if self.snapshot_browser.is_some() =>
+            {
+                self.move_snapshot_selection(action);
+            }
             Action::MoveUp => {
                 if self.selected_index > 0 {
                     self.selected_index -= 1;
@@ -265,133 +1240,1981 @@ impl App {
             }
             Action::TogglePreview => {
                 self.show_preview = !self.show_preview;
+                if !self.show_preview {
+                    self.focus = Focus::Files;
+                }
             }
             Action::ToggleHelp => {
                 self.show_help = !self.show_help;
             }
+            Action::ToggleConsole => {
+                self.show_console = !self.show_console;
+                self.console_selected = 0;
+            }
+            Action::ToggleSnapshots => {
+                if self.snapshot_browser.take().is_none() {
+                    self.snapshot_browser = Some(SnapshotBrowser::default());
+                    self.load_snapshots();
+                }
+            }
+            Action::OpenSnapshotFiles => {
+                if let Some(browser) = &mut self.snapshot_browser {
+                    let has_files = browser
+                        .changes
+                        .as_ref()
+                        .is_some_and(|changes| !changes.files.is_empty());
+                    if browser.file_selected.is_none() && has_files {
+                        browser.file_selected = Some(0);
+                        self.load_snapshot_diff();
+                    }
+                }
+            }
+            Action::CloseSnapshotFiles => {
+                if let Some(browser) = &mut self.snapshot_browser {
+                    browser.file_selected = None;
+                    browser.diff = None;
+                }
+            }
+            Action::RestoreSnapshotFile => {
+                if let Some(browser) = self
+                    .snapshot_browser
+                    .as_mut()
+                    .filter(|b| b.file().is_some())
+                {
+                    browser.confirm = Some(SnapshotAction::Restore);
+                }
+            }
+            Action::PruneSnapshots => {
+                if let Some(browser) = &mut self.snapshot_browser {
+                    let count = browser.snapshots.as_ref().map_or(0, Vec::len);
+                    if browser.selected + 1 < count {
+                        browser.confirm = Some(SnapshotAction::Prune);
+                    } else {
+                        let message = "No snapshots older than the selected one";
+                        self.notifications.push(Toast::new(Severity::Info, message));
+                    }
+                }
+            }
+            Action::ConfirmSnapshotPrompt => {
+                self.run_snapshot_action();
+            }
+            Action::CancelSnapshotPrompt => {
+                if let Some(browser) = &mut self.snapshot_browser {
+                    browser.confirm = None;
+                }
+            }
+            Action::ToggleTimeline => {
+                self.show_timeline = !self.show_timeline;
+                self.timeline_selected = 0;
+                if self.show_timeline {
+                    self.update_timeline_diff();
+                }
+            }
+            Action::ToggleNotificationHistory => {
+                self.show_notification_history = !self.show_notification_history;
+            }
+            Action::DismissToasts => {
+                self.notifications.dismiss_all();
+            }
             Action::Refresh => {
-                self.refresh_git_status(git_service).await?;
+                self.request_status_refresh(ChangeScope::Full);
             }
             Action::SwitchSection => {
                 self.switch_section();
             }
             Action::StageUnstage => {
-                self.stage_unstage_selected(git_service).await?;
+                self.stage_unstage_selected();
             }
-            Action::None => {}
-        }
-        Ok(())
-    }
-
-    /// Refresh the git status
-    async fn refresh_git_status(&mut self, git_service: &GitService) -> Result<()> {
-        let status = git_service.get_status().await?;
-        self.git_status = Some(status);
-
-        // Ensure selected index is valid
-        let max_index = self.get_current_section_len().saturating_sub(1);
-        if self.selected_index > max_index {
-            self.selected_index = max_index;
-        }
-
-        // Update diff for selected file
-        self.update_diff_for_selected();
-
-        Ok(())
-    }
-
-    /// Update the diff content for the currently selected file
-    fn update_diff_for_selected(&mut self) {
-        if let Some(status) = &self.git_status {
-            let files = match self.selected_section {
-                Section::Staged => &status.staged_files,
-                Section::Unstaged => &status.unstaged_files,
-            };
-
-            if let Some(file) = files.get(self.selected_index) {
-                let git_service = GitService::new(self.repo_path.clone());
-                let path = file.path.clone();
-                let staged = file.staged;
-
-                if let Some(tx) = &self.event_tx {
-                    let tx = tx.clone();
-                    tokio::spawn(async move {
-                        if let Ok(diff) = git_service.get_file_diff(&path, staged).await {
-                            let _ = tx.send(Event::DiffReady(diff));
-                        }
-                    });
-                }
-            } else {
-                self.diff_content = None;
+            Action::Push => {
+                self.start_remote_operation(RemoteOperation::Push);
             }
-        }
-    }
-
-    /// Get the number of files in the current section
-    fn get_current_section_len(&self) -> usize {
-        if let Some(status) = &self.git_status {
-            match self.selected_section {
-                Section::Staged => status.staged_files.len(),
-                Section::Unstaged => status.unstaged_files.len(),
+            Action::Pull => {
+                let rebase = self.config.git.pull_rebase;
+                self.start_remote_operation(RemoteOperation::Pull { rebase });
             }
-        } else {
-            0
-        }
+            Action::DismissRemotePanel => {
+                self.remote_panel = None;
+            }
+            Action::CycleUntrackedMode => {
+                self.untracked_mode = self.untracked_mode.next();
+                let message = format!("Untracked files: {}", self.untracked_mode.as_arg());
+                self.notifications.push(Toast::new(Severity::Info, message));
+                self.request_status_refresh(ChangeScope::Full);
+            }
+            Action::ToggleExpand => {
+                self.toggle_expand_selected();
+            }
+            Action::TogglePause => {
+                self.paused = !self.paused;
+                if !self.paused {
+                    self.pending_changes = 0;
+                    if let Some(scope) = self.pending_scope.take() {
+                        self.request_status_refresh(scope);
+                    }
+                }
+            }
+            Action::ToggleFileView => {
+                self.toggle_file_view();
+            }
+            Action::ToggleZoom => {
+                self.zoomed = !self.zoomed;
+            }
+            Action::ToggleMark => {
+                self.toggle_mark_selected();
+                self.handle_action(Action::MoveDown)?;
+            }
+            Action::MarkRange => {
+                self.mark_range();
+            }
+            Action::MarkAll => {
+                let section = self.selected_section;
+                let paths = self
+                    .visible_files(section)
+                    .map(|(_, file)| file.path.clone())
+                    .collect();
+                self.toggle_marks(section, paths);
+            }
+            Action::ClearMarks => {
+                self.marks.clear();
+                self.mark_anchor = None;
+            }
+            Action::StageAll => {
+                let paths = self.batch_paths(Section::Unstaged, true);
+                self.stage_paths(Section::Unstaged, paths);
+            }
+            Action::UnstageAll => {
+                let paths = self.batch_paths(Section::Staged, true);
+                self.stage_paths(Section::Staged, paths);
+            }
+            Action::Discard => {
+                self.request_discard();
+            }
+            Action::ConfirmDiscard => {
+                if let Some(files) = self.pending_discard.take() {
+                    self.discard_files(files);
+                }
+            }
+            Action::CancelDiscard => {
+                self.pending_discard = None;
+            }
+            Action::CycleSort => {
+                self.cycle_sort();
+            }
+            Action::ToggleFollow => {
+                if self.follow {
+                    self.follow = false;
+                } else {
+                    self.follow = true;
+                    // Start from the most recently modified changed file
+                    if let Some(path) = self.latest_changed(|_| true) {
+                        self.follow_path(&path);
+                    }
+                }
+            }
+            Action::OpenFilter => {
+                self.filter_input = true;
+            }
+            Action::FilterChar(c) => {
+                self.filter.query.push(c);
+                self.after_filter_change();
+            }
+            Action::FilterBackspace => {
+                self.filter.query.pop();
+                self.after_filter_change();
+            }
+            Action::CloseFilter => {
+                self.filter_input = false;
+            }
+            Action::ClearFilter => {
+                self.filter_input = false;
+                self.filter = FileFilter::default();
+                self.after_filter_change();
+            }
+            Action::CycleStatusFilter => {
+                self.filter.cycle_status();
+                self.after_filter_change();
+            }
+            Action::CycleExtensionFilter => {
+                let extensions = self.changed_extensions();
+                self.filter.cycle_extension(&extensions);
+                self.after_filter_change();
+            }
+            Action::FocusFiles => {
+                self.focus = Focus::Files;
+            }
+            Action::FocusPreview => {
+                if self.show_preview {
+                    self.focus = Focus::Preview;
+                }
+            }
+            Action::ScrollPreviewUp => self.scroll_preview_by(-1),
+            Action::ScrollPreviewDown => self.scroll_preview_by(1),
+            Action::ScrollPreviewPageUp => self.scroll_preview_by(-self.preview_page()),
+            Action::ScrollPreviewPageDown => self.scroll_preview_by(self.preview_page()),
+            Action::ScrollPreviewHalfPageUp => self.scroll_preview_by(-self.preview_page() / 2),
+            Action::ScrollPreviewHalfPageDown => self.scroll_preview_by(self.preview_page() / 2),
+            Action::ScrollPreviewWheelUp => self.scroll_preview_by(-WHEEL_SCROLL_LINES),
+            Action::ScrollPreviewWheelDown => self.scroll_preview_by(WHEEL_SCROLL_LINES),
+            Action::ScrollPreviewTop => self.set_preview_scroll(0),
+            Action::ScrollPreviewBottom => self.set_preview_scroll(usize::MAX),
+            Action::NextHunk => {
+                let current = self.preview_scroll as usize;
+                if let Some(line) = self.hunk_lines().into_iter().find(|line| *line > current) {
+                    self.set_preview_scroll(line);
+                }
+            }
+            Action::PreviousHunk => {
+                let current = self.preview_scroll as usize;
+                if let Some(line) = self.hunk_lines().into_iter().rfind(|line| *line < current) {
+                    self.set_preview_scroll(line);
+                }
+            }
+            Action::OpenSearch => {
+                self.search_input = true;
+                self.search_origin = self.preview_scroll as usize;
+                self.diff_search.clear();
+            }
+            Action::SearchChar(c) => {
+                self.diff_search.query.push(c);
+                self.refresh_search(true);
+            }
+            Action::SearchBackspace => {
+                self.diff_search.query.pop();
+                self.refresh_search(true);
+            }
+            Action::CloseSearch => {
+                self.search_input = false;
+            }
+            Action::CancelSearch => {
+                self.search_input = false;
+                self.diff_search.clear();
+            }
+            Action::ToggleSearchRegex => {
+                self.diff_search.regex = !self.diff_search.regex;
+                self.refresh_search(true);
+            }
+            Action::ToggleSearchCase => {
+                self.diff_search.case_sensitive = !self.diff_search.case_sensitive;
+                self.refresh_search(true);
+            }
+            Action::NextMatch => {
+                if let Some(found) = self.diff_search.next() {
+                    self.scroll_to_match(found);
+                }
+            }
+            Action::PreviousMatch => {
+                if let Some(found) = self.diff_search.previous() {
+                    self.scroll_to_match(found);
+                }
+            }
+            Action::None => {}
+        }
+        Ok(())
     }
 
-    /// Switch between staged and unstaged sections
-    fn switch_section(&mut self) {
-        if let Some(status) = &self.git_status {
-            let (new_section, new_section_len) = match self.selected_section {
-                Section::Staged => (Section::Unstaged, status.unstaged_files.len()),
-                Section::Unstaged => (Section::Staged, status.staged_files.len()),
+    /// Start a status refresh in the background
+    ///
+    /// Any refresh still in flight is cancelled (killing its git processes) and
+    /// its scope is folded into the new request. Path-limited refreshes fall
+    /// back to a full one when there is no status yet, when too many paths
+    /// changed, or when a changed path is part of a rename.
+    fn request_status_refresh(&mut self, scope: ChangeScope) {
+        let Some(tx) = self.event_tx.clone() else {
+            return;
+        };
+
+        let mut scope = scope;
+        if let Some(previous) = self.status_request.take() {
+            previous.task.abort();
+            scope = scope.merge(previous.scope);
+        }
+
+        // Outside `all` mode a changed file may belong to a collapsed directory,
+        // which only a full status can report correctly
+        if let ChangeScope::Paths(paths) = &scope {
+            let incremental = self.untracked_mode == UntrackedMode::All
+                && self.git_status.as_ref().is_some_and(|status| {
+                    paths.len() <= MAX_INCREMENTAL_PATHS && !status.touches_rename(paths)
+                });
+            if !incremental {
+                scope = ChangeScope::Full;
+            }
+        }
+
+        self.status_generation += 1;
+        let generation = self.status_generation;
+        let git_service = self.git_service.clone();
+        let request_scope = scope.clone();
+        let options = self.status_options();
+
+        let task = tokio::spawn(async move {
+            let result = match request_scope {
+                ChangeScope::Full => git_service
+                    .get_status(&options)
+                    .await
+                    .map(StatusUpdate::Full),
+                ChangeScope::Paths(paths) => git_service
+                    .get_status_for_paths(&options, &paths)
+                    .await
+                    .map(|(staged, unstaged)| StatusUpdate::Paths {
+                        paths,
+                        staged,
+                        unstaged,
+                    }),
+            };
+
+            let _ = tx.send(Event::StatusReady {
+                generation,
+                result: result.map_err(|report| Toast::from_report(&report)),
+            });
+        });
+
+        self.status_request = Some(StatusRequest {
+            generation,
+            scope,
+            task,
+        });
+    }
+
+    /// Apply a finished status refresh
+    fn apply_status_update(&mut self, update: StatusUpdate) {
+        let selected = self.selected_key();
+        // For a path update only the touched entries are compared, before and after
+        let (previous, touched) = match update {
+            StatusUpdate::Full(status) => (self.git_status.replace(status), None),
+            StatusUpdate::Paths {
+                paths,
+                staged,
+                unstaged,
+            } => match &mut self.git_status {
+                Some(status) => {
+                    let touched = GitStatus {
+                        staged_files: staged.clone(),
+                        unstaged_files: unstaged.clone(),
+                        ..Default::default()
+                    };
+                    let replaced = status.apply_path_update(&paths, staged, unstaged);
+                    (Some(replaced), Some(touched))
+                }
+                None => (None, None),
+            },
+        };
+        let mut recorded = None;
+        if let (Some(previous), Some(current)) =
+            (&previous, touched.as_ref().or(self.git_status.as_ref()))
+        {
+            let now = Instant::now();
+            self.highlights.record_status(previous, current, now);
+            recorded = self.timeline.record(previous, current, now);
+        }
+        if let Some((id, files)) = recorded {
+            self.capture_timeline_blobs(id, files);
+        }
+
+        self.enforce_untracked_cap();
+        self.sort_files();
+        self.reselect(selected);
+        if let Some(changes) = self.follow_changes.take().filter(|_| self.follow) {
+            // A changed directory covers the files below it, and a collapsed
+            // untracked directory the changed files inside it
+            let touched = |path: &str| {
+                changes.iter().any(|change| {
+                    path_is_within(path, change)
+                        || (path.ends_with('/') && change.starts_with(path))
+                })
             };
+            if let Some(path) = self.latest_changed(touched) {
+                self.follow_path(&path);
+            }
+        }
+        self.after_status_update();
+    }
+
+    /// Options for the next status refresh
+    fn status_options(&self) -> StatusOptions {
+        StatusOptions {
+            untracked: self.untracked_mode,
+            expanded_dirs: self.expanded_dirs.iter().cloned().collect(),
+        }
+    }
+
+    /// Keep the listed untracked files under `git.maxUntrackedFiles`
+    ///
+    /// The current list is truncated right away. In `all` mode a full
+    /// refresh in `normal` mode collapses the offending directories; in
+    /// `normal` mode the expanded directories are collapsed again.
+    fn enforce_untracked_cap(&mut self) {
+        let expanded = match self.untracked_mode {
+            UntrackedMode::All => false,
+            UntrackedMode::Normal if !self.expanded_dirs.is_empty() => true,
+            _ => return,
+        };
+        let max = self.config.git.max_untracked_files;
+        let Some(status) = &mut self.git_status else {
+            return;
+        };
+        let count = status.untracked_count();
+        if count <= max {
+            return;
+        }
+
+        status.cap_untracked(max);
+        let toast = if expanded {
+            self.expanded_dirs.clear();
+            Toast::new(
+                Severity::Warning,
+                format!(
+                    "{} untracked files (limit {}); collapsing expanded directories",
+                    count, max
+                ),
+            )
+            .with_detail("Raise git.maxUntrackedFiles to list them file by file")
+        } else {
+            self.untracked_mode = UntrackedMode::Normal;
+            Toast::new(
+                Severity::Warning,
+                format!(
+                    "{} untracked files (limit {}); collapsing untracked directories",
+                    count, max
+                ),
+            )
+            .with_detail("Press Enter on a directory to expand it, or raise git.maxUntrackedFiles")
+        };
+        self.notifications.push(toast);
+        self.request_status_refresh(ChangeScope::Full);
+    }
+
+    /// Switch to the next sort mode, keeping the selection, and save it in the config
+    fn cycle_sort(&mut self) {
+        let selected = self.selected_key();
+        self.sort_mode = self.sort_mode.next();
+        self.sort_files();
+        self.reselect(selected);
+        self.update_diff_for_selected();
+
+        self.config.display.sort_by = self.sort_mode;
+        self.save_config();
+    }
+
+    /// Order both sections by the current sort mode
+    fn sort_files(&mut self) {
+        let Some(status) = &mut self.git_status else {
+            return;
+        };
+        for files in [&mut status.staged_files, &mut status.unstaged_files] {
+            sort::sort_files(files, self.sort_mode);
+        }
+    }
+
+    /// Section and path of the selected row (directories end with `/`)
+    fn selected_key(&self) -> Option<(Section, String)> {
+        let row = self.selected_row()?;
+        Some((
+            self.selected_section,
+            self.row_key(self.selected_section, &row),
+        ))
+    }
+
+    /// Select a row by section and path again after the rows moved
+    fn reselect(&mut self, key: Option<(Section, String)>) {
+        let Some((section, path)) = key else {
+            return;
+        };
+        let position = self
+            .section_rows(section)
+            .iter()
+            .position(|row| self.row_key(section, row) == path);
+        if let Some(index) = position {
+            self.selected_section = section;
+            self.selected_index = index;
+        }
+    }
+
+    /// Path of the changed file modified most recently on disk, among those `include` accepts
+    ///
+    /// Uses the modification times recorded with the status.
+    fn latest_changed(&self, include: impl Fn(&str) -> bool) -> Option<String> {
+        [Section::Unstaged, Section::Staged]
+            .into_iter()
+            .flat_map(|section| self.section_files(section))
+            .filter(|file| include(&file.path))
+            .max_by_key(|file| file.modified)
+            .map(|file| file.path.clone())
+    }
+
+    /// Select the row showing a changed path (unstaged changes first)
+    ///
+    /// A path hidden in a collapsed directory selects the deepest visible
+    /// directory row containing it.
+    fn follow_path(&mut self, path: &str) {
+        for section in [Section::Unstaged, Section::Staged] {
+            let rows = self.section_rows(section);
+            let keys: Vec<String> = rows.iter().map(|row| self.row_key(section, row)).collect();
+            let position = keys.iter().position(|key| key == path).or_else(|| {
+                keys.iter()
+                    .enumerate()
+                    .filter(|(_, key)| key.ends_with('/') && path.starts_with(key.as_str()))
+                    .max_by_key(|(_, key)| key.len())
+                    .map(|(index, _)| index)
+            });
+            if let Some(index) = position {
+                if (section, index) != (self.selected_section, self.selected_index) {
+                    self.selected_section = section;
+                    self.selected_index = index;
+                    self.preview_scroll = 0;
+                    self.update_diff_for_selected();
+                }
+                return;
+            }
+        }
+    }
+
+    /// Turn follow mode off because the user took over
+    fn stop_following(&mut self) {
+        self.follow = false;
+        self.follow_changes = None;
+        self.notifications
+            .push(Toast::new(Severity::Info, "Follow mode off"));
+    }
+
+    /// Write the configuration back to its file
+    fn save_config(&mut self) {
+        let Some(path) = self.config_path.clone() else {
+            return;
+        };
+        let result = ConfigLoader::new(Some(path)).and_then(|loader| loader.save(&self.config));
+        if let Err(report) = result {
+            self.notifications.push(
+                Toast::new(Severity::Warning, "Failed to save the configuration")
+                    .with_detail(report.to_string()),
+            );
+        }
+    }
+
+    /// Switch between the flat list and the tree, keeping the selected file selected
+    fn toggle_file_view(&mut self) {
+        let selected = self.selected_row().and_then(|row| match row {
+            FileRow::File { index, .. } => Some(index),
+            FileRow::Dir(_) => None,
+        });
+
+        self.file_view = match self.file_view {
+            FileView::Flat => FileView::Tree,
+            FileView::Tree => FileView::Flat,
+        };
+
+        // The file may sit in a collapsed directory; then select its topmost collapsed ancestor
+        self.selected_index = match selected {
+            Some(selected) => {
+                let path = &self.current_files()[selected].path;
+                self.section_rows(self.selected_section)
+                    .iter()
+                    .position(|row| match row {
+                        FileRow::File { index, .. } => *index == selected,
+                        FileRow::Dir(dir) => dir.collapsed && path.starts_with(&dir.path),
+                    })
+                    .unwrap_or(0)
+            }
+            None => 0,
+        };
+        self.update_diff_for_selected();
+    }
+
+    /// Collapse or expand the selected directory node in the tree view,
+    /// or expand/collapse a collapsed untracked directory
+    fn toggle_expand_selected(&mut self) {
+        if let Some(FileRow::Dir(dir)) = self.selected_row() {
+            if !self.collapsed_dirs.remove(&dir.path) {
+                self.collapsed_dirs.insert(dir.path);
+            }
+            return;
+        }
+
+        if self.untracked_mode != UntrackedMode::Normal {
+            return;
+        }
+        let Some(file) = self.get_selected_file() else {
+            return;
+        };
+
+        if file.is_untracked_dir() {
+            let dir = file.path.clone();
+            self.expanded_dirs.insert(dir);
+        } else {
+            let path = file.path.clone();
+            let before = self.expanded_dirs.len();
+            self.expanded_dirs
+                .retain(|dir| !path.starts_with(dir.as_str()));
+            if self.expanded_dirs.len() == before {
+                return;
+            }
+        }
+
+        self.request_status_refresh(ChangeScope::Full);
+    }
+
+    /// Keep the selection valid and the preview current after the status changed
+    fn after_status_update(&mut self) {
+        self.prune_marks();
+        self.clamp_selection();
+
+        // Update diff for selected file
+        self.update_diff_for_selected();
+    }
 
-            // Only switch if the new section has files
-            if new_section_len > 0 {
-                self.selected_section = new_section;
+    /// Keep the selection on a visible row after the filter changed
+    fn after_filter_change(&mut self) {
+        self.clamp_selection();
+        self.preview_scroll = 0;
+        self.update_diff_for_selected();
+    }
+
+    /// Ensure the selected index is valid, moving to the other section if this one is empty
+    fn clamp_selection(&mut self) {
+        let len = self.get_current_section_len();
+        if len == 0 {
+            let other = match self.selected_section {
+                Section::Staged => Section::Unstaged,
+                Section::Unstaged => Section::Staged,
+            };
+            if !self.section_rows(other).is_empty() {
+                self.selected_section = other;
                 self.selected_index = 0;
-                self.preview_scroll = 0;
-                self.update_diff_for_selected();
+                return;
             }
         }
+        self.selected_index = self.selected_index.min(len.saturating_sub(1));
+    }
+
+    /// Extensions of all changed files, sorted (for the extension quick filter)
+    fn changed_extensions(&self) -> Vec<String> {
+        let extensions: BTreeSet<String> = [Section::Staged, Section::Unstaged]
+            .into_iter()
+            .flat_map(|section| self.section_files(section))
+            .filter_map(|file| filter::extension_of(&file.path))
+            .map(str::to_string)
+            .collect();
+        extensions.into_iter().collect()
     }
 
-    /// Stage or unstage the currently selected file
-    async fn stage_unstage_selected(&mut self, git_service: &GitService) -> Result<()> {
-        if let Some(status) = &self.git_status {
-            let (file, is_staged) = match self.selected_section {
-                Section::Staged => (status.staged_files.get(self.selected_index), true),
-                Section::Unstaged => (status.unstaged_files.get(self.selected_index), false),
+    /// Update the diff content for the currently selected file
+    ///
+    /// Each request gets a new generation; the previous request is cancelled
+    /// and any result it still delivers is dropped.
+    fn update_diff_for_selected(&mut self) {
+        if let Some(task) = self.diff_task.take() {
+            task.abort();
+        }
+        self.diff_generation += 1;
+
+        let Some((path, staged)) = self.get_selected_file().map(|f| (f.path.clone(), f.staged))
+        else {
+            self.diff_content = None;
+            self.diff_key = None;
+            return;
+        };
+
+        if let Some(tx) = &self.event_tx {
+            let tx = tx.clone();
+            let git_service = self.git_service.clone();
+            let generation = self.diff_generation;
+
+            self.diff_task = Some(tokio::spawn(async move {
+                let result = git_service
+                    .get_file_diff(&path, staged)
+                    .await
+                    .map_err(|report| Toast::from_report(&report));
+                let _ = tx.send(Event::DiffReady {
+                    generation,
+                    path,
+                    staged,
+                    result,
+                });
+            }));
+        }
+    }
+
+    /// Get the diff for the selected file, if it has been loaded
+    ///
+    /// Returns None while the diff for a newly selected file is still loading,
+    /// so a previous file's diff is never shown under the selected file's name.
+    pub fn selected_diff(&self) -> Option<&str> {
+        let file = self.get_selected_file()?;
+        let (path, staged) = self.diff_key.as_ref()?;
+        if *path == file.path && *staged == file.staged {
+            self.diff_content.as_deref()
+        } else {
+            None
+        }
+    }
+
+    /// Number of lines in the preview (0 while loading)
+    fn preview_line_count(&self) -> usize {
+        self.selected_diff().map_or(0, |diff| diff.lines().count())
+    }
+
+    /// Recompute the search matches for the shown diff, optionally scrolling to the current one
+    fn refresh_search(&mut self, scroll: bool) {
+        let diff = match (&self.diff_content, self.selected_diff().is_some()) {
+            (Some(diff), true) => diff.as_str(),
+            _ => "",
+        };
+        self.diff_search.update(diff, self.search_origin);
+        if scroll {
+            if let Some(found) = self.diff_search.current_match() {
+                self.scroll_to_match(found);
+            }
+        }
+    }
+
+    /// Scroll the preview so a match is visible with a little context above it
+    fn scroll_to_match(&mut self, found: SearchMatch) {
+        self.set_preview_scroll(found.line.saturating_sub(SEARCH_CONTEXT_LINES));
+    }
+
+    /// Number of diff lines visible in the preview (from the last draw)
+    fn preview_page(&self) -> isize {
+        // Top and bottom border
+        (self.preview_area.height.saturating_sub(2) as isize).max(1)
+    }
+
+    /// Scroll the preview, keeping the last line at the bottom at most
+    pub fn set_preview_scroll(&mut self, line: usize) {
+        let page = self.preview_page() as usize;
+        let max = self.preview_line_count().saturating_sub(page);
+        self.preview_scroll = line.min(max).min(u16::MAX as usize) as u16;
+    }
+
+    /// Scroll the preview by a number of lines (negative scrolls up)
+    pub fn scroll_preview_by(&mut self, lines: isize) {
+        let line = (self.preview_scroll as usize).saturating_add_signed(lines);
+        self.set_preview_scroll(line);
+    }
+
+    /// Hunks of the shown diff that changed in its last refresh, and how far their highlight faded
+    pub fn changed_hunks(&self, now: Instant) -> Option<(&[Range<usize>], f32)> {
+        self.selected_diff()?;
+        self.highlights.changed_hunks(self.diff_key.as_ref()?, now)
+    }
+
+    /// Lines of the shown diff where hunks start
+    fn hunk_lines(&self) -> Vec<usize> {
+        self.selected_diff()
+            .map(|diff| {
+                diff.lines()
+                    .enumerate()
+                    .filter(|(_, line)| line.starts_with("@@"))
+                    .map(|(index, _)| index)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the number of rows in the current section
+    fn get_current_section_len(&self) -> usize {
+        self.section_rows(self.selected_section).len()
+    }
+
+    /// Files of a section (empty until the first status arrives)
+    pub fn section_files(&self, section: Section) -> &[FileStatus] {
+        match (&self.git_status, section) {
+            (Some(status), Section::Staged) => &status.staged_files,
+            (Some(status), Section::Unstaged) => &status.unstaged_files,
+            (None, _) => &[],
+        }
+    }
+
+    /// Files of the current section
+    fn current_files(&self) -> &[FileStatus] {
+        self.section_files(self.selected_section)
+    }
+
+    /// Files of a section that pass the filter, with their index in the section
+    pub fn visible_files(&self, section: Section) -> impl Iterator<Item = (usize, &FileStatus)> {
+        self.section_files(section)
+            .iter()
+            .enumerate()
+            .filter(|(_, file)| self.filter.matches(file).is_some())
+    }
+
+    /// Visible rows of a section in the current view
+    pub fn section_rows(&self, section: Section) -> Vec<FileRow> {
+        if self.collapsed_sections.contains(&section) {
+            return Vec::new();
+        }
+        file_tree::build_rows(
+            self.visible_files(section),
+            self.file_view,
+            &self.collapsed_dirs,
+        )
+    }
+
+    /// Get the selected row (a file, or a directory in the tree view)
+    pub fn selected_row(&self) -> Option<FileRow> {
+        let mut rows = self.section_rows(self.selected_section);
+        if self.selected_index < rows.len() {
+            Some(rows.swap_remove(self.selected_index))
+        } else {
+            None
+        }
+    }
+
+    /// Switch between staged and unstaged sections
+    fn switch_section(&mut self) {
+        let new_section = match self.selected_section {
+            Section::Staged => Section::Unstaged,
+            Section::Unstaged => Section::Staged,
+        };
+
+        // Only switch if the new section has visible files
+        if !self.section_rows(new_section).is_empty() {
+            self.selected_section = new_section;
+            self.selected_index = 0;
+            self.preview_scroll = 0;
+            self.update_diff_for_selected();
+        }
+    }
+
+    /// Stage or unstage the marked files, or the selected row if none are marked
+    ///
+    /// A directory node stages or unstages everything under it in the section.
+    fn stage_unstage_selected(&mut self) {
+        let section = self.selected_section;
+        let paths = self.batch_paths(section, false);
+        self.stage_paths(section, paths);
+    }
+
+    /// Stage (unstaged section) or unstage (staged section) paths in one git invocation
+    fn stage_paths(&mut self, section: Section, paths: Vec<String>) {
+        let Some(tx) = self.event_tx.clone() else {
+            return;
+        };
+        if paths.is_empty() {
+            return;
+        }
+
+        let git_service = self.git_service.clone();
+        tokio::spawn(async move {
+            let result = match section {
+                Section::Staged => git_service.unstage_files(&paths).await,
+                Section::Unstaged => git_service.stage_files(&paths).await,
             };
+            let _ = tx.send(Event::StageFinished(
+                result.map_err(|report| Toast::from_report(&report)),
+            ));
+        });
+    }
 
-            if let Some(file) = file {
-                let path = file.path.clone();
+    /// Paths a batch action applies to in a section
+    ///
+    /// The visible marked files if there are any; otherwise every visible
+    /// file (`all`) or the selected row (if it is in this section).
+    fn batch_paths(&self, section: Section, all: bool) -> Vec<String> {
+        let marked: Vec<String> = self
+            .marked_files(section)
+            .map(|file| file.path.clone())
+            .collect();
+        if !marked.is_empty() {
+            marked
+        } else if all {
+            self.visible_files(section)
+                .map(|(_, file)| file.path.clone())
+                .collect()
+        } else if section == self.selected_section {
+            self.selected_pathspecs()
+        } else {
+            Vec::new()
+        }
+    }
 
-                // Perform the operation
-                if is_staged {
-                    git_service.unstage_file(&path).await?;
-                } else {
-                    git_service.stage_file(&path).await?;
+    /// Ask for confirmation before discarding the marked or selected unstaged files
+    fn request_discard(&mut self) {
+        if self.selected_section != Section::Unstaged {
+            self.notifications.push(Toast::new(
+                Severity::Warning,
+                "Only unstaged changes can be discarded",
+            ));
+            return;
+        }
+        let mut files: Vec<FileStatus> = self.marked_files(Section::Unstaged).cloned().collect();
+        if files.is_empty() {
+            if let Some(row) = self.selected_row() {
+                let paths = self.row_paths(Section::Unstaged, &row);
+                files = self
+                    .section_files(Section::Unstaged)
+                    .iter()
+                    .filter(|file| paths.contains(&file.path))
+                    .cloned()
+                    .collect();
+            }
+        }
+        if !files.is_empty() {
+            self.pending_discard = Some(files);
+        }
+    }
+
+    /// Discard the changes to files in the background (at most one git command per kind)
+    fn discard_files(&mut self, files: Vec<FileStatus>) {
+        let Some(tx) = self.event_tx.clone() else {
+            return;
+        };
+        let (untracked, tracked): (Vec<FileStatus>, Vec<FileStatus>) = files
+            .into_iter()
+            .partition(|file| file.status == FileStatusType::Untracked);
+        let tracked: Vec<String> = tracked.into_iter().map(|file| file.path).collect();
+        let untracked: Vec<String> = untracked.into_iter().map(|file| file.path).collect();
+
+        let git_service = self.git_service.clone();
+        tokio::spawn(async move {
+            let result = git_service.discard_files(&tracked, &untracked).await;
+            let _ = tx.send(Event::StageFinished(
+                result.map_err(|report| Toast::from_report(&report)),
+            ));
+        });
+    }
+
+    /// Path identifying a row for range selection (directories end with `/`)
+    fn row_key(&self, section: Section, row: &FileRow) -> String {
+        match row {
+            FileRow::File { index, .. } => self.section_files(section)[*index].path.clone(),
+            FileRow::Dir(dir) => dir.path.clone(),
+        }
+    }
+
+    /// Visible files a row stands for (a directory stands for the files under it)
+    fn row_paths(&self, section: Section, row: &FileRow) -> Vec<String> {
+        match row {
+            FileRow::File { index, .. } => vec![self.section_files(section)[*index].path.clone()],
+            FileRow::Dir(dir) => self
+                .visible_files(section)
+                .filter(|(_, file)| file.path.starts_with(&dir.path))
+                .map(|(_, file)| file.path.clone())
+                .collect(),
+        }
+    }
+
+    /// Whether a file is marked
+    pub fn is_marked(&self, section: Section, path: &str) -> bool {
+        self.marks.contains(&(section, path.to_string()))
+    }
+
+    /// Visible marked files of a section
+    pub fn marked_files(&self, section: Section) -> impl Iterator<Item = &FileStatus> {
+        self.visible_files(section)
+            .map(|(_, file)| file)
+            .filter(move |file| self.is_marked(section, &file.path))
+    }
+
+    /// Mark the paths, or unmark them if they are all marked already
+    fn toggle_marks(&mut self, section: Section, paths: Vec<String>) {
+        if paths.iter().all(|path| self.is_marked(section, path)) {
+            for path in paths {
+                self.marks.remove(&(section, path));
+            }
+        } else {
+            self.marks
+                .extend(paths.into_iter().map(|path| (section, path)));
+        }
+    }
+
+    /// Toggle the marks of the selected row and make it the range anchor
+    fn toggle_mark_selected(&mut self) {
+        let section = self.selected_section;
+        let Some(row) = self.selected_row() else {
+            return;
+        };
+        let paths = self.row_paths(section, &row);
+        self.toggle_marks(section, paths);
+        self.mark_anchor = Some((section, self.row_key(section, &row)));
+    }
+
+    /// Mark every row between the range anchor and the selection
+    ///
+    /// Without an anchor in this section it marks the selected row.
+    fn mark_range(&mut self) {
+        let section = self.selected_section;
+        let rows = self.section_rows(section);
+        let anchor = self
+            .mark_anchor
+            .as_ref()
+            .filter(|(anchor_section, _)| *anchor_section == section)
+            .and_then(|(_, key)| {
+                rows.iter()
+                    .position(|row| self.row_key(section, row) == *key)
+            });
+        let Some(anchor) = anchor else {
+            self.toggle_mark_selected();
+            return;
+        };
+        let Some(selected) = rows.get(self.selected_index) else {
+            return;
+        };
+
+        let start = anchor.min(self.selected_index);
+        let end = anchor.max(self.selected_index);
+        let paths: Vec<String> = rows[start..=end]
+            .iter()
+            .flat_map(|row| self.row_paths(section, row))
+            .collect();
+        let key = self.row_key(section, selected);
+        self.marks
+            .extend(paths.into_iter().map(|path| (section, path)));
+        self.mark_anchor = Some((section, key));
+    }
+
+    /// Forget marks of files that left their section
+    fn prune_marks(&mut self) {
+        if self.marks.is_empty() {
+            return;
+        }
+        let marks = std::mem::take(&mut self.marks);
+        let present: HashSet<(Section, &str)> = [Section::Staged, Section::Unstaged]
+            .into_iter()
+            .flat_map(|section| {
+                self.section_files(section)
+                    .iter()
+                    .map(move |file| (section, file.path.as_str()))
+            })
+            .collect();
+        let marks = marks
+            .into_iter()
+            .filter(|(section, path)| present.contains(&(*section, path.as_str())))
+            .collect();
+        self.marks = marks;
+    }
+
+    /// Paths to stage/unstage for the selected row
+    ///
+    /// A directory is staged as a whole unless a filter hides some of its
+    /// files; then only the visible files under it are listed.
+    fn selected_pathspecs(&self) -> Vec<String> {
+        match self.selected_row() {
+            Some(FileRow::File { index, .. }) => vec![self.current_files()[index].path.clone()],
+            Some(row @ FileRow::Dir(_)) if self.filter.is_active() => {
+                self.row_paths(self.selected_section, &row)
+            }
+            Some(FileRow::Dir(dir)) => vec![dir.path],
+            None => Vec::new(),
+        }
+    }
+
+    /// Number of changes waiting to be applied while paused
+    ///
+    /// Counts changed paths when they are known, change notifications otherwise.
+    pub fn pending_change_count(&self) -> usize {
+        match &self.pending_scope {
+            Some(ChangeScope::Paths(paths)) => paths.len(),
+            Some(ChangeScope::Full) => self.pending_changes,
+            None => 0,
+        }
+    }
+
+    /// Record the watcher status, notifying about fallbacks and failures
+    fn set_watcher_status(&mut self, status: WatcherStatus) {
+        let toast = match &status {
+            WatcherStatus::Native | WatcherStatus::Polling { reason: None } => None,
+            WatcherStatus::Polling {
+                reason: Some(reason),
+            } => Some(
+                Toast::new(Severity::Warning, "Switched to polling for file changes")
+                    .with_detail(reason.clone()),
+            ),
+            WatcherStatus::Degraded(reason) => Some(
+                Toast::new(Severity::Warning, "File watcher may be missing changes")
+                    .with_detail(reason.clone()),
+            ),
+            WatcherStatus::Failed(reason) => Some(
+                Toast::new(
+                    Severity::Error,
+                    "File watching is unavailable; press r to refresh",
+                )
+                .with_detail(reason.clone()),
+            ),
+        };
+        if let Some(toast) = toast {
+            self.notifications.push(toast);
+        }
+        self.watcher_status = Some(status);
+    }
+
+    /// Move the selection in the command console (index 0 is the most recent command)
+    fn move_console_selection(&mut self, action: Action) {
+        let max_index = self.git_service.command_log().len().saturating_sub(1);
+        self.console_selected = match action {
+            Action::MoveUp => self.console_selected.saturating_sub(1),
+            Action::MoveDown => self.console_selected + 1,
+            Action::PageUp => self.console_selected.saturating_sub(10),
+            Action::PageDown => self.console_selected + 10,
+            _ => self.console_selected,
+        }
+        .min(max_index);
+    }
+
+    /// Move the selection in the timeline (index 0 is the most recent row)
+    fn move_timeline_selection(&mut self, action: Action) {
+        let max_index = self.timeline.row_count().saturating_sub(1);
+        let selected = match action {
+            Action::MoveUp => self.timeline_selected.saturating_sub(1),
+            Action::MoveDown => self.timeline_selected + 1,
+            Action::PageUp => self.timeline_selected.saturating_sub(10),
+            Action::PageDown => self.timeline_selected + 10,
+            _ => self.timeline_selected,
+        }
+        .min(max_index);
+        if selected != self.timeline_selected {
+            self.timeline_selected = selected;
+            self.update_timeline_diff();
+        }
+    }
+
+    /// Capture the blobs of the files a timeline entry touched in the background
+    fn capture_timeline_blobs(&mut self, id: u64, files: Vec<FileStatus>) {
+        // New rows go on top; keep the selected row in place while the timeline is open
+        if self.show_timeline && self.timeline_selected > 0 {
+            let added = self
+                .timeline
+                .rows()
+                .take_while(|(entry, _)| entry.id == id)
+                .count();
+            self.timeline_selected += added;
+        }
+
+        if let Some(tx) = &self.event_tx {
+            let tx = tx.clone();
+            let git_service = self.git_service.clone();
+            tokio::spawn(async move {
+                let result = git_service
+                    .capture_blobs(&files)
+                    .await
+                    .map_err(|report| Toast::from_report(&report));
+                let _ = tx.send(Event::TimelineBlobs { id, result });
+            });
+        }
+    }
+
+    /// Load the diff of the selected timeline row from its captured blobs
+    ///
+    /// Rows whose blobs are not captured yet (or that are no longer listed) have no diff.
+    fn update_timeline_diff(&mut self) {
+        self.timeline_diff_generation += 1;
+        self.timeline_diff = None;
+
+        let Some((path, blobs)) = self
+            .timeline
+            .rows()
+            .nth(self.timeline_selected)
+            .filter(|(_, row)| !row.gone)
+            .and_then(|(_, row)| Some((row.file.path.clone(), row.blobs.clone()?)))
+        else {
+            return;
+        };
+
+        if let Some(tx) = &self.event_tx {
+            let tx = tx.clone();
+            let git_service = self.git_service.clone();
+            let generation = self.timeline_diff_generation;
+            tokio::spawn(async move {
+                let result = git_service
+                    .blob_diff(&path, &blobs)
+                    .await
+                    .map_err(|report| Toast::from_report(&report));
+                let _ = tx.send(Event::TimelineDiffReady { generation, result });
+            });
+        }
+    }
+
+    /// Whether files have settled since a change, or the interval has passed, so a snapshot is due
+    fn snapshot_is_due(&self, now: Instant) -> bool {
+        let config = &self.config.snapshots;
+        if !config.enabled || self.snapshot_running {
+            return false;
+        }
+        let settled = self.snapshot_due.is_some_and(|due| now >= due);
+        let interval = config.interval_ms > 0
+            && now.saturating_duration_since(self.last_snapshot_at)
+                >= Duration::from_millis(config.interval_ms);
+        settled || interval
+    }
+
+    /// Snapshot the working tree in the background, then prune snapshots past the maximum age
+    fn take_snapshot(&mut self) {
+        self.snapshot_due = None;
+        self.last_snapshot_at = Instant::now();
+
+        if let Some(tx) = &self.event_tx {
+            self.snapshot_running = true;
+            let tx = tx.clone();
+            let git_service = self.git_service.clone();
+            let max_age = Duration::from_secs(self.config.snapshots.max_age_hours * 3600);
+            tokio::spawn(async move {
+                let result = async {
+                    let snapshot = git_service.create_snapshot().await?;
+                    if snapshot.is_some() && !max_age.is_zero() {
+                        let cutoff = SystemTime::now()
+                            .checked_sub(max_age)
+                            .unwrap_or(SystemTime::UNIX_EPOCH);
+                        git_service.prune_snapshots(cutoff).await?;
+                    }
+                    Ok(snapshot)
                 }
+                .await
+                .map_err(|report| Toast::from_report(&report));
+                let _ = tx.send(Event::SnapshotTaken(result));
+            });
+        }
+    }
 
-                // Refresh the status to reflect the changes
-                self.refresh_git_status(git_service).await?;
+    /// Move the selection in the snapshot browser (snapshots, or the files of one)
+    fn move_snapshot_selection(&mut self, action: Action) {
+        let Some(browser) = &mut self.snapshot_browser else {
+            return;
+        };
+        let (current, count) = match browser.file_selected {
+            Some(file) => (file, browser.changes.as_ref().map_or(0, |c| c.files.len())),
+            None => (
+                browser.selected,
+                browser.snapshots.as_ref().map_or(0, Vec::len),
+            ),
+        };
+        let selected = match action {
+            Action::MoveUp => current.saturating_sub(1),
+            Action::MoveDown => current + 1,
+            Action::PageUp => current.saturating_sub(10),
+            Action::PageDown => current + 10,
+            _ => current,
+        }
+        .min(count.saturating_sub(1));
+        if selected == current {
+            return;
+        }
+
+        if browser.file_selected.is_some() {
+            browser.file_selected = Some(selected);
+            self.load_snapshot_diff();
+        } else {
+            browser.selected = selected;
+            self.load_snapshot_changes();
+        }
+    }
+
+    /// Load the snapshot list for the snapshot browser
+    fn load_snapshots(&mut self) {
+        self.snapshot_generation += 1;
+
+        if let Some(tx) = &self.event_tx {
+            let tx = tx.clone();
+            let git_service = self.git_service.clone();
+            let generation = self.snapshot_generation;
+            tokio::spawn(async move {
+                let result = git_service
+                    .list_snapshots()
+                    .await
+                    .map_err(|report| Toast::from_report(&report));
+                let _ = tx.send(Event::SnapshotsLoaded { generation, result });
+            });
+        }
+    }
+
+    /// Show a loaded snapshot list, keeping the selected snapshot selected if it is still there
+    fn show_snapshots(&mut self, snapshots: Vec<Snapshot>) {
+        let Some(browser) = &mut self.snapshot_browser else {
+            return;
+        };
+        let previous = browser.snapshot().map(|snapshot| snapshot.name.clone());
+        browser.selected = previous
+            .and_then(|name| snapshots.iter().position(|snapshot| snapshot.name == name))
+            .unwrap_or(0);
+        browser.snapshots = Some(snapshots);
+        self.load_snapshot_changes();
+    }
+
+    /// Compare the selected snapshot with the working tree
+    fn load_snapshot_changes(&mut self) {
+        self.snapshot_generation += 1;
+        let Some(browser) = &mut self.snapshot_browser else {
+            return;
+        };
+        browser.changes = None;
+        browser.diff = None;
+        let Some(snapshot) = browser.snapshot().cloned() else {
+            browser.file_selected = None;
+            return;
+        };
+
+        if let Some(tx) = &self.event_tx {
+            let tx = tx.clone();
+            let git_service = self.git_service.clone();
+            let generation = self.snapshot_generation;
+            tokio::spawn(async move {
+                let result = git_service
+                    .snapshot_changes(&snapshot)
+                    .await
+                    .map_err(|report| Toast::from_report(&report));
+                let _ = tx.send(Event::SnapshotChangesReady { generation, result });
+            });
+        }
+    }
+
+    /// Show the changes since the selected snapshot, reloading the diff of the selected file
+    fn show_snapshot_changes(&mut self, changes: SnapshotChanges) {
+        let Some(browser) = &mut self.snapshot_browser else {
+            return;
+        };
+        let count = changes.files.len();
+        browser.changes = Some(changes);
+        match browser.file_selected {
+            Some(_) if count == 0 => browser.file_selected = None,
+            Some(file) => {
+                browser.file_selected = Some(file.min(count - 1));
+                self.load_snapshot_diff();
             }
+            None => {}
+        }
+    }
+
+    /// Load the diff of the selected file since the selected snapshot
+    fn load_snapshot_diff(&mut self) {
+        self.snapshot_generation += 1;
+        let Some(browser) = &mut self.snapshot_browser else {
+            return;
+        };
+        browser.diff = None;
+        let (Some(snapshot), Some(changes), Some(path)) = (
+            browser.snapshot().cloned(),
+            browser.changes.clone(),
+            browser.file().map(|file| file.path.clone()),
+        ) else {
+            return;
+        };
+
+        if let Some(tx) = &self.event_tx {
+            let tx = tx.clone();
+            let git_service = self.git_service.clone();
+            let generation = self.snapshot_generation;
+            tokio::spawn(async move {
+                let result = git_service
+                    .snapshot_file_diff(&snapshot, &changes, &path)
+                    .await
+                    .map_err(|report| Toast::from_report(&report));
+                let _ = tx.send(Event::SnapshotDiffReady { generation, result });
+            });
+        }
+    }
+
+    /// Run the confirmed restore or prune of the snapshot browser in the background
+    fn run_snapshot_action(&mut self) {
+        let Some(browser) = &mut self.snapshot_browser else {
+            return;
+        };
+        let (Some(action), Some(snapshot)) = (browser.confirm.take(), browser.snapshot().cloned())
+        else {
+            return;
+        };
+        let path = browser.file().map(|file| file.path.clone());
+        if action == SnapshotAction::Restore && path.is_none() {
+            return;
+        }
+
+        if let Some(tx) = &self.event_tx {
+            let tx = tx.clone();
+            let git_service = self.git_service.clone();
+            tokio::spawn(async move {
+                let result = match (action, path) {
+                    (SnapshotAction::Restore, Some(path)) => git_service
+                        .restore_from_snapshot(&snapshot, &path)
+                        .await
+                        .map(|()| format!("Restored {} from the snapshot", path)),
+                    _ => git_service
+                        .prune_snapshots(snapshot.time)
+                        .await
+                        .map(|count| {
+                            let noun = if count == 1 { "snapshot" } else { "snapshots" };
+                            format!("Pruned {} {}", count, noun)
+                        }),
+                };
+                let result = result.map_err(|report| Toast::from_report(&report));
+                let _ = tx.send(Event::SnapshotActionFinished(result));
+            });
+        }
+    }
+
+    /// Start a push/pull in the background, streaming its progress into the remote panel
+    fn start_remote_operation(&mut self, operation: RemoteOperation) {
+        if let Some(panel) = self.remote_panel.as_ref().filter(|p| p.is_running()) {
+            let message = format!("{} is already running", panel.operation.label());
+            self.notifications.push(Toast::new(Severity::Info, message));
+            return;
+        }
+
+        let Some(tx) = self.event_tx.clone() else {
+            return;
+        };
+
+        self.remote_panel = Some(RemotePanel::new(operation));
+
+        let git_service = self.git_service.clone();
+        tokio::spawn(async move {
+            let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+
+            let forward_tx = tx.clone();
+            let forward = tokio::spawn(async move {
+                while let Some(line) = progress_rx.recv().await {
+                    let _ = forward_tx.send(Event::RemoteProgress(line));
+                }
+            });
+
+            let result = git_service
+                .run_remote_operation(operation, progress_tx)
+                .await
+                .map_err(|e| e.to_string());
+
+            // Make sure all progress lines are delivered before the outcome
+            let _ = forward.await;
+            let _ = tx.send(Event::RemoteFinished(result));
+        });
+    }
+
+    /// Get the currently selected file (None when a directory node is selected)
+    pub fn get_selected_file(&self) -> Option<&FileStatus> {
+        match self.selected_row()? {
+            FileRow::File { index, .. } => self.current_files().get(index),
+            FileRow::Dir(_) => None,
         }
-        Ok(())
     }
 
     /// Get the currently selected file path
     pub fn get_selected_file_path(&self) -> Option<&str> {
-        if let Some(status) = &self.git_status {
-            let files = match self.selected_section {
-                Section::Staged => &status.staged_files,
-                Section::Unstaged => &status.unstaged_files,
-            };
-            files.get(self.selected_index).map(|f| f.path.as_str())
-        } else {
-            None
+        self.get_selected_file().map(|f| f.path.as_str())
+    }
+}
+
+/// Line to show for the change between two versions of a diff
+///
+/// The hunk header above the first differing line (or that line if it is
+/// not in a hunk); None if the diffs are the same.
+fn changed_hunk_start(old: &str, new: &str) -> Option<usize> {
+    let mut old_lines = old.lines();
+    let mut hunk = None;
+    for (index, line) in new.lines().enumerate() {
+        if line.starts_with("@@") {
+            hunk = Some(index);
+        }
+        if old_lines.next() != Some(line) {
+            return Some(hunk.unwrap_or(index));
+        }
+    }
+    // Lines were removed at the end
+    old_lines.next().map(|_| hunk.unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        App::new(
+            PathBuf::from("/repo"),
+            Config::default(),
+            Theme::catppuccin(),
+            true,
+        )
+    }
+
+    /// Status listing `paths` as modified, unstaged files
+    fn unstaged_status(paths: &[&str]) -> GitStatus {
+        GitStatus {
+            unstaged_files: paths
+                .iter()
+                .map(|path| FileStatus::new(path.to_string(), FileStatusType::Modified, false))
+                .collect(),
+            ..GitStatus::default()
+        }
+    }
+
+    #[test]
+    fn test_changes_are_queued_while_paused() {
+        let mut app = app();
+        app.paused = true;
+
+        let paths = |p: &[&str]| ChangeScope::Paths(p.iter().map(|s| s.to_string()).collect());
+        app.handle_event(Event::FileChange(paths(&["a.rs", "b.rs"])));
+        app.handle_event(Event::FileChange(paths(&["b.rs", "c.rs"])));
+        assert_eq!(app.pending_change_count(), 3);
+        assert!(app.status_request.is_none());
+
+        app.handle_event(Event::FileChange(ChangeScope::Full));
+        assert_eq!(app.pending_change_count(), 3);
+
+        app.handle_action(Action::TogglePause).unwrap();
+        assert!(!app.paused);
+        assert_eq!(app.pending_change_count(), 0);
+    }
+
+    #[test]
+    fn test_filter_narrows_selection_and_directory_staging() {
+        let mut app = app();
+        let files = [
+            "docs/guide.md",
+            "src/app.rs",
+            "src/ui/list.rs",
+            "src/ui/notes.md",
+        ];
+        app.git_status = Some(unstaged_status(&files));
+        app.file_view = FileView::Tree;
+
+        // Without a filter, the `src/` node stages the whole directory
+        assert_eq!(app.get_current_section_len(), 7);
+        assert_eq!(app.selected_pathspecs(), vec!["docs/".to_string()]);
+        app.selected_index = 1;
+        app.handle_action(Action::MoveDown).unwrap();
+        assert_eq!(app.selected_pathspecs(), vec!["src/".to_string()]);
+
+        app.handle_action(Action::OpenFilter).unwrap();
+        for c in "md".chars() {
+            app.handle_action(Action::FilterChar(c)).unwrap();
+        }
+        // docs/, guide.md, src/ui/, notes.md
+        assert_eq!(app.get_current_section_len(), 4);
+        assert_eq!(app.selected_index, 2);
+        // The filtered `src/ui/` node only stages the visible file under it
+        assert_eq!(
+            app.selected_pathspecs(),
+            vec!["src/ui/notes.md".to_string()]
+        );
+
+        app.handle_action(Action::ClearFilter).unwrap();
+        assert!(!app.filter.is_active());
+        assert_eq!(app.get_current_section_len(), 7);
+    }
+
+    #[test]
+    fn test_marks_select_batches_and_survive_refreshes() {
+        let mut app = app();
+        app.git_status = Some(unstaged_status(&["a.rs", "b.rs", "c.rs", "d.rs"]));
+
+        // Without marks the selected file is staged, all files for "stage all"
+        assert_eq!(app.batch_paths(Section::Unstaged, false), vec!["a.rs"]);
+        assert_eq!(app.batch_paths(Section::Unstaged, true).len(), 4);
+
+        app.handle_action(Action::ToggleMark).unwrap();
+        assert_eq!(app.selected_index, 1);
+        app.selected_index = 2;
+        app.handle_action(Action::MarkRange).unwrap();
+        assert_eq!(
+            app.batch_paths(Section::Unstaged, true),
+            vec!["a.rs", "b.rs", "c.rs"]
+        );
+
+        // Marks follow paths across refreshes; files that left the section lose theirs
+        app.apply_status_update(StatusUpdate::Full(unstaged_status(&[
+            "b.rs", "c.rs", "d.rs",
+        ])));
+        assert_eq!(
+            app.batch_paths(Section::Unstaged, false),
+            vec!["b.rs", "c.rs"]
+        );
+        assert_eq!(app.marks.len(), 2);
+
+        app.handle_action(Action::MarkAll).unwrap();
+        assert_eq!(app.marks.len(), 3);
+        app.handle_action(Action::MarkAll).unwrap();
+        assert!(app.marks.is_empty());
+
+        // Discarding asks first; any key but `y` cancels
+        app.handle_action(Action::Discard).unwrap();
+        assert_eq!(app.pending_discard.as_ref().map(Vec::len), Some(1));
+        let action = app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('q'))));
+        assert_eq!(action, Action::CancelDiscard);
+    }
+
+    #[test]
+    fn test_cycling_sort_keeps_selection_and_saves_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("gfm.json");
+        let mut app = app().with_config_path(config_path.clone());
+        app.git_status = Some(GitStatus {
+            unstaged_files: vec![
+                FileStatus::new("a.rs".to_string(), FileStatusType::Untracked, false),
+                FileStatus::new("b.rs".to_string(), FileStatusType::Modified, false),
+            ],
+            ..GitStatus::default()
+        });
+
+        app.handle_action(Action::CycleSort).unwrap();
+        assert_eq!(app.sort_mode, SortMode::Status);
+        assert_eq!(app.section_files(Section::Unstaged)[0].path, "b.rs");
+        assert_eq!(app.get_selected_file().unwrap().path, "a.rs");
+
+        let saved = std::fs::read_to_string(config_path).unwrap();
+        assert!(saved.contains(r#""sortBy": "status""#));
+    }
+
+    #[test]
+    fn test_search_in_focused_preview_scrolls_to_matches() {
+        let mut app = app();
+        app.git_status = Some(unstaged_status(&["lib.rs"]));
+        let diff: Vec<String> = (0..40).map(|i| format!("+line {}", i)).collect();
+        app.handle_event(Event::DiffReady {
+            generation: app.diff_generation,
+            path: "lib.rs".to_string(),
+            staged: false,
+            result: Ok(diff.join("\n")),
+        });
+
+        // `n` opens the notification history until the preview has focus
+        let key = |c| Event::Key(KeyEvent::from(KeyCode::Char(c)));
+        assert_eq!(
+            app.handle_event(key('n')),
+            Action::ToggleNotificationHistory
+        );
+        app.handle_action(Action::FocusPreview).unwrap();
+        let action = app.handle_event(key('/'));
+        app.handle_action(action).unwrap();
+        for c in "line 2".chars() {
+            let action = app.handle_event(key(c));
+            app.handle_action(action).unwrap();
+        }
+
+        // line 2, line 20..29
+        assert_eq!(app.diff_search.matches.len(), 11);
+        assert_eq!(app.preview_scroll, 0);
+        app.handle_action(Action::CloseSearch).unwrap();
+        let action = app.handle_event(key('n'));
+        app.handle_action(action).unwrap();
+        assert_eq!(app.diff_search.current, Some(1));
+        assert_eq!(app.preview_scroll, 20 - SEARCH_CONTEXT_LINES as u16);
+
+        let action = app.handle_event(Event::Key(KeyEvent::from(KeyCode::Esc)));
+        app.handle_action(action).unwrap();
+        assert!(!app.diff_search.is_active());
+    }
+
+    #[test]
+    fn test_follow_selects_latest_change_until_navigating() {
+        let mut app = app();
+        let now = std::time::SystemTime::now();
+        let mut status = unstaged_status(&["a.rs", "b.rs", "c.rs"]);
+        for (file, age) in status.unstaged_files.iter_mut().zip([20, 10, 30]) {
+            file.modified = Some(now - Duration::from_secs(age));
+        }
+        app.git_status = Some(status.clone());
+
+        app.handle_action(Action::ToggleFollow).unwrap();
+        assert_eq!(app.get_selected_file().unwrap().path, "b.rs");
+
+        // The newest of the changed paths is selected once its status arrives
+        app.handle_event(Event::FileChange(ChangeScope::Paths(vec![
+            "a.rs".to_string(),
+            "c.rs".to_string(),
+        ])));
+        app.apply_status_update(StatusUpdate::Full(status));
+        assert_eq!(app.get_selected_file().unwrap().path, "a.rs");
+
+        // A refreshed diff scrolls to the hunk that changed
+        let diff = |second: &str| {
+            let mut lines = vec!["diff --git a/a.rs b/a.rs", "@@ -1 +1 @@", "-a", "+b"];
+            lines.extend(["@@ -9 +9 @@", "-x", second, " ctx", " ctx"]);
+            lines.join("\n")
+        };
+        for content in [diff("+y"), diff("+z")] {
+            app.handle_event(Event::DiffReady {
+                generation: app.diff_generation,
+                path: "a.rs".to_string(),
+                staged: false,
+                result: Ok(content),
+            });
+        }
+        assert_eq!(app.preview_scroll, 4);
+        assert_eq!(changed_hunk_start("a\nb", "a\nb"), None);
+
+        app.handle_action(Action::MoveDown).unwrap();
+        assert!(!app.follow);
+    }
+
+    #[test]
+    fn test_timeline_records_changes_and_keeps_the_selected_row() {
+        let mut app = app();
+        app.git_status = Some(unstaged_status(&["a.rs"]));
+        app.apply_status_update(StatusUpdate::Full(unstaged_status(&["a.rs"])));
+        assert!(app.timeline.is_empty());
+        app.apply_status_update(StatusUpdate::Full(unstaged_status(&["a.rs", "b.rs"])));
+        app.apply_status_update(StatusUpdate::Full(unstaged_status(&["b.rs", "c.rs"])));
+        assert_eq!(app.timeline.len(), 2);
+
+        let key = |c| Event::Key(KeyEvent::from(KeyCode::Char(c)));
+        let action = app.handle_event(key('T'));
+        app.handle_action(action).unwrap();
+        assert!(app.show_timeline);
+        let action = app.handle_event(key('j'));
+        app.handle_action(action).unwrap();
+        let selected = |app: &App| {
+            let (_, row) = app.timeline.rows().nth(app.timeline_selected).unwrap();
+            (row.file.path.clone(), row.gone)
+        };
+        assert_eq!(selected(&app), ("a.rs".to_string(), true));
+
+        // A new refresh adds rows on top without moving the selection
+        app.apply_status_update(StatusUpdate::Full(unstaged_status(&["c.rs"])));
+        assert_eq!(selected(&app), ("a.rs".to_string(), true));
+
+        // Diffs of rows no longer selected are dropped
+        app.handle_event(Event::TimelineDiffReady {
+            generation: app.timeline_diff_generation - 1,
+            result: Ok("stale".to_string()),
+        });
+        assert_eq!(app.timeline_diff, None);
+
+        let action = app.handle_event(Event::Key(KeyEvent::from(KeyCode::Esc)));
+        app.handle_action(action).unwrap();
+        assert!(!app.show_timeline);
+    }
+
+    #[test]
+    fn test_snapshots_wait_for_changes_to_settle_and_browser_navigation() {
+        let mut app = app();
+        app.config.snapshots.enabled = true;
+        app.config.snapshots.settle_ms = 1000;
+        let now = Instant::now();
+        assert!(!app.snapshot_is_due(now));
+        app.handle_event(Event::FileChange(ChangeScope::Full));
+        assert!(!app.snapshot_is_due(now));
+        assert!(app.snapshot_is_due(now + Duration::from_secs(2)));
+
+        let press = |app: &mut App, code| {
+            let action = app.handle_event(Event::Key(KeyEvent::from(code)));
+            app.handle_action(action).unwrap();
+        };
+        press(&mut app, KeyCode::Char('B'));
+        let snapshot = |millis| Snapshot {
+            name: format!("refs/gfm/snapshots/{}", millis),
+            commit: "c".to_string(),
+            tree: "t".to_string(),
+            time: SystemTime::UNIX_EPOCH + Duration::from_millis(millis),
+        };
+        app.handle_event(Event::SnapshotsLoaded {
+            generation: app.snapshot_generation,
+            result: Ok(vec![snapshot(2), snapshot(1)]),
+        });
+        let files = ["a.rs", "b.rs"]
+            .iter()
+            .map(|path| FileStatus::new(path.to_string(), FileStatusType::Modified, false))
+            .collect();
+        app.handle_event(Event::SnapshotChangesReady {
+            generation: app.snapshot_generation,
+            result: Ok(SnapshotChanges {
+                current_tree: "now".to_string(),
+                files,
+            }),
+        });
+
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Down);
+        let browser = app.snapshot_browser.as_ref().unwrap();
+        assert_eq!(browser.file().unwrap().path, "b.rs");
+        press(&mut app, KeyCode::Char('R'));
+        assert_eq!(
+            app.snapshot_browser.as_ref().unwrap().confirm,
+            Some(SnapshotAction::Restore)
+        );
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.snapshot_browser.as_ref().unwrap().confirm, None);
+
+        // Back in the snapshot list, the last snapshot has nothing older to prune
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Down);
+        let browser = app.snapshot_browser.as_ref().unwrap();
+        assert_eq!(browser.snapshot(), Some(&snapshot(1)));
+        assert!(browser.changes.is_none());
+        press(&mut app, KeyCode::Char('X'));
+        assert_eq!(app.snapshot_browser.as_ref().unwrap().confirm, None);
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::Char('X'));
+        assert_eq!(
+            app.snapshot_browser.as_ref().unwrap().confirm,
+            Some(SnapshotAction::Prune)
+        );
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Esc);
+        assert!(app.snapshot_browser.is_none());
+    }
+
+    #[test]
+    fn test_preview_scrolling_by_keys_hunks_and_wheel() {
+        let mut app = app();
+        app.git_status = Some(unstaged_status(&["lib.rs"]));
+        // Hunks start at lines 0, 30 and 60 of a 90-line diff
+        let diff: Vec<String> = (0..90)
+            .map(|i| {
+                if i % 30 == 0 {
+                    "@@ -1 +1 @@".to_string()
+                } else {
+                    format!("+{}", i)
+                }
+            })
+            .collect();
+        app.handle_event(Event::DiffReady {
+            generation: app.diff_generation,
+            path: "lib.rs".to_string(),
+            staged: false,
+            result: Ok(diff.join("\n")),
+        });
+        // 20 visible lines inside the borders
+        app.preview_area = Rect::new(50, 4, 50, 22);
+
+        let press = |app: &mut App, key: KeyEvent| {
+            let action = app.handle_event(Event::Key(key));
+            app.handle_action(action).unwrap();
+        };
+        let char_key = |c| KeyEvent::from(KeyCode::Char(c));
+
+        // `j` moves the file selection until the preview has focus
+        press(&mut app, char_key('j'));
+        assert_eq!(app.preview_scroll, 0);
+        press(&mut app, KeyEvent::from(KeyCode::Right));
+        assert_eq!(app.focus, Focus::Preview);
+
+        press(&mut app, char_key('j'));
+        assert_eq!(app.preview_scroll, 1);
+        press(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL),
+        );
+        assert_eq!(app.preview_scroll, 11);
+        press(&mut app, char_key(']'));
+        assert_eq!(app.preview_scroll, 30);
+        press(&mut app, char_key(']'));
+        assert_eq!(app.preview_scroll, 60);
+        press(&mut app, char_key('['));
+        assert_eq!(app.preview_scroll, 30);
+
+        // The last line stays at the bottom of the pane
+        press(&mut app, char_key('G'));
+        assert_eq!(app.preview_scroll, 70);
+        press(&mut app, char_key('g'));
+        assert_eq!(app.preview_scroll, 0);
+
+        let wheel = |kind, column| {
+            Event::Mouse(MouseEvent {
+                kind,
+                column,
+                row: 10,
+                modifiers: KeyModifiers::NONE,
+            })
+        };
+        let action = app.handle_event(wheel(MouseEventKind::ScrollDown, 60));
+        app.handle_action(action).unwrap();
+        assert_eq!(app.preview_scroll, WHEEL_SCROLL_LINES as u16);
+        // Over the file list the wheel does not scroll the preview
+        assert_eq!(
+            app.handle_event(wheel(MouseEventKind::ScrollUp, 10)),
+            Action::None
+        );
+    }
+
+    #[test]
+    fn test_mouse_selects_rows_collapses_sections_and_drags_divider() {
+        let mut app = app();
+        let file = |path: &str, staged| {
+            FileStatus::new(path.to_string(), FileStatusType::Modified, staged)
+        };
+        app.git_status = Some(GitStatus {
+            staged_files: vec![file("a.rs", true)],
+            unstaged_files: vec![file("b.rs", false), file("c.rs", false)],
+            ..GitStatus::default()
+        });
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 30)).unwrap();
+        let mut draw = |app: &mut App| {
+            terminal.draw(|frame| ui::render(frame, app)).unwrap();
+        };
+        let mouse = |kind, column, row| {
+            Event::Mouse(MouseEvent {
+                kind,
+                column,
+                row,
+                modifiers: KeyModifiers::NONE,
+            })
+        };
+        let left = MouseButton::Left;
+        draw(&mut app);
+
+        // The list starts below the 4-row header and its top border:
+        // staged header, a.rs, separator, unstaged header, b.rs, c.rs
+        app.handle_event(mouse(MouseEventKind::Down(left), 10, 10));
+        assert_eq!(
+            (app.selected_section, app.selected_index),
+            (Section::Unstaged, 1)
+        );
+
+        app.handle_event(mouse(MouseEventKind::Down(left), 10, 5));
+        assert!(app.collapsed_sections.contains(&Section::Staged));
+        assert!(app.section_rows(Section::Staged).is_empty());
+
+        let action = app.handle_event(mouse(MouseEventKind::Down(left), 70, 10));
+        app.handle_action(action).unwrap();
+        assert_eq!(app.focus, Focus::Preview);
+
+        // The list's right border is the divider
+        app.handle_event(mouse(MouseEventKind::Down(left), 49, 12));
+        app.handle_event(mouse(MouseEventKind::Drag(left), 69, 12));
+        app.handle_event(mouse(MouseEventKind::Up(left), 69, 12));
+        assert_eq!(app.split_percent, 70);
+        draw(&mut app);
+        assert_eq!(app.preview_area.x, 70);
+
+        // Zooming maximizes the focused preview
+        app.handle_action(Action::ToggleZoom).unwrap();
+        draw(&mut app);
+        assert_eq!(app.preview_area, app.pane_layout.area);
+        assert!(app.file_list_area.is_empty());
+    }
+
+    /// CPU time consumed by the current thread, from `/proc/thread-self/stat`
+    #[cfg(target_os = "linux")]
+    fn thread_cpu_time() -> Duration {
+        let stat = std::fs::read_to_string("/proc/thread-self/stat").unwrap();
+        // utime and stime are the 14th and 15th fields; the name before ')' may contain spaces
+        let fields: Vec<u64> = stat
+            .rsplit_once(')')
+            .unwrap()
+            .1
+            .split_whitespace()
+            .skip(11)
+            .take(2)
+            .map(|field| field.parse().unwrap())
+            .collect();
+        // Clock ticks are 1/100 s on Linux
+        Duration::from_millis((fields[0] + fields[1]) * 10)
+    }
+
+    #[tokio::test]
+    async fn test_idle_app_does_not_redraw() {
+        let mut app = app();
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(120, 40)).unwrap();
+        let mut events = EventHandler::without_terminal(Duration::from_millis(5));
+
+        let tx = events.sender();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            let _ = tx.send(Event::Resize(120, 40));
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            let _ = tx.send(Event::Key(KeyEvent::from(KeyCode::Char('q'))));
+        });
+
+        #[cfg(target_os = "linux")]
+        let cpu_before = thread_cpu_time();
+        app.event_loop(&mut terminal, &mut events).await.unwrap();
+
+        // About 200 ticks went by, but only the first frame and the resize were drawn
+        assert_eq!(terminal.get_frame().count(), 2);
+
+        // Idle ticks cost next to nothing (the test thread also runs the event handler)
+        #[cfg(target_os = "linux")]
+        {
+            let cpu = thread_cpu_time() - cpu_before;
+            assert!(
+                cpu < Duration::from_millis(200),
+                "used {cpu:?} of CPU in 1s"
+            );
         }
     }
 }
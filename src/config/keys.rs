@@ -0,0 +1,112 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Whether a key event matches a binding from the config
+///
+/// Bindings are a single character (`g`, `G`, `]`), a key name (`up`,
+/// `pagedown`, `home`, `enter`, ...), optionally prefixed with `ctrl+` or
+/// `alt+` (`ctrl+d`).
+pub fn key_matches(binding: &str, key: &KeyEvent) -> bool {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut name = binding;
+    loop {
+        if let Some(rest) = strip_prefix_ignore_case(name, "ctrl+") {
+            modifiers |= KeyModifiers::CONTROL;
+            name = rest;
+        } else if let Some(rest) = strip_prefix_ignore_case(name, "alt+") {
+            modifiers |= KeyModifiers::ALT;
+            name = rest;
+        } else {
+            break;
+        }
+    }
+
+    // Shift is part of the character itself (`G`), so it is not compared
+    let pressed = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+    if pressed != modifiers {
+        return false;
+    }
+
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return match key.code {
+            KeyCode::Char(pressed) if modifiers.is_empty() => pressed == c,
+            // Terminals report ctrl+letter in lowercase
+            KeyCode::Char(pressed) => pressed.eq_ignore_ascii_case(&c),
+            _ => false,
+        };
+    }
+
+    let code = match name.to_ascii_lowercase().as_str() {
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "space" => KeyCode::Char(' '),
+        _ => return false,
+    };
+    key.code == code
+}
+
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = text.get(..prefix.len())?;
+    if head.eq_ignore_ascii_case(prefix) && text.len() > prefix.len() {
+        Some(&text[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_matches_chars_names_and_modifiers() {
+        let key = |code, modifiers| KeyEvent::new(code, modifiers);
+
+        assert!(key_matches(
+            "g",
+            &key(KeyCode::Char('g'), KeyModifiers::NONE)
+        ));
+        assert!(!key_matches(
+            "g",
+            &key(KeyCode::Char('G'), KeyModifiers::SHIFT)
+        ));
+        assert!(key_matches(
+            "G",
+            &key(KeyCode::Char('G'), KeyModifiers::SHIFT)
+        ));
+        assert!(key_matches(
+            "ctrl+d",
+            &key(KeyCode::Char('d'), KeyModifiers::CONTROL)
+        ));
+        assert!(!key_matches(
+            "ctrl+d",
+            &key(KeyCode::Char('d'), KeyModifiers::NONE)
+        ));
+        assert!(!key_matches(
+            "d",
+            &key(KeyCode::Char('d'), KeyModifiers::CONTROL)
+        ));
+        assert!(key_matches(
+            "PageDown",
+            &key(KeyCode::PageDown, KeyModifiers::NONE)
+        ));
+        assert!(key_matches(
+            "+",
+            &key(KeyCode::Char('+'), KeyModifiers::NONE)
+        ));
+        assert!(!key_matches(
+            "unknown",
+            &key(KeyCode::Enter, KeyModifiers::NONE)
+        ));
+    }
+}
@@ -60,7 +60,6 @@ impl ConfigLoader {
     }
 
     /// Get the configuration file path
-    #[allow(dead_code)]
     pub fn config_path(&self) -> &PathBuf {
         &self.config_path
     }
@@ -1,2 +1,3 @@
+pub mod keys;
 pub mod loader;
 pub mod types;
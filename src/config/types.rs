@@ -1,7 +1,13 @@
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 
+use crate::git::runner::RunnerLimits;
+use crate::git::types::UntrackedMode;
+
 /// Main configuration structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     /// Display settings
     pub display: DisplayConfig,
@@ -9,21 +15,17 @@ pub struct Config {
     pub ui: UIConfig,
     /// Keybindings
     pub keybindings: KeybindingsConfig,
-}
-
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            display: DisplayConfig::default(),
-            ui: UIConfig::default(),
-            keybindings: KeybindingsConfig::default(),
-        }
-    }
+    /// Git settings
+    pub git: GitConfig,
+    /// File watcher settings
+    pub watcher: WatcherConfig,
+    /// Working-tree snapshot settings
+    pub snapshots: SnapshotConfig,
 }
 
 /// Display configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(default, rename_all = "camelCase")]
 pub struct DisplayConfig {
     /// Show file path and status
     pub show_file_path_and_status: bool,
@@ -37,6 +39,10 @@ pub struct DisplayConfig {
     pub show_branch_info: bool,
     /// Show last commit information
     pub show_last_commit_info: bool,
+    /// Group changed files by directory instead of listing full paths
+    pub tree_view: bool,
+    /// Order of the files within each section
+    pub sort_by: SortMode,
 }
 
 impl Default for DisplayConfig {
@@ -48,35 +54,134 @@ impl Default for DisplayConfig {
             show_file_preview: true,
             show_branch_info: true,
             show_last_commit_info: true,
+            tree_view: false,
+            sort_by: SortMode::Path,
+        }
+    }
+}
+
+/// Order of the files in the file list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortMode {
+    /// Alphabetically by path
+    #[default]
+    Path,
+    /// By status type (modified, added, deleted, ...), then path
+    Status,
+    /// Most lines changed first
+    Lines,
+    /// By file extension, then path
+    Extension,
+    /// Most recently modified on disk first
+    Modified,
+}
+
+impl SortMode {
+    /// The mode after this one, wrapping around
+    pub fn next(self) -> Self {
+        match self {
+            SortMode::Path => SortMode::Status,
+            SortMode::Status => SortMode::Lines,
+            SortMode::Lines => SortMode::Extension,
+            SortMode::Extension => SortMode::Modified,
+            SortMode::Modified => SortMode::Path,
+        }
+    }
+
+    /// Name shown in the file list title
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::Path => "path",
+            SortMode::Status => "status",
+            SortMode::Lines => "lines",
+            SortMode::Extension => "extension",
+            SortMode::Modified => "modified",
         }
     }
 }
 
 /// UI configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(default, rename_all = "camelCase")]
 pub struct UIConfig {
     /// Color scheme name
     pub color_scheme: String,
-    /// Refresh debounce in milliseconds
-    pub refresh_debounce_ms: u32,
+    /// How often timers and animations are checked in milliseconds (idle ticks draw nothing)
+    pub tick_rate_ms: u32,
     /// Maximum lines to show in preview
     pub max_preview_lines: u32,
+    /// How long notifications stay on screen in milliseconds (errors stay twice as long)
+    pub toast_timeout_ms: u32,
+    /// Capture the mouse (off keeps the terminal's own text selection)
+    pub mouse: bool,
+    /// Share of the content area given to the file list in percent
+    pub split_percent: u16,
+    /// Whether the file list and the preview are side by side or stacked
+    pub layout: LayoutMode,
+    /// In `auto` layout, stack the panes when the terminal is narrower than this
+    pub vertical_below_width: u16,
+    /// Which pane comes first (left or top)
+    pub panel_order: PanelOrder,
+    /// How long files and hunks that just changed stay highlighted in milliseconds (0 = off)
+    pub change_highlight_ms: u64,
+}
+
+impl UIConfig {
+    /// Whether the panes are stacked at the given terminal width
+    pub fn stacks_vertically(&self, width: u16) -> bool {
+        match self.layout {
+            LayoutMode::Auto => width < self.vertical_below_width,
+            LayoutMode::Horizontal => false,
+            LayoutMode::Vertical => true,
+        }
+    }
 }
 
 impl Default for UIConfig {
     fn default() -> Self {
         Self {
             color_scheme: "catppuccin".to_string(),
-            refresh_debounce_ms: 100,
+            tick_rate_ms: 100,
             max_preview_lines: 20,
+            toast_timeout_ms: 4000,
+            mouse: true,
+            split_percent: 50,
+            layout: LayoutMode::Auto,
+            vertical_below_width: 100,
+            panel_order: PanelOrder::FilesFirst,
+            change_highlight_ms: 3000,
         }
     }
 }
 
+/// Arrangement of the file list and the preview
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutMode {
+    /// Side by side, stacked on narrow terminals
+    #[default]
+    Auto,
+    /// Always side by side
+    Horizontal,
+    /// Always stacked
+    Vertical,
+}
+
+/// Order of the file list and the preview
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PanelOrder {
+    /// File list left of (or above) the preview
+    #[default]
+    FilesFirst,
+    /// Preview left of (or above) the file list
+    PreviewFirst,
+}
+
 /// Keybindings configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(default, rename_all = "camelCase")]
 pub struct KeybindingsConfig {
     /// Quit the application
     pub quit: String,
@@ -96,6 +201,70 @@ pub struct KeybindingsConfig {
     pub page_down: String,
     /// Stage or unstage the selected file
     pub stage: String,
+    /// Push the current branch
+    pub push: String,
+    /// Pull the current branch
+    pub pull: String,
+    /// Toggle the git command console
+    pub console: String,
+    /// Toggle the notification history
+    pub notifications: String,
+    /// Cycle the untracked-file mode
+    pub untracked: String,
+    /// Pause/resume applying file changes
+    pub pause: String,
+    /// Switch between the flat list and the directory tree
+    pub tree: String,
+    /// Open the fuzzy filter prompt
+    pub filter: String,
+    /// Cycle the status type quick filter
+    pub filter_status: String,
+    /// Cycle the extension quick filter
+    pub filter_extension: String,
+    /// Move focus to the preview
+    pub focus_preview: String,
+    /// Move focus back to the file list
+    pub focus_files: String,
+    /// Scroll the preview half a page up
+    pub scroll_half_page_up: String,
+    /// Scroll the preview half a page down
+    pub scroll_half_page_down: String,
+    /// Scroll the preview to the top
+    pub scroll_top: String,
+    /// Scroll the preview to the bottom
+    pub scroll_bottom: String,
+    /// Jump to the next hunk in the preview
+    pub next_hunk: String,
+    /// Jump to the previous hunk in the preview
+    pub previous_hunk: String,
+    /// Maximize the focused pane
+    pub zoom: String,
+    /// Mark or unmark the selected file
+    pub mark: String,
+    /// Mark every file between the last marked row and the selection
+    pub mark_range: String,
+    /// Mark or unmark every file in the current section
+    pub mark_all: String,
+    /// Stage the marked files (all files if none are marked)
+    pub stage_all: String,
+    /// Unstage the marked files (all files if none are marked)
+    pub unstage_all: String,
+    /// Discard the changes to the marked (or selected) files
+    pub discard: String,
+    /// Confirm a pending discard, snapshot restore or prune (any other key cancels)
+    pub confirm: String,
+    /// Cycle the sort order of the file list
+    pub sort: String,
+    /// Toggle following the most recently changed file
+    pub follow: String,
+    /// Toggle the session activity timeline
+    pub timeline: String,
+    /// Toggle the snapshot browser
+    pub snapshots: String,
+    /// Restore the selected file from the snapshot (in the snapshot browser)
+    pub restore_snapshot: String,
+    /// Delete the snapshots older than the selected one (in the snapshot browser)
+    pub prune_snapshots: String,
 }
 
 impl Default for KeybindingsConfig {
@@ -110,6 +279,182 @@ impl Default for KeybindingsConfig {
             page_up: "pageup".to_string(),
             page_down: "pagedown".to_string(),
             stage: "s".to_string(),
+            push: "P".to_string(),
+            pull: "f".to_string(),
+            console: "`".to_string(),
+            notifications: "n".to_string(),
+            untracked: "u".to_string(),
+            pause: "z".to_string(),
+            tree: "t".to_string(),
+            filter: "/".to_string(),
+            filter_status: "F".to_string(),
+            filter_extension: "E".to_string(),
+            focus_preview: "right".to_string(),
+            focus_files: "left".to_string(),
+            scroll_half_page_up: "ctrl+u".to_string(),
+            scroll_half_page_down: "ctrl+d".to_string(),
+            scroll_top: "g".to_string(),
+            scroll_bottom: "G".to_string(),
+            next_hunk: "]".to_string(),
+            previous_hunk: "[".to_string(),
+            zoom: "Z".to_string(),
+            mark: "space".to_string(),
+            mark_range: "v".to_string(),
+            mark_all: "a".to_string(),
+            stage_all: "S".to_string(),
+            unstage_all: "U".to_string(),
+            discard: "D".to_string(),
+            confirm: "y".to_string(),
+            sort: "o".to_string(),
+            follow: "w".to_string(),
+            timeline: "T".to_string(),
+            snapshots: "B".to_string(),
+            restore_snapshot: "R".to_string(),
+            prune_snapshots: "X".to_string(),
+        }
+    }
+}
+
+/// Git configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GitConfig {
+    /// Pull with `--rebase` instead of `--ff-only`
+    pub pull_rebase: bool,
+    /// Which untracked files to show (`no`, `normal` or `all`)
+    pub untracked_files: UntrackedMode,
+    /// Above this many untracked files, `all` falls back to `normal` for the
+    /// session and expanded directories collapse again
+    pub max_untracked_files: usize,
+    /// Maximum number of git processes running at once
+    pub max_concurrent_commands: usize,
+    /// Kill local git commands running longer than this (0 for no timeout)
+    pub command_timeout_ms: u64,
+    /// Kill push/pull running longer than this (0 for no timeout)
+    pub remote_timeout_ms: u64,
+}
+
+impl GitConfig {
+    /// Concurrency and timeout limits for the git runner
+    pub fn runner_limits(&self) -> RunnerLimits {
+        RunnerLimits {
+            max_concurrent: self.max_concurrent_commands,
+            command_timeout: timeout(self.command_timeout_ms),
+            remote_timeout: timeout(self.remote_timeout_ms),
+        }
+    }
+}
+
+/// A timeout in milliseconds, where 0 means commands are never killed
+fn timeout(ms: u64) -> Duration {
+    if ms == 0 {
+        Duration::MAX
+    } else {
+        Duration::from_millis(ms)
+    }
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        Self {
+            pull_rebase: false,
+            untracked_files: UntrackedMode::All,
+            max_untracked_files: 1000,
+            max_concurrent_commands: 4,
+            command_timeout_ms: 30_000,
+            remote_timeout_ms: 600_000,
+        }
+    }
+}
+
+/// File watching backend
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatcherBackend {
+    /// Native notifications, falling back to polling if they fail
+    #[default]
+    Auto,
+    /// Native notifications only (inotify, FSEvents, ReadDirectoryChangesW)
+    Native,
+    /// Poll the filesystem (works on NFS/SSHFS and without inotify watches)
+    Poll,
+}
+
+/// File watcher configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WatcherConfig {
+    /// Which backend to use
+    pub backend: WatcherBackend,
+    /// How often the polling backend scans for changes in milliseconds
+    pub poll_interval_ms: u64,
+    /// Throttling of worktree changes
+    pub worktree: ThrottleConfig,
+    /// Throttling of git metadata changes (HEAD, index, refs)
+    pub git_metadata: ThrottleConfig,
+    /// Run a full refresh this often in milliseconds, regardless of file events (0 = never)
+    pub periodic_refresh_ms: u64,
+}
+
+impl Default for WatcherConfig {
+    fn default() -> Self {
+        Self {
+            backend: WatcherBackend::Auto,
+            poll_interval_ms: 1000,
+            worktree: ThrottleConfig::default(),
+            git_metadata: ThrottleConfig {
+                debounce_ms: 50,
+                max_wait_ms: 500,
+                leading: true,
+            },
+            periodic_refresh_ms: 0,
+        }
+    }
+}
+
+/// Leading/trailing throttle settings for file change events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ThrottleConfig {
+    /// Wait until events stop for this long in milliseconds
+    pub debounce_ms: u64,
+    /// But never delay a refresh longer than this in milliseconds
+    pub max_wait_ms: u64,
+    /// Refresh immediately on the first event after a quiet period
+    pub leading: bool,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        Self {
+            debounce_ms: 100,
+            max_wait_ms: 1000,
+            leading: true,
+        }
+    }
+}
+
+/// Working-tree snapshot configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SnapshotConfig {
+    /// Record snapshots of the working tree under `refs/gfm/snapshots/`
+    pub enabled: bool,
+    /// Take a snapshot once files stopped changing for this long in milliseconds
+    pub settle_ms: u64,
+    /// Also take one this often in milliseconds (0 = only after changes)
+    pub interval_ms: u64,
+    /// Prune snapshots older than this many hours after taking one (0 = keep them all)
+    pub max_age_hours: u64,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            settle_ms: 5000,
+            interval_ms: 0,
+            max_age_hours: 72,
         }
     }
 }
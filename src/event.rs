@@ -1,10 +1,40 @@
 use std::time::Duration;
 
 use color_eyre::eyre::Result;
-use crossterm::event::{Event as CrosstermEvent, KeyEvent, KeyEventKind};
+use crossterm::event::{
+    Event as CrosstermEvent, KeyEvent, KeyEventKind, MouseEvent, MouseEventKind,
+};
 use futures::{FutureExt, StreamExt};
 use tokio::sync::mpsc;
 
+use crate::git::types::{FileBlobs, ProgressLine, Snapshot, SnapshotChanges, StatusUpdate};
+use crate::toast::Toast;
+use crate::watcher::service::WatcherStatus;
+
+/// What a file system change affects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeScope {
+    /// Only these paths (relative to the repository root) changed
+    Paths(Vec<String>),
+    /// Git metadata changed (index, HEAD, refs), so everything must be refreshed
+    Full,
+}
+
+impl ChangeScope {
+    /// Combine two scopes into one that covers both
+    pub fn merge(self, other: ChangeScope) -> ChangeScope {
+        match (self, other) {
+            (ChangeScope::Paths(mut paths), ChangeScope::Paths(other)) => {
+                paths.extend(other);
+                paths.sort();
+                paths.dedup();
+                ChangeScope::Paths(paths)
+            }
+            _ => ChangeScope::Full,
+        }
+    }
+}
+
 /// Application events
 #[derive(Debug, Clone)]
 pub enum Event {
@@ -12,13 +42,78 @@ pub enum Event {
     Tick,
     /// Keyboard input
     Key(KeyEvent),
+    /// Mouse input (plain pointer movement is not reported)
+    Mouse(MouseEvent),
     /// Terminal resize
     #[allow(dead_code)]
     Resize(u16, u16),
     /// File system change detected
-    FileChange,
-    /// Diff content is ready
-    DiffReady(String),
+    FileChange(ChangeScope),
+    /// Diff content is ready for a diff request
+    DiffReady {
+        /// Generation of the request (stale results are dropped)
+        generation: u64,
+        /// Path the diff was requested for
+        path: String,
+        /// Whether the staged diff was requested
+        staged: bool,
+        /// The diff, or a notification describing the failure
+        result: std::result::Result<String, Toast>,
+    },
+    /// A status refresh finished
+    StatusReady {
+        /// Generation of the request (stale results are dropped)
+        generation: u64,
+        /// The update, or a notification describing the failure
+        result: std::result::Result<StatusUpdate, Toast>,
+    },
+    /// Blobs of the files touched by a timeline entry were captured
+    TimelineBlobs {
+        /// Id of the timeline entry
+        id: u64,
+        /// The blobs in the order of the entry's listed files, or a notification describing the failure
+        result: std::result::Result<Vec<FileBlobs>, Toast>,
+    },
+    /// The diff of the selected timeline file is ready
+    TimelineDiffReady {
+        /// Generation of the request (stale results are dropped)
+        generation: u64,
+        /// The diff, or a notification describing the failure
+        result: std::result::Result<String, Toast>,
+    },
+    /// A background snapshot finished (None if nothing changed since the latest one)
+    SnapshotTaken(std::result::Result<Option<Snapshot>, Toast>),
+    /// The snapshot list for the snapshot browser is ready
+    SnapshotsLoaded {
+        /// Generation of the request (stale results are dropped)
+        generation: u64,
+        /// Snapshots newest first, or a notification describing the failure
+        result: std::result::Result<Vec<Snapshot>, Toast>,
+    },
+    /// The changes since the selected snapshot are ready
+    SnapshotChangesReady {
+        /// Generation of the request (stale results are dropped)
+        generation: u64,
+        /// The changes, or a notification describing the failure
+        result: std::result::Result<SnapshotChanges, Toast>,
+    },
+    /// The diff of a file since the selected snapshot is ready
+    SnapshotDiffReady {
+        /// Generation of the request (stale results are dropped)
+        generation: u64,
+        /// The diff, or a notification describing the failure
+        result: std::result::Result<String, Toast>,
+    },
+    /// A restore or prune from the snapshot browser finished (a summary on success)
+    SnapshotActionFinished(std::result::Result<String, Toast>),
+    /// A stage/unstage finished (a notification describing the failure, if any)
+    StageFinished(std::result::Result<(), Toast>),
+    /// Progress output from a running push/pull
+    RemoteProgress(ProgressLine),
+    /// A push/pull finished (summary on success, readable error on failure)
+    RemoteFinished(std::result::Result<String, String>),
+    /// The file watcher changed backend or ran into problems
+    WatcherStatus(WatcherStatus),
 }
 
 /// Event handler that manages event polling and distribution
@@ -35,16 +130,32 @@ pub struct EventHandler {
 impl EventHandler {
     /// Create a new event handler with the specified tick rate
     pub fn new(tick_rate: Duration) -> Self {
+        Self::spawn(tick_rate, true)
+    }
+
+    /// Create an event handler that only produces ticks (no terminal input)
+    #[cfg(test)]
+    pub fn without_terminal(tick_rate: Duration) -> Self {
+        Self::spawn(tick_rate, false)
+    }
+
+    fn spawn(tick_rate: Duration, read_terminal: bool) -> Self {
         let (sender, receiver) = mpsc::unbounded_channel();
         let sender_clone = sender.clone();
 
         let task = tokio::spawn(async move {
-            let mut reader = crossterm::event::EventStream::new();
+            let mut reader = read_terminal.then(crossterm::event::EventStream::new);
             let mut tick_interval = tokio::time::interval(tick_rate);
 
             loop {
                 let tick = tick_interval.tick();
-                let crossterm_event = reader.next().fuse();
+                let crossterm_event = async {
+                    match reader.as_mut() {
+                        Some(reader) => reader.next().await,
+                        None => std::future::pending().await,
+                    }
+                }
+                .fuse();
 
                 tokio::select! {
                     _ = tick => {
@@ -53,20 +164,21 @@ impl EventHandler {
                         }
                     }
                     Some(Ok(evt)) = crossterm_event => {
-                        match evt {
-                            CrosstermEvent::Key(key) => {
-                                if key.kind == KeyEventKind::Press {
-                                    if sender_clone.send(Event::Key(key)).is_err() {
-                                        break;
-                                    }
-                                }
+                        let event = match evt {
+                            CrosstermEvent::Key(key) if key.kind == KeyEventKind::Press => {
+                                Some(Event::Key(key))
+                            }
+                            CrosstermEvent::Mouse(mouse) if mouse.kind != MouseEventKind::Moved => {
+                                Some(Event::Mouse(mouse))
                             }
-                            CrosstermEvent::Resize(w, h) => {
-                                if sender_clone.send(Event::Resize(w, h)).is_err() {
-                                    break;
-                                }
+                            CrosstermEvent::Resize(w, h) => Some(Event::Resize(w, h)),
+                            _ => None,
+                        };
+
+                        if let Some(event) = event {
+                            if sender_clone.send(event).is_err() {
+                                break;
                             }
-                            _ => {}
                         }
                     }
                 }
@@ -93,3 +205,19 @@ impl EventHandler {
             .ok_or_else(|| color_eyre::eyre::eyre!("Event channel closed"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_change_scopes() {
+        let a = ChangeScope::Paths(vec!["b".to_string(), "a".to_string()]);
+        let b = ChangeScope::Paths(vec!["a".to_string(), "c".to_string()]);
+        assert_eq!(
+            a.clone().merge(b),
+            ChangeScope::Paths(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+        );
+        assert_eq!(a.merge(ChangeScope::Full), ChangeScope::Full);
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::git::types::FileStatus;
+
+/// How the changed files of a section are laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileView {
+    /// One line per file with its full path
+    Flat,
+    /// Files grouped under collapsible directory nodes
+    Tree,
+}
+
+/// A directory node in the tree view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirRow {
+    /// Directory path relative to the repository root, with a trailing `/`
+    pub path: String,
+    /// Label shown for the node (directories with a single subdirectory are joined)
+    pub name: String,
+    /// Nesting level (0 at the root)
+    pub depth: usize,
+    /// Whether the contents are hidden
+    pub collapsed: bool,
+    /// Number of changed files below the directory
+    pub files: usize,
+    /// Lines added below the directory
+    pub lines_added: usize,
+    /// Lines deleted below the directory
+    pub lines_deleted: usize,
+}
+
+/// One line of the file list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileRow {
+    /// A directory node (tree view only)
+    Dir(DirRow),
+    /// A changed file
+    File {
+        /// Index into the section's file list
+        index: usize,
+        /// Nesting level (0 at the root)
+        depth: usize,
+        /// Label shown for the file (full path in the flat view, file name in the tree)
+        name: String,
+    },
+}
+
+/// Directory contents while building the tree
+#[derive(Debug, Default)]
+struct Node {
+    dirs: BTreeMap<String, Node>,
+    /// (file name, index into the file list)
+    files: Vec<(String, usize)>,
+    count: usize,
+    lines_added: usize,
+    lines_deleted: usize,
+}
+
+/// Lay out files of a section, given with their index in the section, as visible rows
+///
+/// In the tree view, the contents of directories listed in `collapsed`
+/// (paths with a trailing `/`) are left out.
+pub fn build_rows<'a>(
+    files: impl IntoIterator<Item = (usize, &'a FileStatus)>,
+    view: FileView,
+    collapsed: &BTreeSet<String>,
+) -> Vec<FileRow> {
+    if view == FileView::Flat {
+        return files
+            .into_iter()
+            .map(|(index, file)| FileRow::File {
+                index,
+                depth: 0,
+                name: file.path.clone(),
+            })
+            .collect();
+    }
+
+    let mut root = Node::default();
+    for (index, file) in files {
+        // Collapsed untracked directories (`build/`) are leaves, not nodes
+        let trimmed = file.path.trim_end_matches('/');
+        let (dirs, name) = match trimmed.rsplit_once('/') {
+            Some((dirs, name)) => (Some(dirs), name),
+            None => (None, trimmed),
+        };
+        let name = format!("{}{}", name, &file.path[trimmed.len()..]);
+
+        let mut node = &mut root;
+        for component in dirs.into_iter().flat_map(|dirs| dirs.split('/')) {
+            node = node.dirs.entry(component.to_string()).or_default();
+            node.count += 1;
+            node.lines_added += file.lines_added;
+            node.lines_deleted += file.lines_deleted;
+        }
+        node.files.push((name, index));
+    }
+
+    let mut rows = Vec::new();
+    push_rows(&root, "", 0, collapsed, &mut rows);
+    rows
+}
+
+/// Append the rows of a directory's contents, subdirectories first
+fn push_rows(
+    node: &Node,
+    prefix: &str,
+    depth: usize,
+    collapsed: &BTreeSet<String>,
+    rows: &mut Vec<FileRow>,
+) {
+    for (name, child) in &node.dirs {
+        // Join chains like `src/deep/` into one node
+        let mut name = name.clone();
+        let mut child = child;
+        while child.files.is_empty() && child.dirs.len() == 1 {
+            let Some((next_name, next)) = child.dirs.iter().next() else {
+                break;
+            };
+            name = format!("{}/{}", name, next_name);
+            child = next;
+        }
+
+        let path = format!("{}{}/", prefix, name);
+        let is_collapsed = collapsed.contains(&path);
+        rows.push(FileRow::Dir(DirRow {
+            path: path.clone(),
+            name: format!("{}/", name),
+            depth,
+            collapsed: is_collapsed,
+            files: child.count,
+            lines_added: child.lines_added,
+            lines_deleted: child.lines_deleted,
+        }));
+        if !is_collapsed {
+            push_rows(child, &path, depth + 1, collapsed, rows);
+        }
+    }
+
+    for (name, index) in &node.files {
+        rows.push(FileRow::File {
+            index: *index,
+            depth,
+            name: name.clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::types::FileStatusType;
+
+    fn file(path: &str, added: usize) -> FileStatus {
+        let mut file = FileStatus::new(path.to_string(), FileStatusType::Modified, false);
+        file.lines_added = added;
+        file
+    }
+
+    fn labels(rows: &[FileRow]) -> Vec<String> {
+        rows.iter()
+            .map(|row| match row {
+                FileRow::Dir(dir) => format!(
+                    "{}{} ({}, +{})",
+                    "  ".repeat(dir.depth),
+                    dir.name,
+                    dir.files,
+                    dir.lines_added
+                ),
+                FileRow::File { depth, name, .. } => format!("{}{}", "  ".repeat(*depth), name),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_tree_rolls_up_counts_and_collapses() {
+        let files = [
+            file("README.md", 1),
+            file("build/", 0),
+            file("src/app.rs", 2),
+            file("src/ui/deep/list.rs", 3),
+            file("src/ui/deep/view.rs", 4),
+        ];
+
+        let rows = build_rows(files.iter().enumerate(), FileView::Tree, &BTreeSet::new());
+        assert_eq!(
+            labels(&rows),
+            vec![
+                "src/ (3, +9)",
+                "  ui/deep/ (2, +7)",
+                "    list.rs",
+                "    view.rs",
+                "  app.rs",
+                "README.md",
+                "build/",
+            ]
+        );
+        assert_eq!(
+            rows[3],
+            FileRow::File {
+                index: 4,
+                depth: 2,
+                name: "view.rs".to_string()
+            }
+        );
+
+        let collapsed = BTreeSet::from(["src/ui/deep/".to_string()]);
+        let rows = build_rows(files.iter().enumerate(), FileView::Tree, &collapsed);
+        assert_eq!(
+            labels(&rows),
+            vec![
+                "src/ (3, +9)",
+                "  ui/deep/ (2, +7)",
+                "  app.rs",
+                "README.md",
+                "build/"
+            ]
+        );
+
+        let rows = build_rows(files.iter().enumerate(), FileView::Flat, &collapsed);
+        assert_eq!(labels(&rows)[3], "src/ui/deep/list.rs");
+    }
+}
@@ -0,0 +1,149 @@
+use std::path::Path;
+
+use crate::git::types::{FileStatus, FileStatusType};
+
+/// Narrows the file lists by fuzzy path match, status type and extension
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileFilter {
+    /// Fuzzy query; every whitespace-separated word must match
+    pub query: String,
+    /// Only show files with this status
+    pub status: Option<FileStatusType>,
+    /// Only show files with this extension (without the dot)
+    pub extension: Option<String>,
+}
+
+impl FileFilter {
+    /// Whether any criterion is set
+    pub fn is_active(&self) -> bool {
+        !self.query.trim().is_empty() || self.status.is_some() || self.extension.is_some()
+    }
+
+    /// Match a file, returning the positions (char indices into the path) matched by the query
+    pub fn matches(&self, file: &FileStatus) -> Option<Vec<usize>> {
+        if self.status.is_some_and(|status| status != file.status) {
+            return None;
+        }
+        if let Some(extension) = &self.extension {
+            if extension_of(&file.path) != Some(extension.as_str()) {
+                return None;
+            }
+        }
+
+        let mut positions = Vec::new();
+        for word in self.query.split_whitespace() {
+            positions.extend(fuzzy_match(word, &file.path)?);
+        }
+        positions.sort_unstable();
+        positions.dedup();
+        Some(positions)
+    }
+
+    /// Switch to the next status filter (all statuses after the last one)
+    pub fn cycle_status(&mut self) {
+        let all = FileStatusType::ALL;
+        self.status = match self.status {
+            None => Some(all[0]),
+            Some(status) => all
+                .iter()
+                .position(|s| *s == status)
+                .and_then(|i| all.get(i + 1))
+                .copied(),
+        };
+    }
+
+    /// Switch to the next of the given extensions (sorted; all extensions after the last one)
+    pub fn cycle_extension(&mut self, extensions: &[String]) {
+        self.extension = match &self.extension {
+            None => extensions.first().cloned(),
+            Some(current) => extensions
+                .iter()
+                .find(|extension| *extension > current)
+                .cloned(),
+        };
+    }
+}
+
+/// Extension of a path (without the dot), if it has one
+pub fn extension_of(path: &str) -> Option<&str> {
+    Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+}
+
+/// Match `query` as a subsequence of `text`
+///
+/// Case-insensitive unless the query contains uppercase letters. Returns the
+/// char indices of the matched characters, preferring a contiguous match and
+/// matches in the file name.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<Vec<usize>> {
+    let case_sensitive = query.chars().any(char::is_uppercase);
+    let normalize = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_ascii_lowercase()
+        }
+    };
+    let text: Vec<char> = text.chars().map(normalize).collect();
+    let query: Vec<char> = query.chars().map(normalize).collect();
+
+    if query.is_empty() {
+        return Some(Vec::new());
+    }
+    if let Some(start) = text
+        .windows(query.len())
+        .rposition(|window| window == query)
+    {
+        return Some((start..start + query.len()).collect());
+    }
+
+    // Match backwards so the characters land as far right (in the file name) as possible
+    let mut positions = Vec::with_capacity(query.len());
+    let mut end = text.len();
+    for q in query.iter().rev() {
+        let found = text[..end].iter().rposition(|c| c == q)?;
+        positions.push(found);
+        end = found;
+    }
+    positions.reverse();
+    Some(positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match_prefers_file_name_and_smart_case() {
+        assert_eq!(fuzzy_match("app", "src/app.rs"), Some(vec![4, 5, 6]));
+        assert_eq!(fuzzy_match("sar", "src/app.rs"), Some(vec![0, 4, 8]));
+        assert_eq!(fuzzy_match("APP", "src/app.rs"), None);
+        assert_eq!(fuzzy_match("READ", "README.md"), Some(vec![0, 1, 2, 3]));
+        assert_eq!(fuzzy_match("xyz", "src/app.rs"), None);
+    }
+
+    #[test]
+    fn test_filter_combines_query_status_and_extension() {
+        let file = FileStatus::new(
+            "src/ui/list.rs".to_string(),
+            FileStatusType::Modified,
+            false,
+        );
+        let mut filter = FileFilter {
+            query: "ui rs".to_string(),
+            ..FileFilter::default()
+        };
+        assert_eq!(filter.matches(&file), Some(vec![4, 5, 12, 13]));
+
+        filter.extension = Some("rs".to_string());
+        assert!(filter.matches(&file).is_some());
+        filter.status = Some(FileStatusType::Added);
+        assert!(filter.matches(&file).is_none());
+
+        filter.cycle_extension(&["md".to_string(), "rs".to_string()]);
+        assert_eq!(filter.extension, None);
+        filter.cycle_extension(&["md".to_string(), "rs".to_string()]);
+        assert_eq!(filter.extension.as_deref(), Some("md"));
+    }
+}
@@ -0,0 +1,155 @@
+//! Refresh latency benchmark over synthetic repositories
+//!
+//! Run with `cargo test --release refresh_benchmark -- --ignored --nocapture`.
+//! `GFM_BENCH_FILES` sets the number of tracked files (default 20000) and
+//! `GFM_BENCH_MAX_MS` fails the run if the median full refresh is slower.
+
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use super::service::GitService;
+use super::types::StatusOptions;
+
+/// Files per generated directory
+const FILES_PER_DIR: usize = 100;
+
+/// Timed runs per measurement
+const ITERATIONS: usize = 7;
+
+/// Run git in `dir` with a fixed identity, panicking on failure
+fn git(dir: &Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .args(["-c", "user.name=gfm", "-c", "user.email=gfm@example.com"])
+        .args([
+            "-c",
+            "init.defaultBranch=main",
+            "-c",
+            "commit.gpgsign=false",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .expect("failed to run git");
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+/// Path of the i-th generated file
+fn file_path(i: usize) -> String {
+    format!("dir_{:04}/file_{:06}.txt", i / FILES_PER_DIR, i)
+}
+
+/// Create a committed repository with `files` files, then dirty a small part of it
+fn generate_repo(root: &Path, files: usize) {
+    git(root, &["init", "-q"]);
+
+    for i in 0..files {
+        let path = root.join(file_path(i));
+        if i % FILES_PER_DIR == 0 {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        }
+        std::fs::write(&path, format!("line one of {}\nline two\n", i)).unwrap();
+    }
+
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "synthetic"]);
+
+    // 1% modified, a tenth of those staged, plus 1% untracked files
+    let changed = (files / 100).max(1);
+    for i in 0..changed {
+        let path = file_path(i * 100 % files);
+        std::fs::write(root.join(&path), "changed\n").unwrap();
+        if i % 10 == 0 {
+            git(root, &["add", &path]);
+        }
+        std::fs::write(root.join(format!("untracked_{:06}.txt", i)), "new\n").unwrap();
+    }
+}
+
+/// Time `ITERATIONS` runs of `f`, returning (min, median, max)
+async fn measure<F, Fut>(mut f: F) -> (Duration, Duration, Duration)
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    // Warm up the filesystem cache and git's index stat data
+    f().await;
+
+    let mut samples = Vec::with_capacity(ITERATIONS);
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        f().await;
+        samples.push(start.elapsed());
+    }
+    samples.sort();
+
+    (samples[0], samples[ITERATIONS / 2], samples[ITERATIONS - 1])
+}
+
+#[tokio::test]
+#[ignore = "benchmark; run explicitly with --ignored"]
+async fn refresh_benchmark() {
+    let files: usize = std::env::var("GFM_BENCH_FILES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(20_000);
+
+    let dir = tempfile::tempdir().unwrap();
+    let setup = Instant::now();
+    generate_repo(dir.path(), files);
+    println!(
+        "generated {} files in {}ms",
+        files,
+        setup.elapsed().as_millis()
+    );
+
+    let service = GitService::new(dir.path().to_path_buf());
+    let status = service.get_status(&StatusOptions::default()).await.unwrap();
+    println!(
+        "status: {} staged, {} unstaged",
+        status.staged_files.len(),
+        status.unstaged_files.len()
+    );
+
+    let full = measure(|| async {
+        service.get_status(&StatusOptions::default()).await.unwrap();
+    })
+    .await;
+
+    let paths = vec![file_path(0)];
+    let incremental = measure(|| async {
+        service
+            .get_status_for_paths(&StatusOptions::default(), &paths)
+            .await
+            .unwrap();
+    })
+    .await;
+
+    println!(
+        "{:<24} {:>8} {:>8} {:>8}",
+        "refresh", "min", "median", "max"
+    );
+    for (name, (min, median, max)) in [("full", full), ("incremental (1 path)", incremental)] {
+        println!(
+            "{:<24} {:>6}ms {:>6}ms {:>6}ms",
+            name,
+            min.as_millis(),
+            median.as_millis(),
+            max.as_millis()
+        );
+    }
+
+    if let Some(max_ms) = std::env::var("GFM_BENCH_MAX_MS")
+        .ok()
+        .and_then(|v| v.parse::<u128>().ok())
+    {
+        assert!(
+            full.1.as_millis() <= max_ms,
+            "median full refresh took {}ms (limit {}ms)",
+            full.1.as_millis(),
+            max_ms
+        );
+    }
+}
@@ -1,2 +1,8 @@
+#[cfg(test)]
+mod bench;
+pub mod parser;
+pub mod pool;
+pub mod progress;
+pub mod runner;
 pub mod service;
 pub mod types;
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use super::types::{BranchInfo, FileStatus, FileStatusType};
+
+/// Everything reported by a single `git status --porcelain=v2 --branch --show-stash -z`
+#[derive(Debug, Clone, Default)]
+pub struct PorcelainStatus {
+    /// Branch information from the `# branch.*` headers
+    pub branch: BranchInfo,
+    /// Entries with changes in the index
+    pub staged: Vec<FileStatus>,
+    /// Entries with changes in the worktree, unmerged and untracked entries
+    pub unstaged: Vec<FileStatus>,
+}
+
+/// Parse NUL-separated porcelain v2 status output
+///
+/// Rename and copy entries (`2 ...`) are followed by an extra NUL-terminated
+/// field holding the original path.
+pub fn parse_porcelain_v2(output: &str) -> PorcelainStatus {
+    let mut status = PorcelainStatus::default();
+    let mut records = output.split('\0').filter(|r| !r.is_empty());
+
+    while let Some(record) = records.next() {
+        if let Some(header) = record.strip_prefix("# ") {
+            parse_header(header, &mut status.branch);
+        } else if let Some(entry) = record.strip_prefix("1 ") {
+            // 1 XY sub mH mI mW hH hI path
+            let fields: Vec<&str> = entry.splitn(8, ' ').collect();
+            if fields.len() == 8 {
+                push_changed_entry(fields[0], fields[7], None, &mut status);
+            }
+        } else if let Some(entry) = record.strip_prefix("2 ") {
+            // 2 XY sub mH mI mW hH hI Xscore path NUL origPath
+            let fields: Vec<&str> = entry.splitn(9, ' ').collect();
+            let old_path = records.next();
+            if fields.len() == 9 {
+                push_changed_entry(fields[0], fields[8], old_path, &mut status);
+            }
+        } else if let Some(entry) = record.strip_prefix("u ") {
+            // u XY sub m1 m2 m3 mW h1 h2 h3 path
+            let fields: Vec<&str> = entry.splitn(10, ' ').collect();
+            if fields.len() == 10 {
+                status.unstaged.push(FileStatus::new(
+                    fields[9].to_string(),
+                    FileStatusType::Unmerged,
+                    false,
+                ));
+            }
+        } else if let Some(path) = record.strip_prefix("? ") {
+            status.unstaged.push(FileStatus::new(
+                path.to_string(),
+                FileStatusType::Untracked,
+                false,
+            ));
+        }
+    }
+
+    status
+}
+
+/// Parse a `# branch.*` or `# stash` header into the branch info
+fn parse_header(header: &str, info: &mut BranchInfo) {
+    if let Some(name) = header.strip_prefix("branch.head ") {
+        info.name = name.to_string();
+    } else if let Some(upstream) = header.strip_prefix("branch.upstream ") {
+        info.upstream = Some(upstream.to_string());
+    } else if let Some(ab) = header.strip_prefix("branch.ab ") {
+        let parts: Vec<&str> = ab.split_whitespace().collect();
+        if parts.len() >= 2 {
+            info.ahead = parts[0].trim_start_matches('+').parse().unwrap_or(0);
+            info.behind = parts[1].trim_start_matches('-').parse().unwrap_or(0);
+        }
+    } else if let Some(count) = header.strip_prefix("stash ") {
+        info.stash_count = count.trim().parse().unwrap_or(0);
+    }
+}
+
+/// Split a changed entry into its staged and unstaged halves
+fn push_changed_entry(xy: &str, path: &str, old_path: Option<&str>, status: &mut PorcelainStatus) {
+    let mut codes = xy.chars();
+    let index_status = codes.next().unwrap_or('.');
+    let worktree_status = codes.next().unwrap_or('.');
+
+    // Staged changes (index)
+    if let Some(status_type) = FileStatusType::from_code(index_status) {
+        let mut file = FileStatus::new(path.to_string(), status_type, true);
+        file.old_path = old_path.map(str::to_string);
+        status.staged.push(file);
+    }
+
+    // Unstaged changes (worktree)
+    if let Some(status_type) = FileStatusType::from_code(worktree_status) {
+        status
+            .unstaged
+            .push(FileStatus::new(path.to_string(), status_type, false));
+    }
+}
+
+/// Parse NUL-separated `git diff --numstat -z` output into per-path line counts
+///
+/// Binary files (`-\t-`) count as zero lines. Renames are keyed by their new path.
+pub fn parse_numstat(output: &str) -> HashMap<String, (usize, usize)> {
+    let mut stats = HashMap::new();
+    let mut records = output.split('\0');
+
+    while let Some(record) = records.next() {
+        let mut fields = record.splitn(3, '\t');
+        let (Some(added), Some(deleted), Some(path)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+
+        let path = if path.is_empty() {
+            // Rename: the old and new paths follow as separate records
+            let _old_path = records.next();
+            match records.next() {
+                Some(new_path) => new_path,
+                None => continue,
+            }
+        } else {
+            path
+        };
+
+        let added = added.parse().unwrap_or(0);
+        let deleted = deleted.parse().unwrap_or(0);
+        stats.insert(path.to_string(), (added, deleted));
+    }
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_porcelain_v2_entries_and_headers() {
+        let output = [
+            "# branch.oid 1234567890abcdef",
+            "# branch.head main",
+            "# branch.upstream origin/main",
+            "# branch.ab +2 -1",
+            "# stash 3",
+            "1 MM N... 100644 100644 100644 abc def src/with space.rs",
+            "2 R. N... 100644 100644 100644 abc def R100 new name.rs",
+            "old name.rs",
+            "u UU N... 100644 100644 100644 100644 a b c conflict.rs",
+            "? untracked dir/file.txt",
+            "",
+        ]
+        .join("\0");
+
+        let status = parse_porcelain_v2(&output);
+
+        assert_eq!(status.branch.name, "main");
+        assert_eq!(status.branch.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((status.branch.ahead, status.branch.behind), (2, 1));
+        assert_eq!(status.branch.stash_count, 3);
+
+        let staged: Vec<(&str, FileStatusType)> = status
+            .staged
+            .iter()
+            .map(|f| (f.path.as_str(), f.status))
+            .collect();
+        assert_eq!(
+            staged,
+            vec![
+                ("src/with space.rs", FileStatusType::Modified),
+                ("new name.rs", FileStatusType::Renamed),
+            ]
+        );
+        assert_eq!(status.staged[1].old_path.as_deref(), Some("old name.rs"));
+
+        let unstaged: Vec<(&str, FileStatusType)> = status
+            .unstaged
+            .iter()
+            .map(|f| (f.path.as_str(), f.status))
+            .collect();
+        assert_eq!(
+            unstaged,
+            vec![
+                ("src/with space.rs", FileStatusType::Modified),
+                ("conflict.rs", FileStatusType::Unmerged),
+                ("untracked dir/file.txt", FileStatusType::Untracked),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_numstat_with_renames_and_binaries() {
+        let output = "3\t1\ta.rs\0-\t-\timage.png\0\
+                      5\t0\t\0old.rs\0new.rs\0";
+
+        let stats = parse_numstat(output);
+
+        assert_eq!(stats.get("a.rs"), Some(&(3, 1)));
+        assert_eq!(stats.get("image.png"), Some(&(0, 0)));
+        assert_eq!(stats.get("new.rs"), Some(&(5, 0)));
+        assert_eq!(stats.len(), 3);
+    }
+}
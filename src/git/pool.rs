@@ -0,0 +1,218 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::oneshot;
+
+/// Priority of a git command when competing for a slot in the pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Commands the user is waiting on (stage, diff preview, push/pull)
+    User = 0,
+    /// Status refreshes
+    Status = 1,
+    /// Informational extras (line counts, last commit)
+    Background = 2,
+}
+
+impl Priority {
+    /// All priorities, highest first
+    const ALL: [Priority; 3] = [Priority::User, Priority::Status, Priority::Background];
+}
+
+/// Limits the number of concurrently running git processes
+///
+/// Waiting commands are started highest priority first. One slot is kept free
+/// for [`Priority::User`] commands, so a burst of background work never makes
+/// the user wait behind it.
+#[derive(Debug, Clone)]
+pub struct GitPool {
+    state: Arc<Mutex<PoolState>>,
+}
+
+#[derive(Debug)]
+struct PoolState {
+    /// Maximum number of processes running at once
+    capacity: usize,
+    /// Number of permits currently handed out
+    running: usize,
+    /// Waiters per priority, oldest first
+    waiters: [VecDeque<oneshot::Sender<PoolPermit>>; 3],
+}
+
+impl PoolState {
+    /// Number of slots a command of the given priority may occupy
+    fn limit(&self, priority: Priority) -> usize {
+        match priority {
+            Priority::User => self.capacity,
+            _ if self.capacity > 1 => self.capacity - 1,
+            _ => self.capacity,
+        }
+    }
+
+    /// Whether anyone of at least the given priority is still waiting
+    fn has_waiters_at_or_above(&self, priority: Priority) -> bool {
+        Priority::ALL
+            .iter()
+            .take_while(|p| **p <= priority)
+            .any(|p| self.waiters[*p as usize].iter().any(|tx| !tx.is_closed()))
+    }
+}
+
+/// A slot in the pool, released when dropped
+#[derive(Debug)]
+pub struct PoolPermit {
+    state: Option<Arc<Mutex<PoolState>>>,
+}
+
+impl Drop for PoolPermit {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.take() {
+            if let Ok(mut guard) = state.lock() {
+                guard.running -= 1;
+            }
+            dispatch(&state);
+        }
+    }
+}
+
+/// Hand free slots to the highest-priority waiters
+fn dispatch(state: &Arc<Mutex<PoolState>>) {
+    let Ok(mut guard) = state.lock() else {
+        return;
+    };
+
+    for priority in Priority::ALL {
+        while guard.running < guard.limit(priority) {
+            let Some(tx) = guard.waiters[priority as usize].pop_front() else {
+                break;
+            };
+
+            guard.running += 1;
+            let permit = PoolPermit {
+                state: Some(state.clone()),
+            };
+            if let Err(mut permit) = tx.send(permit) {
+                // The waiter was cancelled; take the slot back without re-dispatching
+                permit.state = None;
+                guard.running -= 1;
+            }
+        }
+    }
+}
+
+impl GitPool {
+    /// Create a pool allowing `capacity` concurrent git processes (at least one)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(PoolState {
+                capacity: capacity.max(1),
+                running: 0,
+                waiters: Default::default(),
+            })),
+        }
+    }
+
+    /// Wait for a free slot
+    ///
+    /// Cancel-safe: dropping the returned future gives up the place in the queue.
+    pub async fn acquire(&self, priority: Priority) -> PoolPermit {
+        let rx = {
+            let Ok(mut guard) = self.state.lock() else {
+                return PoolPermit { state: None };
+            };
+
+            if guard.running < guard.limit(priority) && !guard.has_waiters_at_or_above(priority) {
+                guard.running += 1;
+                return PoolPermit {
+                    state: Some(self.state.clone()),
+                };
+            }
+
+            let (tx, rx) = oneshot::channel();
+            guard.waiters[priority as usize].push_back(tx);
+            rx
+        };
+
+        // Senders are only removed from the queue to be sent a permit
+        rx.await.unwrap_or(PoolPermit { state: None })
+    }
+
+    /// Number of git processes currently running
+    pub fn running(&self) -> usize {
+        self.state.lock().map(|guard| guard.running).unwrap_or(0)
+    }
+
+    /// Number of git commands waiting for a slot
+    pub fn queued(&self) -> usize {
+        self.state
+            .lock()
+            .map(|guard| {
+                guard
+                    .waiters
+                    .iter()
+                    .flatten()
+                    .filter(|tx| !tx.is_closed())
+                    .count()
+            })
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_waiters_are_served_by_priority() {
+        let pool = GitPool::new(2);
+        let first = pool.acquire(Priority::User).await;
+        let second = pool.acquire(Priority::User).await;
+        assert_eq!(pool.running(), 2);
+
+        let background = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.acquire(Priority::Background).await }
+        });
+        tokio::task::yield_now().await;
+        let user = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.acquire(Priority::User).await }
+        });
+        tokio::task::yield_now().await;
+        assert_eq!(pool.queued(), 2);
+
+        // The freed slot goes to the user command even though it queued later
+        drop(first);
+        let user = user.await.unwrap();
+        assert!(!background.is_finished());
+
+        // Background work may not take the last slot reserved for user commands
+        drop(second);
+        tokio::task::yield_now().await;
+        assert!(!background.is_finished());
+
+        drop(user);
+        let _background = background.await.unwrap();
+        assert_eq!(pool.running(), 1);
+        assert_eq!(pool.queued(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_waiter_does_not_leak_a_slot() {
+        let pool = GitPool::new(1);
+        let held = pool.acquire(Priority::User).await;
+
+        let waiter = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.acquire(Priority::Status).await }
+        });
+        tokio::task::yield_now().await;
+        waiter.abort();
+        let _ = waiter.await;
+
+        drop(held);
+        assert_eq!(pool.running(), 0);
+        let _permit = pool.acquire(Priority::Status).await;
+        assert_eq!(pool.running(), 1);
+    }
+}
@@ -0,0 +1,80 @@
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::mpsc;
+
+use super::types::ProgressLine;
+
+/// Forward the stderr of a long-running git command to `progress` line by line
+///
+/// Lines ending in a carriage return are sent as transient, since git
+/// overwrites them with the next update. Blank lines are skipped and reading
+/// stops at the first error. Returns everything forwarded, one line per line.
+pub async fn forward_progress(
+    mut stderr: impl AsyncRead + Unpin,
+    progress: &mpsc::UnboundedSender<ProgressLine>,
+) -> String {
+    let mut collected = String::new();
+    let mut pending = Vec::new();
+    let mut buf = [0u8; 4096];
+
+    while let Ok(n) = stderr.read(&mut buf).await {
+        if n == 0 {
+            break;
+        }
+
+        for &byte in &buf[..n] {
+            if byte == b'\r' || byte == b'\n' {
+                let text = String::from_utf8_lossy(&pending).trim_end().to_string();
+                pending.clear();
+                if text.is_empty() {
+                    continue;
+                }
+                collected.push_str(&text);
+                collected.push('\n');
+                let _ = progress.send(ProgressLine {
+                    text,
+                    transient: byte == b'\r',
+                });
+            } else {
+                pending.push(byte);
+            }
+        }
+    }
+
+    if !pending.is_empty() {
+        let text = String::from_utf8_lossy(&pending).trim_end().to_string();
+        collected.push_str(&text);
+        let _ = progress.send(ProgressLine {
+            text,
+            transient: false,
+        });
+    }
+
+    collected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_carriage_returns_mark_transient_lines() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let stderr: &[u8] = b"Counting: 50%\rCounting: 100%\n\nerror: rejected";
+
+        let collected = forward_progress(stderr, &tx).await;
+        assert_eq!(collected, "Counting: 50%\nCounting: 100%\nerror: rejected");
+
+        let mut lines = Vec::new();
+        while let Ok(line) = rx.try_recv() {
+            lines.push((line.text, line.transient));
+        }
+        assert_eq!(
+            lines,
+            vec![
+                ("Counting: 50%".to_string(), true),
+                ("Counting: 100%".to_string(), false),
+                ("error: rejected".to_string(), false),
+            ]
+        );
+    }
+}
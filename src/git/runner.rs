@@ -0,0 +1,484 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+use super::pool::{GitPool, Priority};
+use super::progress::forward_progress;
+use super::types::ProgressLine;
+
+/// Number of commands kept in the command log
+const COMMAND_LOG_CAPACITY: usize = 200;
+
+/// Maximum number of stderr bytes kept per logged command
+const MAX_LOGGED_STDERR: usize = 8 * 1024;
+
+/// A single git invocation recorded by the runner
+#[derive(Debug, Clone)]
+pub struct CommandRecord {
+    /// Arguments passed to git (without the leading `git`)
+    pub args: Vec<String>,
+    /// When the command was started
+    pub started_at: Instant,
+    /// How long the command took
+    pub duration: Duration,
+    /// Exit code (None if the process could not be spawned or was killed)
+    pub exit_code: Option<i32>,
+    /// Captured stderr (possibly truncated)
+    pub stderr: String,
+}
+
+impl CommandRecord {
+    /// Whether the command exited successfully
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    /// The full command line as it would be typed in a shell
+    pub fn command_line(&self) -> String {
+        format!("git {}", self.args.join(" "))
+    }
+}
+
+/// Ring buffer of recently run git commands, shared between clones
+#[derive(Debug, Clone, Default)]
+pub struct CommandLog {
+    records: Arc<Mutex<VecDeque<CommandRecord>>>,
+}
+
+impl CommandLog {
+    /// Append a record, evicting the oldest one when full
+    fn push(&self, record: CommandRecord) {
+        if let Ok(mut records) = self.records.lock() {
+            if records.len() >= COMMAND_LOG_CAPACITY {
+                records.pop_front();
+            }
+            records.push_back(record);
+        }
+    }
+
+    /// Number of records currently in the log
+    pub fn len(&self) -> usize {
+        self.records
+            .lock()
+            .map(|records| records.len())
+            .unwrap_or(0)
+    }
+
+    /// Whether no commands have been recorded yet
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get a copy of all records, oldest first
+    pub fn snapshot(&self) -> Vec<CommandRecord> {
+        self.records
+            .lock()
+            .map(|records| records.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Error returned when a git command fails, carrying its captured stderr
+#[derive(Debug, Clone)]
+pub struct GitError {
+    /// Readable description of what failed
+    pub message: String,
+    /// Arguments passed to git (without the leading `git`)
+    pub args: Vec<String>,
+    /// Exit code (None if the process could not be spawned or was killed)
+    pub exit_code: Option<i32>,
+    /// Captured stderr
+    pub stderr: String,
+}
+
+impl GitError {
+    /// Build an error for a failed command, appending git's own explanation
+    pub fn from_output(context: &str, args: &[String], output: &Output) -> Self {
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        let message = match explain_stderr(&stderr) {
+            Some(reason) => format!("{}: {}", context, reason),
+            None => context.to_string(),
+        };
+
+        Self {
+            message,
+            args: args.to_vec(),
+            exit_code: output.status.code(),
+            stderr,
+        }
+    }
+}
+
+impl fmt::Display for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for GitError {}
+
+/// Extract the most useful line from git's stderr (the last `fatal:`/`error:` line)
+pub fn explain_stderr(stderr: &str) -> Option<String> {
+    let lines: Vec<&str> = stderr
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+
+    lines
+        .iter()
+        .rev()
+        .find_map(|line| {
+            line.strip_prefix("fatal: ")
+                .or_else(|| line.strip_prefix("error: "))
+        })
+        .or_else(|| lines.last().copied())
+        .map(str::to_string)
+}
+
+/// Concurrency and timeout limits for git commands
+#[derive(Debug, Clone, Copy)]
+pub struct RunnerLimits {
+    /// Maximum number of git processes running at once
+    pub max_concurrent: usize,
+    /// Timeout for local commands
+    pub command_timeout: Duration,
+    /// Timeout for commands talking to a remote (push/pull)
+    pub remote_timeout: Duration,
+}
+
+impl Default for RunnerLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 4,
+            command_timeout: Duration::from_secs(30),
+            remote_timeout: Duration::from_secs(600),
+        }
+    }
+}
+
+/// Central runner for all git invocations
+///
+/// Every command waits for a slot in the shared [`GitPool`], is killed when
+/// it exceeds its timeout, and is recorded in the shared [`CommandLog`] with
+/// its argv, duration, exit code and stderr.
+#[derive(Debug, Clone)]
+pub struct GitRunner {
+    /// Path to the repository
+    repo_path: PathBuf,
+    /// Log of recently run commands
+    log: CommandLog,
+    /// Pool limiting concurrent git processes
+    pool: GitPool,
+    /// Timeouts for local and remote commands
+    limits: RunnerLimits,
+}
+
+impl GitRunner {
+    /// Create a new runner for the given repository path
+    pub fn new(repo_path: PathBuf, limits: RunnerLimits) -> Self {
+        Self {
+            repo_path,
+            log: CommandLog::default(),
+            pool: GitPool::new(limits.max_concurrent),
+            limits,
+        }
+    }
+
+    /// Get the repository path commands run in
+    pub fn repo_path(&self) -> &Path {
+        &self.repo_path
+    }
+
+    /// Get the shared command log
+    pub fn log(&self) -> &CommandLog {
+        &self.log
+    }
+
+    /// Get the shared process pool
+    pub fn pool(&self) -> &GitPool {
+        &self.pool
+    }
+
+    /// Run a git command and return its raw output, whatever the exit code
+    ///
+    /// The git process is killed if it runs longer than the command timeout or
+    /// if the returned future is dropped (e.g. when the task awaiting it is
+    /// aborted because its result is no longer needed).
+    pub async fn output(&self, priority: Priority, args: &[&str]) -> Result<Output, GitError> {
+        self.output_with_env(priority, &[], args).await
+    }
+
+    /// Like [`GitRunner::output`], with extra environment variables for git
+    pub async fn output_with_env(
+        &self,
+        priority: Priority,
+        env: &[(&str, &str)],
+        args: &[&str],
+    ) -> Result<Output, GitError> {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        let _permit = self.pool.acquire(priority).await;
+        let started_at = Instant::now();
+
+        let command = Command::new("git")
+            .args(&args)
+            .current_dir(&self.repo_path)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .envs(env.iter().copied())
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output();
+
+        let result = match tokio::time::timeout(self.limits.command_timeout, command).await {
+            Ok(result) => result,
+            Err(_) => return Err(self.timed_out(args, started_at, self.limits.command_timeout)),
+        };
+
+        match result {
+            Ok(output) => {
+                self.log.push(CommandRecord {
+                    args,
+                    started_at,
+                    duration: started_at.elapsed(),
+                    exit_code: output.status.code(),
+                    stderr: truncate_stderr(&String::from_utf8_lossy(&output.stderr)),
+                });
+                Ok(output)
+            }
+            Err(e) => Err(self.spawn_failed(args, started_at, e)),
+        }
+    }
+
+    /// Run a git command, returning stdout on success or a [`GitError`] on failure
+    pub async fn run(
+        &self,
+        priority: Priority,
+        context: &str,
+        args: &[&str],
+    ) -> Result<String, GitError> {
+        self.run_with_env(priority, context, &[], args).await
+    }
+
+    /// Like [`GitRunner::run`], with extra environment variables for git
+    pub async fn run_with_env(
+        &self,
+        priority: Priority,
+        context: &str,
+        env: &[(&str, &str)],
+        args: &[&str],
+    ) -> Result<String, GitError> {
+        let output = self.output_with_env(priority, env, args).await?;
+
+        if !output.status.success() {
+            let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+            return Err(GitError::from_output(context, &args, &output));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Run a user-initiated remote command, streaming stderr line by line to `progress`
+    ///
+    /// The remote timeout applies instead of the command timeout. On failure,
+    /// the returned error carries the full collected stderr and a message
+    /// built by `describe` from it.
+    pub async fn run_streaming(
+        &self,
+        args: &[&str],
+        progress: mpsc::UnboundedSender<ProgressLine>,
+        describe: impl FnOnce(&str) -> String,
+    ) -> Result<(), GitError> {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        let _permit = self.pool.acquire(Priority::User).await;
+        let started_at = Instant::now();
+
+        let mut child = match Command::new("git")
+            .args(&args)
+            .current_dir(&self.repo_path)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => return Err(self.spawn_failed(args, started_at, e)),
+        };
+
+        let stream = async {
+            let collected = match child.stderr.take() {
+                Some(stderr) => forward_progress(stderr, &progress).await,
+                None => String::new(),
+            };
+            child.wait().await.map(|status| (status, collected))
+        };
+
+        let (exit_code, collected) =
+            match tokio::time::timeout(self.limits.remote_timeout, stream).await {
+                Ok(Ok((status, collected))) => (status.code(), collected),
+                Ok(Err(e)) => return Err(self.spawn_failed(args, started_at, e)),
+                Err(_) => return Err(self.timed_out(args, started_at, self.limits.remote_timeout)),
+            };
+
+        self.log.push(CommandRecord {
+            args: args.clone(),
+            started_at,
+            duration: started_at.elapsed(),
+            exit_code,
+            stderr: truncate_stderr(&collected),
+        });
+
+        if exit_code == Some(0) {
+            Ok(())
+        } else {
+            Err(GitError {
+                message: describe(&collected),
+                args,
+                exit_code,
+                stderr: collected,
+            })
+        }
+    }
+
+    /// Record and build the error for a command that was killed after `timeout`
+    fn timed_out(&self, args: Vec<String>, started_at: Instant, timeout: Duration) -> GitError {
+        let stderr = format!("killed after {}s without finishing", timeout.as_secs_f32());
+        self.log.push(CommandRecord {
+            args: args.clone(),
+            started_at,
+            duration: started_at.elapsed(),
+            exit_code: None,
+            stderr: stderr.clone(),
+        });
+
+        GitError {
+            message: format!(
+                "git {} timed out after {}s",
+                subcommand(&args),
+                timeout.as_secs_f32()
+            ),
+            args,
+            exit_code: None,
+            stderr,
+        }
+    }
+
+    /// Record and build the error for a command that could not be run
+    fn spawn_failed(&self, args: Vec<String>, started_at: Instant, e: std::io::Error) -> GitError {
+        let stderr = e.to_string();
+        self.log.push(CommandRecord {
+            args: args.clone(),
+            started_at,
+            duration: started_at.elapsed(),
+            exit_code: None,
+            stderr: stderr.clone(),
+        });
+
+        GitError {
+            message: format!("Failed to run git: {}", stderr),
+            args,
+            exit_code: None,
+            stderr,
+        }
+    }
+}
+
+/// Get the git subcommand from an argument list, skipping global options
+fn subcommand(args: &[String]) -> &str {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-c" | "-C" => {
+                args.next();
+            }
+            option if option.starts_with('-') => {}
+            command => return command,
+        }
+    }
+    ""
+}
+
+/// Keep at most `MAX_LOGGED_STDERR` bytes of stderr (the tail, where errors are)
+fn truncate_stderr(stderr: &str) -> String {
+    if stderr.len() <= MAX_LOGGED_STDERR {
+        return stderr.to_string();
+    }
+
+    let mut start = stderr.len() - MAX_LOGGED_STDERR;
+    while !stderr.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("...{}", &stderr[start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_failed_command_is_logged_with_stderr() {
+        let dir = tempfile::tempdir().unwrap();
+        let runner = GitRunner::new(dir.path().to_path_buf(), RunnerLimits::default());
+
+        let err = runner
+            .run(Priority::User, "Failed to get last commit", &["log", "-1"])
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.args, vec!["log", "-1"]);
+        assert_eq!(err.exit_code, Some(128));
+        assert!(err.stderr.contains("not a git repository"));
+        assert!(err.message.starts_with("Failed to get last commit: "));
+
+        let records = runner.log().snapshot();
+        assert_eq!(records.len(), 1);
+        assert!(!records[0].success());
+        assert_eq!(records[0].command_line(), "git log -1");
+        assert!(records[0].stderr.contains("not a git repository"));
+    }
+
+    #[tokio::test]
+    async fn test_hung_command_times_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let limits = RunnerLimits {
+            command_timeout: Duration::from_millis(200),
+            ..RunnerLimits::default()
+        };
+        let runner = GitRunner::new(dir.path().to_path_buf(), limits);
+
+        let started = Instant::now();
+        let err = runner
+            .run(
+                Priority::User,
+                "Failed to hang",
+                &["-c", "alias.hang=!sleep 5", "hang"],
+            )
+            .await
+            .unwrap_err();
+
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(err.exit_code, None);
+        assert!(err.message.starts_with("git hang timed out"));
+        assert_eq!(runner.pool().running(), 0);
+        assert_eq!(runner.log().snapshot()[0].exit_code, None);
+    }
+
+    #[test]
+    fn test_explain_stderr_prefers_fatal_line() {
+        let stderr = "hint: something\nfatal: bad revision 'x'\nhint: more\n";
+        assert_eq!(explain_stderr(stderr).as_deref(), Some("bad revision 'x'"));
+        assert_eq!(
+            explain_stderr("warning: odd\n").as_deref(),
+            Some("warning: odd")
+        );
+        assert_eq!(explain_stderr(""), None);
+    }
+}
@@ -1,124 +1,261 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use color_eyre::eyre::{eyre, Result};
-use tokio::process::Command;
+use tokio::sync::mpsc;
 
-use super::types::{BranchInfo, CommitInfo, FileStatus, FileStatusType, GitStatus};
+use super::parser::{parse_numstat, parse_porcelain_v2, PorcelainStatus};
+use super::pool::{GitPool, Priority};
+use super::runner::{explain_stderr, CommandLog, GitRunner, RunnerLimits};
+use super::types::{
+    CommitInfo, FileBlobs, FileStatus, FileStatusType, GitStatus, ProgressLine, RemoteOperation,
+    Snapshot, SnapshotChanges, StatusOptions,
+};
+
+/// Ref namespace working-tree snapshots are recorded under
+const SNAPSHOT_REFS: &str = "refs/gfm/snapshots/";
+
+/// Identity snapshot commits are made with (they are never pushed)
+const SNAPSHOT_IDENTITY: [(&str, &str); 4] = [
+    ("GIT_AUTHOR_NAME", "gfm"),
+    ("GIT_AUTHOR_EMAIL", "gfm@localhost"),
+    ("GIT_COMMITTER_NAME", "gfm"),
+    ("GIT_COMMITTER_EMAIL", "gfm@localhost"),
+];
 
 /// Service for executing git commands and parsing their output
+#[derive(Debug, Clone)]
 pub struct GitService {
-    /// Path to the repository
-    repo_path: PathBuf,
+    /// Runner used for every git invocation
+    runner: GitRunner,
+    /// Serializes commands that write the index, so they don't fight over index.lock
+    index_lock: Arc<tokio::sync::Mutex<()>>,
+    /// Serializes commands that use the temporary snapshot index
+    snapshot_lock: Arc<tokio::sync::Mutex<()>>,
 }
 
 impl GitService {
-    /// Create a new git service for the given repository path
+    /// Create a new git service for the given repository path with default limits
+    #[cfg(test)]
     pub fn new(repo_path: PathBuf) -> Self {
-        Self { repo_path }
+        Self::with_limits(repo_path, RunnerLimits::default())
+    }
+
+    /// Create a new git service with the given concurrency and timeout limits
+    pub fn with_limits(repo_path: PathBuf, limits: RunnerLimits) -> Self {
+        Self {
+            runner: GitRunner::new(repo_path, limits),
+            index_lock: Arc::default(),
+            snapshot_lock: Arc::default(),
+        }
+    }
+
+    /// Get the log of git commands run by this service (shared between clones)
+    pub fn command_log(&self) -> &CommandLog {
+        self.runner.log()
+    }
+
+    /// Get the pool limiting concurrent git processes (shared between clones)
+    pub fn pool(&self) -> &GitPool {
+        self.runner.pool()
     }
 
     /// Check if the path is a valid git repository
     pub async fn is_git_repo(&self) -> Result<bool> {
-        let output = Command::new("git")
-            .args(["rev-parse", "--git-dir"])
-            .current_dir(&self.repo_path)
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
+        let output = self
+            .runner
+            .output(Priority::User, &["rev-parse", "--git-dir"])
             .await?;
-
-        Ok(output.success())
+        Ok(output.status.success())
     }
 
     /// Get the complete git status for the repository
-    pub async fn get_status(&self) -> Result<GitStatus> {
-        // Run multiple git commands in parallel
-        let (branch_result, commit_result, status_result, diff_staged_result, diff_unstaged_result) = tokio::join!(
-            self.get_branch_info(),
+    ///
+    /// Branch, stash and file information come from a single `git status`
+    /// run; line counts come from the index and worktree numstats, which run
+    /// alongside it. File modification times are read last.
+    pub async fn get_status(&self, options: &StatusOptions) -> Result<GitStatus> {
+        let (status_result, commit_result, stats_result) = tokio::join!(
+            self.get_porcelain_status(options, &[]),
             self.get_last_commit(),
-            self.get_file_statuses(),
-            self.get_diff_stats(true),
-            self.get_diff_stats(false),
+            self.get_diff_stats(&[]),
         );
 
-        let branch = branch_result.unwrap_or_default();
+        let status = status_result?;
         let last_commit = commit_result.ok();
-        let (mut staged_files, mut unstaged_files) = status_result?;
+        let (staged_stats, unstaged_stats) = stats_result;
 
-        // Merge diff stats
-        let staged_stats = diff_staged_result.unwrap_or_default();
-        let unstaged_stats = diff_unstaged_result.unwrap_or_default();
-
-        for file in &mut staged_files {
-            if let Some((added, deleted)) = staged_stats.get(&file.path) {
-                file.lines_added = *added;
-                file.lines_deleted = *deleted;
-            }
-        }
-
-        for file in &mut unstaged_files {
-            if let Some((added, deleted)) = unstaged_stats.get(&file.path) {
-                file.lines_added = *added;
-                file.lines_deleted = *deleted;
-            }
-        }
+        let mut staged_files = status.staged;
+        let mut unstaged_files = status.unstaged;
+        apply_diff_stats(&mut staged_files, &staged_stats);
+        apply_diff_stats(&mut unstaged_files, &unstaged_stats);
+        let (staged_files, unstaged_files) =
+            self.stamp_modified(staged_files, unstaged_files).await?;
 
         Ok(GitStatus {
-            branch,
+            branch: status.branch,
             last_commit,
             staged_files,
             unstaged_files,
         })
     }
 
-    /// Get branch information
-    async fn get_branch_info(&self) -> Result<BranchInfo> {
-        let output = Command::new("git")
-            .args(["status", "-sb", "--porcelain=v2", "--untracked-files=all"])
-            .current_dir(&self.repo_path)
-            .output()
-            .await?;
+    /// Get the staged and unstaged entries for the given paths only
+    ///
+    /// Paths are relative to the repository root; a directory path covers
+    /// everything below it. Used for incremental refreshes.
+    pub async fn get_status_for_paths(
+        &self,
+        options: &StatusOptions,
+        paths: &[String],
+    ) -> Result<(Vec<FileStatus>, Vec<FileStatus>)> {
+        let pathspecs: Vec<&str> = paths.iter().map(String::as_str).collect();
 
-        if !output.status.success() {
-            return Err(eyre!("Failed to get branch info"));
+        let (status_result, stats_result) = tokio::join!(
+            self.get_porcelain_status(options, &pathspecs),
+            self.get_diff_stats(&pathspecs),
+        );
+
+        let status = status_result?;
+        let (staged_stats, unstaged_stats) = stats_result;
+
+        let mut staged_files = status.staged;
+        let mut unstaged_files = status.unstaged;
+        apply_diff_stats(&mut staged_files, &staged_stats);
+        apply_diff_stats(&mut unstaged_files, &unstaged_stats);
+        let (staged_files, unstaged_files) =
+            self.stamp_modified(staged_files, unstaged_files).await?;
+
+        Ok((staged_files, unstaged_files))
+    }
+
+    /// Record each file's modification time on disk, for sorting and follow mode
+    ///
+    /// Runs on a blocking thread so a large status never stalls the runtime.
+    async fn stamp_modified(
+        &self,
+        mut staged: Vec<FileStatus>,
+        mut unstaged: Vec<FileStatus>,
+    ) -> Result<(Vec<FileStatus>, Vec<FileStatus>)> {
+        let repo_path = self.runner.repo_path().to_path_buf();
+        let stamped = tokio::task::spawn_blocking(move || {
+            for file in staged.iter_mut().chain(unstaged.iter_mut()) {
+                file.modified = std::fs::symlink_metadata(repo_path.join(&file.path))
+                    .and_then(|m| m.modified())
+                    .ok();
+            }
+            (staged, unstaged)
+        })
+        .await?;
+        Ok(stamped)
+    }
+
+    /// Run `git status` once for branch, stash and file information,
+    /// optionally limited to `pathspecs`
+    ///
+    /// Collapsed untracked directories listed in `options.expanded_dirs` are
+    /// replaced by the untracked files inside them.
+    async fn get_porcelain_status(
+        &self,
+        options: &StatusOptions,
+        pathspecs: &[&str],
+    ) -> Result<PorcelainStatus> {
+        let untracked = format!("--untracked-files={}", options.untracked.as_arg());
+        // Background reads must not take index.lock away from the user's own git commands
+        let mut args = vec![
+            "--no-optional-locks",
+            "--literal-pathspecs",
+            "status",
+            "--porcelain=v2",
+            "--branch",
+            "--show-stash",
+            "-z",
+            untracked.as_str(),
+        ];
+        if !pathspecs.is_empty() {
+            args.push("--");
+            args.extend_from_slice(pathspecs);
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let mut info = BranchInfo::default();
-
-        for line in stdout.lines() {
-            if line.starts_with("# branch.head ") {
-                info.name = line.trim_start_matches("# branch.head ").to_string();
-            } else if line.starts_with("# branch.upstream ") {
-                info.upstream = Some(line.trim_start_matches("# branch.upstream ").to_string());
-            } else if line.starts_with("# branch.ab ") {
-                let ab = line.trim_start_matches("# branch.ab ");
-                let parts: Vec<&str> = ab.split_whitespace().collect();
-                if parts.len() >= 2 {
-                    info.ahead = parts[0].trim_start_matches('+').parse().unwrap_or(0);
-                    info.behind = parts[1].trim_start_matches('-').parse().unwrap_or(0);
+        let stdout = self
+            .runner
+            .run(Priority::Status, "Failed to get file statuses", &args)
+            .await?;
+
+        let mut status = parse_porcelain_v2(&stdout);
+
+        let expanded: Vec<String> = status
+            .unstaged
+            .iter()
+            .filter(|f| f.is_untracked_dir() && options.expanded_dirs.contains(&f.path))
+            .map(|f| f.path.clone())
+            .collect();
+
+        if !expanded.is_empty() {
+            let dirs: Vec<&str> = expanded.iter().map(String::as_str).collect();
+            let mut files = self
+                .list_untracked_files(&dirs)
+                .await?
+                .into_iter()
+                .peekable();
+            let mut unstaged = Vec::with_capacity(status.unstaged.len());
+
+            for entry in std::mem::take(&mut status.unstaged) {
+                if entry.is_untracked_dir() && expanded.contains(&entry.path) {
+                    // Files come back sorted, so each directory's files are contiguous
+                    while let Some(path) = files.next_if(|p| p.starts_with(&entry.path)) {
+                        unstaged.push(FileStatus::new(path, FileStatusType::Untracked, false));
+                    }
+                } else {
+                    unstaged.push(entry);
                 }
             }
+
+            status.unstaged = unstaged;
         }
 
-        Ok(info)
+        Ok(status)
     }
 
-    /// Get last commit information
-    async fn get_last_commit(&self) -> Result<CommitInfo> {
-        let output = Command::new("git")
-            .args(["log", "-1", "--pretty=format:%h|%an|%s"])
-            .current_dir(&self.repo_path)
-            .output()
+    /// List untracked (non-ignored) files below the given directories, sorted by path
+    async fn list_untracked_files(&self, dirs: &[&str]) -> Result<Vec<String>> {
+        let mut args = vec![
+            "--literal-pathspecs",
+            "ls-files",
+            "--others",
+            "--exclude-standard",
+            "-z",
+            "--",
+        ];
+        args.extend_from_slice(dirs);
+
+        let stdout = self
+            .runner
+            .run(Priority::Status, "Failed to list untracked files", &args)
             .await?;
 
-        if !output.status.success() {
-            return Err(eyre!("Failed to get last commit"));
-        }
+        let mut files: Vec<String> = stdout
+            .split('\0')
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+            .collect();
+        files.sort();
+
+        Ok(files)
+    }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
+    /// Get last commit information
+    async fn get_last_commit(&self) -> Result<CommitInfo> {
+        let stdout = self
+            .runner
+            .run(
+                Priority::Background,
+                "Failed to get last commit",
+                &["log", "-1", "--pretty=format:%h|%an|%s"],
+            )
+            .await?;
         let parts: Vec<&str> = stdout.splitn(3, '|').collect();
 
         if parts.len() < 3 {
@@ -132,199 +269,1160 @@ impl GitService {
         })
     }
 
-    /// Get file statuses (staged and unstaged)
-    async fn get_file_statuses(&self) -> Result<(Vec<FileStatus>, Vec<FileStatus>)> {
-        let output = Command::new("git")
-            .args(["status", "--porcelain=v2", "--untracked-files=all"])
-            .current_dir(&self.repo_path)
-            .output()
+    /// Get line counts for staged and unstaged changes, optionally limited to `pathspecs`
+    ///
+    /// Both numstats run concurrently. Failures yield empty stats, since line
+    /// counts are informational only.
+    async fn get_diff_stats(&self, pathspecs: &[&str]) -> (DiffStats, DiffStats) {
+        let numstat = |staged: bool| {
+            let mut args = vec![
+                "--no-optional-locks",
+                "--literal-pathspecs",
+                "diff",
+                "--numstat",
+                "-z",
+            ];
+            if staged {
+                args.push("--cached");
+            }
+            if !pathspecs.is_empty() {
+                args.push("--");
+                args.extend_from_slice(pathspecs);
+            }
+
+            async move {
+                match self.runner.output(Priority::Background, &args).await {
+                    Ok(output) if output.status.success() => {
+                        parse_numstat(&String::from_utf8_lossy(&output.stdout))
+                    }
+                    _ => DiffStats::new(),
+                }
+            }
+        };
+
+        tokio::join!(numstat(true), numstat(false))
+    }
+
+    /// Get the diff content for a specific file (the path is taken literally)
+    pub async fn get_file_diff(&self, path: &str, staged: bool) -> Result<String> {
+        let mut args = vec!["--literal-pathspecs", "diff"];
+        if staged {
+            args.push("--cached");
+        }
+        args.push("--");
+        args.push(path);
+
+        let diff = self
+            .runner
+            .run(
+                Priority::User,
+                &format!("Failed to get diff for {}", path),
+                &args,
+            )
             .await?;
 
+        Ok(diff)
+    }
+
+    /// Stage files (or everything under directories), taking paths literally
+    pub async fn stage_files(&self, paths: &[String]) -> Result<()> {
+        let _index = self.index_lock.lock().await;
+        let args = pathspec_args(&["--literal-pathspecs", "add"], paths);
+        self.runner
+            .run(
+                Priority::User,
+                &format!("Failed to stage {}", describe_paths(paths)),
+                &args,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Unstage files (or everything under directories), taking paths literally
+    pub async fn unstage_files(&self, paths: &[String]) -> Result<()> {
+        let _index = self.index_lock.lock().await;
+
+        // Try git restore --staged first (works for repos with commits)
+        let args = pathspec_args(&["--literal-pathspecs", "restore", "--staged"], paths);
+        let output = self.runner.output(Priority::User, &args).await?;
+
+        // If restore fails (e.g., on initial commit), use git rm --cached
         if !output.status.success() {
-            return Err(eyre!("Failed to get file statuses"));
+            let args = pathspec_args(&["--literal-pathspecs", "rm", "--cached", "-r"], paths);
+            self.runner
+                .run(
+                    Priority::User,
+                    &format!("Failed to unstage {}", describe_paths(paths)),
+                    &args,
+                )
+                .await?;
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let mut staged = Vec::new();
-        let mut unstaged = Vec::new();
-
-        for line in stdout.lines() {
-            if line.starts_with("1 ") || line.starts_with("2 ") {
-                // Changed entries
-                self.parse_changed_entry(line, &mut staged, &mut unstaged);
-            } else if line.starts_with("? ") {
-                // Untracked files
-                let path = line[2..].to_string();
-                unstaged.push(FileStatus::new(path, FileStatusType::Untracked, false));
-            } else if line.starts_with("u ") {
-                // Unmerged entries
-                self.parse_unmerged_entry(line, &mut unstaged);
-            }
+        Ok(())
+    }
+
+    /// Discard worktree changes
+    ///
+    /// Tracked files are restored from the index in one `git restore`;
+    /// untracked files and directories are deleted in one `git clean`.
+    /// Paths are taken literally, so a file name never acts as a glob.
+    ///
+    /// The two commands cannot be made atomic: if deleting fails after the
+    /// restore went through, the error says the tracked changes are gone.
+    pub async fn discard_files(&self, tracked: &[String], untracked: &[String]) -> Result<()> {
+        let _index = self.index_lock.lock().await;
+        if !tracked.is_empty() {
+            let args = pathspec_args(&["--literal-pathspecs", "restore", "--worktree"], tracked);
+            self.runner
+                .run(
+                    Priority::User,
+                    &format!("Failed to discard changes to {}", describe_paths(tracked)),
+                    &args,
+                )
+                .await?;
+        }
+        if !untracked.is_empty() {
+            let context = if tracked.is_empty() {
+                format!("Failed to delete {}", describe_paths(untracked))
+            } else {
+                format!(
+                    "Discarded changes to {}, but failed to delete {}",
+                    describe_paths(tracked),
+                    describe_paths(untracked)
+                )
+            };
+            let args = pathspec_args(&["--literal-pathspecs", "clean", "-f", "-d"], untracked);
+            self.runner.run(Priority::User, &context, &args).await?;
         }
 
-        Ok((staged, unstaged))
+        Ok(())
     }
 
-    /// Parse a changed entry from porcelain v2 output
-    fn parse_changed_entry(
-        &self,
-        line: &str,
-        staged: &mut Vec<FileStatus>,
-        unstaged: &mut Vec<FileStatus>,
-    ) {
-        let parts: Vec<&str> = line.split(' ').collect();
-        if parts.len() < 9 {
-            return;
+    /// Capture the blob ids of changed files so their diffs can be shown later
+    ///
+    /// Pass only the files that changed in a refresh: the worktree contents
+    /// of the unstaged ones are written to the object database, so they stay
+    /// readable after the files change again. Nothing touches the index.
+    /// Returns one entry per file; collapsed untracked directories,
+    /// submodules and files that could not be read get no blobs.
+    pub async fn capture_blobs(&self, files: &[FileStatus]) -> Result<Vec<FileBlobs>> {
+        let listed = || files.iter().filter(|f| !f.is_untracked_dir());
+
+        // Only regular files and symlinks can be hashed; a submodule is a
+        // directory, and a file may be gone again since the status was taken
+        let mut worktree = Vec::new();
+        for file in listed().filter(|f| !f.staged && f.status != FileStatusType::Deleted) {
+            let path = self.runner.repo_path().join(&file.path);
+            let hashable = tokio::fs::symlink_metadata(path)
+                .await
+                .is_ok_and(|m| m.is_file() || m.is_symlink());
+            if hashable {
+                worktree.push(file.path.clone());
+            }
         }
+        let indexed: Vec<String> = listed().map(|f| f.path.clone()).collect();
+        let committed: Vec<String> = listed()
+            .filter(|f| f.staged)
+            .map(|f| f.old_path.clone().unwrap_or_else(|| f.path.clone()))
+            .collect();
 
-        let xy = parts[1];
-        let path = if line.starts_with("2 ") {
-            // Rename entry has path at the end after a tab
-            if let Some(tab_pos) = line.find('\t') {
-                let paths = &line[tab_pos + 1..];
-                let path_parts: Vec<&str> = paths.split('\t').collect();
-                if path_parts.len() >= 2 {
-                    path_parts[1].to_string()
-                } else {
-                    parts[9..].join(" ")
-                }
-            } else {
-                parts[9..].join(" ")
+        let written = self.hash_objects(&worktree).await;
+
+        let mut index = HashMap::new();
+        if !indexed.is_empty() {
+            let args = pathspec_args(&["--literal-pathspecs", "ls-files", "-s", "-z"], &indexed);
+            let output = self
+                .runner
+                .run(Priority::Background, "Failed to read the index", &args)
+                .await?;
+            index = parse_object_ids(&output, 1);
+        }
+
+        // Without a HEAD commit nothing staged has a base
+        let mut head = HashMap::new();
+        if !committed.is_empty() {
+            let args = pathspec_args(
+                &["--literal-pathspecs", "ls-tree", "-z", "HEAD"],
+                &committed,
+            );
+            let output = self.runner.output(Priority::Background, &args).await?;
+            if output.status.success() {
+                head = parse_object_ids(&String::from_utf8_lossy(&output.stdout), 2);
             }
-        } else {
-            parts[8..].join(" ")
-        };
+        }
 
-        let index_status = xy.chars().next().unwrap_or('.');
-        let worktree_status = xy.chars().nth(1).unwrap_or('.');
-
-        // Staged changes (index)
-        if index_status != '.' {
-            if let Some(status_type) = FileStatusType::from_code(index_status) {
-                let mut file = FileStatus::new(path.clone(), status_type, true);
-                if line.starts_with("2 ") {
-                    // Handle rename - extract old path
-                    if let Some(tab_pos) = line.find('\t') {
-                        let paths = &line[tab_pos + 1..];
-                        let path_parts: Vec<&str> = paths.split('\t').collect();
-                        if !path_parts.is_empty() {
-                            file.old_path = Some(path_parts[0].to_string());
-                        }
+        Ok(files
+            .iter()
+            .map(|file| {
+                if file.is_untracked_dir() {
+                    FileBlobs::default()
+                } else if file.staged {
+                    let base_path = file.old_path.as_ref().unwrap_or(&file.path);
+                    FileBlobs {
+                        base: head.get(base_path).cloned(),
+                        current: index.get(&file.path).cloned(),
+                    }
+                } else {
+                    FileBlobs {
+                        base: index.get(&file.path).cloned(),
+                        current: written.get(&file.path).cloned(),
                     }
                 }
-                staged.push(file);
-            }
+            })
+            .collect())
+    }
+
+    /// Write files from the worktree to the object database, returning their blob ids
+    ///
+    /// All paths go to one `git hash-object`. If that fails (say a file was
+    /// deleted in the meantime), each path is retried on its own so the
+    /// others are still captured; paths that fail again are left out.
+    async fn hash_objects(&self, paths: &[String]) -> HashMap<String, String> {
+        if paths.is_empty() {
+            return HashMap::new();
+        }
+
+        let args = pathspec_args(&["hash-object", "-w"], paths);
+        let context = format!("Failed to capture {}", describe_paths(paths));
+        if let Ok(output) = self.runner.run(Priority::Background, &context, &args).await {
+            return paths
+                .iter()
+                .cloned()
+                .zip(output.lines().map(str::to_string))
+                .collect();
         }
 
-        // Unstaged changes (worktree)
-        if worktree_status != '.' {
-            if let Some(status_type) = FileStatusType::from_code(worktree_status) {
-                unstaged.push(FileStatus::new(path, status_type, false));
+        let mut written = HashMap::new();
+        for path in paths {
+            let args = ["hash-object", "-w", "--", path.as_str()];
+            let context = format!("Failed to capture {}", path);
+            if let Ok(output) = self.runner.run(Priority::Background, &context, &args).await {
+                written.insert(path.clone(), output.trim().to_string());
             }
         }
+        written
     }
 
-    /// Parse an unmerged entry from porcelain v2 output
-    fn parse_unmerged_entry(&self, line: &str, unstaged: &mut Vec<FileStatus>) {
-        let parts: Vec<&str> = line.split(' ').collect();
-        if parts.len() >= 11 {
-            let path = parts[10..].join(" ");
-            unstaged.push(FileStatus::new(path, FileStatusType::Unmerged, false));
+    /// Diff the captured blobs of a file (a missing side shows as an added or deleted file)
+    pub async fn blob_diff(&self, path: &str, blobs: &FileBlobs) -> Result<String> {
+        let context = format!("Failed to get diff for {}", path);
+        let (id, added) = match (&blobs.base, &blobs.current) {
+            (Some(base), Some(current)) => {
+                let args = ["diff", base.as_str(), current.as_str()];
+                return Ok(self.runner.run(Priority::User, &context, &args).await?);
+            }
+            (None, Some(current)) => (current, true),
+            (Some(base), None) => (base, false),
+            (None, None) => return Ok(String::new()),
+        };
+
+        let contents = self
+            .runner
+            .run(Priority::User, &context, &["cat-file", "-p", id])
+            .await?;
+        Ok(whole_file_diff(path, &contents, added))
+    }
+
+    /// Record the working tree, untracked files included, as a snapshot commit
+    ///
+    /// Like `git stash create`, but through a temporary index, so neither the
+    /// index nor the worktree is touched. Returns None if nothing changed
+    /// since the latest snapshot.
+    pub async fn create_snapshot(&self) -> Result<Option<Snapshot>> {
+        let tree = self.worktree_tree().await?;
+        let latest = self.list_snapshots().await?.into_iter().next();
+        if latest.is_some_and(|latest| latest.tree == tree) {
+            return Ok(None);
         }
+
+        let head = self
+            .runner
+            .output(
+                Priority::Background,
+                &["rev-parse", "--verify", "-q", "HEAD^{commit}"],
+            )
+            .await?;
+        let head = String::from_utf8_lossy(&head.stdout).trim().to_string();
+        let mut args = vec!["commit-tree", tree.as_str(), "-m", "gfm snapshot"];
+        if !head.is_empty() {
+            args.extend(["-p", head.as_str()]);
+        }
+        let commit = self
+            .runner
+            .run_with_env(
+                Priority::Background,
+                "Failed to record a snapshot",
+                &SNAPSHOT_IDENTITY,
+                &args,
+            )
+            .await?
+            .trim()
+            .to_string();
+
+        // The ref name carries the time, in milliseconds since the epoch
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let name = format!("{}{}", SNAPSHOT_REFS, millis);
+        self.runner
+            .run(
+                Priority::Background,
+                "Failed to record a snapshot",
+                &["update-ref", &name, &commit],
+            )
+            .await?;
+
+        Ok(Some(Snapshot {
+            name,
+            commit,
+            tree,
+            time: UNIX_EPOCH + Duration::from_millis(millis),
+        }))
     }
 
-    /// Get diff stats (lines added/deleted per file)
-    async fn get_diff_stats(&self, staged: bool) -> Result<HashMap<String, (usize, usize)>> {
-        let mut args = vec!["diff", "--numstat"];
-        if staged {
-            args.push("--cached");
+    /// List the recorded snapshots, newest first
+    pub async fn list_snapshots(&self) -> Result<Vec<Snapshot>> {
+        let output = self
+            .runner
+            .run(
+                Priority::Background,
+                "Failed to list snapshots",
+                &[
+                    "for-each-ref",
+                    "--sort=-refname",
+                    "--format=%(refname) %(objectname) %(tree)",
+                    SNAPSHOT_REFS,
+                ],
+            )
+            .await?;
+
+        Ok(output.lines().filter_map(parse_snapshot).collect())
+    }
+
+    /// Find the files that differ between a snapshot and the current working tree
+    pub async fn snapshot_changes(&self, snapshot: &Snapshot) -> Result<SnapshotChanges> {
+        let current_tree = self.worktree_tree().await?;
+        let output = self
+            .runner
+            .run(
+                Priority::User,
+                "Failed to compare the snapshot",
+                &[
+                    "diff",
+                    "--name-status",
+                    "-z",
+                    "--no-renames",
+                    &snapshot.tree,
+                    &current_tree,
+                ],
+            )
+            .await?;
+
+        let mut files = Vec::new();
+        let mut fields = output.split('\0');
+        while let (Some(code), Some(path)) = (fields.next(), fields.next()) {
+            let status = code
+                .chars()
+                .next()
+                .and_then(FileStatusType::from_code)
+                .unwrap_or(FileStatusType::Modified);
+            files.push(FileStatus::new(path.to_string(), status, false));
         }
 
-        let output = Command::new("git")
-            .args(&args)
-            .current_dir(&self.repo_path)
-            .output()
+        Ok(SnapshotChanges {
+            current_tree,
+            files,
+        })
+    }
+
+    /// Diff a file between a snapshot and the compared working tree
+    pub async fn snapshot_file_diff(
+        &self,
+        snapshot: &Snapshot,
+        changes: &SnapshotChanges,
+        path: &str,
+    ) -> Result<String> {
+        let diff = self
+            .runner
+            .run(
+                Priority::User,
+                &format!("Failed to get diff for {}", path),
+                &[
+                    "--literal-pathspecs",
+                    "diff",
+                    &snapshot.tree,
+                    &changes.current_tree,
+                    "--",
+                    path,
+                ],
+            )
             .await?;
 
-        if !output.status.success() {
-            return Ok(HashMap::new());
+        Ok(diff)
+    }
+
+    /// Restore a file in the worktree to its contents in a snapshot
+    ///
+    /// A file that is not in the snapshot is deleted. The index is left alone.
+    pub async fn restore_from_snapshot(&self, snapshot: &Snapshot, path: &str) -> Result<()> {
+        let context = format!("Failed to restore {}", path);
+        let listed = self
+            .runner
+            .run(
+                Priority::User,
+                &context,
+                &[
+                    "--literal-pathspecs",
+                    "ls-tree",
+                    "--name-only",
+                    &snapshot.commit,
+                    "--",
+                    path,
+                ],
+            )
+            .await?;
+
+        if listed.trim().is_empty() {
+            let file = self.runner.repo_path().join(path);
+            tokio::fs::remove_file(&file)
+                .await
+                .map_err(|e| eyre!("{}: {}", context, e))?;
+        } else {
+            let source = format!("--source={}", snapshot.commit);
+            self.runner
+                .run(
+                    Priority::User,
+                    &context,
+                    &[
+                        "--literal-pathspecs",
+                        "restore",
+                        &source,
+                        "--worktree",
+                        "--",
+                        path,
+                    ],
+                )
+                .await?;
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let mut stats = HashMap::new();
+        Ok(())
+    }
 
-        for line in stdout.lines() {
-            let parts: Vec<&str> = line.split('\t').collect();
-            if parts.len() >= 3 {
-                let added: usize = parts[0].parse().unwrap_or(0);
-                let deleted: usize = parts[1].parse().unwrap_or(0);
-                let path = parts[2].to_string();
-                stats.insert(path, (added, deleted));
-            }
+    /// Delete the snapshots taken before `cutoff`, returning how many were deleted
+    pub async fn prune_snapshots(&self, cutoff: SystemTime) -> Result<usize> {
+        let old: Vec<Snapshot> = self
+            .list_snapshots()
+            .await?
+            .into_iter()
+            .filter(|snapshot| snapshot.time < cutoff)
+            .collect();
+        for snapshot in &old {
+            self.runner
+                .run(
+                    Priority::Background,
+                    "Failed to prune snapshots",
+                    &["update-ref", "-d", &snapshot.name],
+                )
+                .await?;
         }
 
-        Ok(stats)
+        Ok(old.len())
     }
 
-    /// Get the diff content for a specific file
-    pub async fn get_file_diff(&self, path: &str, staged: bool) -> Result<String> {
-        let mut args = vec!["diff"];
-        if staged {
-            args.push("--cached");
+    /// Write the working tree, untracked files included, as a tree through a temporary index
+    async fn worktree_tree(&self) -> Result<String> {
+        let _snapshot = self.snapshot_lock.lock().await;
+        let paths = self
+            .runner
+            .run(
+                Priority::Background,
+                "Failed to locate the index",
+                &[
+                    "rev-parse",
+                    "--git-path",
+                    "index",
+                    "--git-path",
+                    "gfm-snapshot-index",
+                ],
+            )
+            .await?;
+        let mut paths = paths.lines().map(|path| self.runner.repo_path().join(path));
+        let (Some(index), Some(temporary)) = (paths.next(), paths.next()) else {
+            return Err(eyre!("Failed to locate the index"));
+        };
+
+        // Starting from a copy of the real index lets git skip unchanged files
+        let copied = tokio::fs::copy(&index, &temporary).await;
+        if copied.is_err() {
+            let _ = tokio::fs::remove_file(&temporary).await;
         }
-        args.push("--");
-        args.push(path);
+        let temporary = temporary.to_string_lossy().to_string();
+        let env = [("GIT_INDEX_FILE", temporary.as_str())];
 
-        let output = Command::new("git")
-            .args(&args)
-            .current_dir(&self.repo_path)
-            .output()
+        self.runner
+            .run_with_env(
+                Priority::Background,
+                "Failed to record a snapshot",
+                &env,
+                &["add", "-A"],
+            )
+            .await?;
+        let tree = self
+            .runner
+            .run_with_env(
+                Priority::Background,
+                "Failed to record a snapshot",
+                &env,
+                &["write-tree"],
+            )
             .await?;
 
-        if !output.status.success() {
-            return Err(eyre!("Failed to get diff for {}", path));
+        Ok(tree.trim().to_string())
+    }
+
+    /// Run a remote operation, streaming git's progress output to `progress`
+    ///
+    /// Returns a short summary on success. On failure the error message is a
+    /// readable description of what went wrong (e.g. a non-fast-forward rejection).
+    pub async fn run_remote_operation(
+        &self,
+        operation: RemoteOperation,
+        progress: mpsc::UnboundedSender<ProgressLine>,
+    ) -> Result<String> {
+        match operation {
+            RemoteOperation::Push => self.push(progress).await,
+            RemoteOperation::Pull { rebase } => self.pull(rebase, progress).await,
         }
+    }
+
+    /// Push the current branch, setting the upstream if it is missing
+    pub async fn push(&self, progress: mpsc::UnboundedSender<ProgressLine>) -> Result<String> {
+        let branch = self.current_branch().await?;
+
+        let mut args = vec!["push", "--progress"];
+        let remote;
+        if !self.has_upstream().await {
+            remote = self.default_remote(&branch).await?;
+            args.extend(["--set-upstream", remote.as_str(), branch.as_str()]);
+        }
+
+        self.runner
+            .run_streaming(&args, progress, |stderr| {
+                describe_remote_failure(RemoteOperation::Push, stderr)
+            })
+            .await?;
 
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        Ok(format!("Pushed {}", branch))
     }
 
-    /// Stage a file
-    pub async fn stage_file(&self, path: &str) -> Result<()> {
-        let output = Command::new("git")
-            .args(["add", "--", path])
-            .current_dir(&self.repo_path)
-            .output()
+    /// Pull the current branch (fast-forward only, or rebase onto upstream)
+    pub async fn pull(
+        &self,
+        rebase: bool,
+        progress: mpsc::UnboundedSender<ProgressLine>,
+    ) -> Result<String> {
+        let branch = self.current_branch().await?;
+
+        let mode = if rebase { "--rebase" } else { "--ff-only" };
+        self.runner
+            .run_streaming(&["pull", "--progress", mode], progress, |stderr| {
+                describe_remote_failure(RemoteOperation::Pull { rebase }, stderr)
+            })
+            .await?;
+
+        Ok(format!("Pulled {}", branch))
+    }
+
+    /// Get the name of the currently checked-out branch
+    async fn current_branch(&self) -> Result<String> {
+        let output = self
+            .runner
+            .output(
+                Priority::User,
+                &["symbolic-ref", "--quiet", "--short", "HEAD"],
+            )
             .await?;
 
         if !output.status.success() {
-            return Err(eyre!("Failed to stage file: {}", path));
+            return Err(eyre!("HEAD is detached; check out a branch first"));
         }
 
-        Ok(())
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
     }
 
-    /// Unstage a file
-    pub async fn unstage_file(&self, path: &str) -> Result<()> {
-        // Try git restore --staged first (works for repos with commits)
-        let output = Command::new("git")
-            .args(["restore", "--staged", "--", path])
-            .current_dir(&self.repo_path)
-            .output()
-            .await?;
+    /// Check whether the current branch has an upstream configured
+    async fn has_upstream(&self) -> bool {
+        self.runner
+            .output(
+                Priority::User,
+                &["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"],
+            )
+            .await
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
 
-        // If restore fails (e.g., on initial commit), use git rm --cached
-        if !output.status.success() {
-            let output = Command::new("git")
-                .args(["rm", "--cached", "--", path])
-                .current_dir(&self.repo_path)
-                .output()
+    /// Pick the remote to push a branch without an upstream to
+    ///
+    /// Honours `branch.<name>.pushRemote` and `remote.pushDefault`, then
+    /// prefers `origin`, then falls back to the only configured remote.
+    async fn default_remote(&self, branch: &str) -> Result<String> {
+        for key in [
+            format!("branch.{}.pushRemote", branch),
+            "remote.pushDefault".to_string(),
+        ] {
+            let output = self
+                .runner
+                .output(Priority::User, &["config", "--get", &key])
                 .await?;
-
-            if !output.status.success() {
-                return Err(eyre!("Failed to unstage file: {}", path));
+            let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if output.status.success() && !value.is_empty() {
+                return Ok(value);
             }
         }
 
-        Ok(())
+        let stdout = self
+            .runner
+            .run(Priority::User, "Failed to list remotes", &["remote"])
+            .await?;
+        let remotes: Vec<&str> = stdout
+            .lines()
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+            .collect();
+
+        if remotes.contains(&"origin") {
+            Ok("origin".to_string())
+        } else if let Some(remote) = remotes.first() {
+            Ok(remote.to_string())
+        } else {
+            Err(eyre!("No remote configured; add one with `git remote add`"))
+        }
+    }
+}
+
+/// Lines added/deleted per path
+type DiffStats = HashMap<String, (usize, usize)>;
+
+/// Copy line counts from numstat output onto the matching file entries
+fn apply_diff_stats(files: &mut [FileStatus], stats: &DiffStats) {
+    for file in files {
+        if let Some((added, deleted)) = stats.get(&file.path) {
+            file.lines_added = *added;
+            file.lines_deleted = *deleted;
+        }
+    }
+}
+
+/// Parse a `%(refname) %(objectname) %(tree)` line of a snapshot ref
+fn parse_snapshot(line: &str) -> Option<Snapshot> {
+    let mut fields = line.split(' ');
+    let (name, commit, tree) = (fields.next()?, fields.next()?, fields.next()?);
+    let millis = name.strip_prefix(SNAPSHOT_REFS)?.parse().ok()?;
+    Some(Snapshot {
+        name: name.to_string(),
+        commit: commit.to_string(),
+        tree: tree.to_string(),
+        time: UNIX_EPOCH + Duration::from_millis(millis),
+    })
+}
+
+/// Map paths to object ids in `ls-files -s -z` or `ls-tree -z` output
+///
+/// `field` is the position of the id among the space-separated fields before the tab.
+fn parse_object_ids(output: &str, field: usize) -> HashMap<String, String> {
+    let mut ids = HashMap::new();
+    for entry in output.split('\0') {
+        let Some((info, path)) = entry.split_once('\t') else {
+            continue;
+        };
+        // Submodules are recorded as commits, which have no contents to diff
+        if info.starts_with("160000 ") {
+            continue;
+        }
+        if let Some(id) = info.split(' ').nth(field) {
+            // Conflicted paths list several stages; keep the first
+            ids.entry(path.to_string())
+                .or_insert_with(|| id.to_string());
+        }
+    }
+    ids
+}
+
+/// Show the contents of a file that exists on one side only as a diff
+fn whole_file_diff(path: &str, contents: &str, added: bool) -> String {
+    let count = contents.lines().count();
+    let (old, new, range, marker) = if added {
+        (
+            "/dev/null".to_string(),
+            format!("b/{}", path),
+            format!("-0,0 +1,{}", count),
+            '+',
+        )
+    } else {
+        (
+            format!("a/{}", path),
+            "/dev/null".to_string(),
+            format!("-1,{} +0,0", count),
+            '-',
+        )
+    };
+
+    let mut diff = format!("--- {}\n+++ {}\n@@ {} @@\n", old, new, range);
+    for line in contents.lines() {
+        diff.push(marker);
+        diff.push_str(line);
+        diff.push('\n');
+    }
+    diff
+}
+
+/// Build `<command> -- <paths>` arguments
+fn pathspec_args<'a>(command: &[&'a str], paths: &'a [String]) -> Vec<&'a str> {
+    let mut args = command.to_vec();
+    args.push("--");
+    args.extend(paths.iter().map(String::as_str));
+    args
+}
+
+/// Name a set of paths for an error message
+fn describe_paths(paths: &[String]) -> String {
+    match paths {
+        [path] => path.clone(),
+        _ => format!("{} paths", paths.len()),
+    }
+}
+
+/// Turn the stderr of a failed push/pull into a readable error message
+pub fn describe_remote_failure(operation: RemoteOperation, stderr: &str) -> String {
+    let lower = stderr.to_lowercase();
+
+    let reason = if lower.contains("non-fast-forward") || lower.contains("fetch first") {
+        "the remote contains commits you don't have locally; pull first, then push again"
+            .to_string()
+    } else if lower.contains("not possible to fast-forward") {
+        "local and remote branches have diverged; pull with rebase or merge manually".to_string()
+    } else if lower.contains("no tracking information") {
+        "the current branch has no upstream; push it first to set one".to_string()
+    } else if lower.contains("would be overwritten") || lower.contains("unstaged changes") {
+        "local changes would be overwritten; commit or stash them first".to_string()
+    } else if lower.contains("could not read from remote repository")
+        || lower.contains("does not appear to be a git repository")
+    {
+        "could not reach the remote repository".to_string()
+    } else if lower.contains("authentication failed") || lower.contains("permission denied") {
+        "authentication with the remote failed".to_string()
+    } else {
+        explain_stderr(stderr).unwrap_or_else(|| "git exited with an error".to_string())
+    };
+
+    format!("{} failed: {}", operation.label(), reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::process::Stdio;
+
+    use super::*;
+    use crate::git::types::UntrackedMode;
+
+    /// Run a git command in `dir` with a fixed identity, panicking on failure
+    fn git(dir: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=gfm", "-c", "user.email=gfm@example.com"])
+            .args([
+                "-c",
+                "init.defaultBranch=main",
+                "-c",
+                "commit.gpgsign=false",
+            ])
+            .args(args)
+            .current_dir(dir)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .expect("failed to run git");
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    /// Commit a file with the given contents
+    fn commit_file(dir: &Path, name: &str, contents: &str) {
+        std::fs::write(dir.join(name), contents).unwrap();
+        git(dir, &["add", name]);
+        git(dir, &["commit", "-m", name]);
+    }
+
+    /// Create a bare remote and a clone of it with one pushed commit on `main`
+    fn setup() -> (tempfile::TempDir, PathBuf, String) {
+        let root = tempfile::tempdir().unwrap();
+        let remote = root.path().join("remote.git");
+        let local = root.path().join("local");
+        std::fs::create_dir(&remote).unwrap();
+        git(&remote, &["init", "--bare"]);
+        let url = format!("file://{}", remote.display());
+        git(root.path(), &["clone", &url, "local"]);
+        git(&local, &["checkout", "-B", "main"]);
+        commit_file(&local, "a.txt", "a\n");
+        (root, local, url)
+    }
+
+    fn progress() -> mpsc::UnboundedSender<ProgressLine> {
+        mpsc::unbounded_channel().0
+    }
+
+    #[tokio::test]
+    async fn test_push_sets_missing_upstream() {
+        let (_root, local, _url) = setup();
+        let service = GitService::new(local.clone());
+
+        assert!(!service.has_upstream().await);
+        service.push(progress()).await.unwrap();
+        assert!(service.has_upstream().await);
+    }
+
+    #[tokio::test]
+    async fn test_push_rejection_is_readable() {
+        let (root, local, url) = setup();
+        let service = GitService::new(local.clone());
+        service.push(progress()).await.unwrap();
+
+        // Another clone pushes first, so our push is no longer a fast-forward
+        git(root.path(), &["clone", &url, "other"]);
+        let other = root.path().join("other");
+        commit_file(&other, "b.txt", "b\n");
+        git(&other, &["push"]);
+        commit_file(&local, "c.txt", "c\n");
+
+        let err = service.push(progress()).await.unwrap_err().to_string();
+        assert!(err.starts_with("Push failed"), "{}", err);
+        assert!(err.contains("pull first"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_pull_fast_forward_and_rebase() {
+        let (root, local, url) = setup();
+        let service = GitService::new(local.clone());
+        service.push(progress()).await.unwrap();
+
+        git(root.path(), &["clone", &url, "other"]);
+        let other = root.path().join("other");
+        commit_file(&other, "b.txt", "b\n");
+        git(&other, &["push"]);
+
+        service.pull(false, progress()).await.unwrap();
+        assert!(local.join("b.txt").exists());
+
+        // Diverge: ff-only refuses, rebase succeeds
+        commit_file(&other, "c.txt", "c\n");
+        git(&other, &["push"]);
+        commit_file(&local, "d.txt", "d\n");
+
+        let err = service
+            .pull(false, progress())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("diverged"), "{}", err);
+
+        git(&local, &["config", "user.name", "gfm"]);
+        git(&local, &["config", "user.email", "gfm@example.com"]);
+        service.pull(true, progress()).await.unwrap();
+        assert!(local.join("c.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_glob_like_names_are_taken_literally() {
+        let dir = tempfile::tempdir().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        commit_file(dir.path(), "a.txt", "a\n");
+        commit_file(dir.path(), "[a].txt", "glob\n");
+        // With the literal file gone, `[a].txt` would match a.txt as a glob
+        std::fs::write(dir.path().join("a.txt"), "changed\n").unwrap();
+        std::fs::remove_file(dir.path().join("[a].txt")).unwrap();
+        let service = GitService::new(dir.path().to_path_buf());
+        let staged = |service: GitService| async move {
+            let status = service.get_status(&StatusOptions::default()).await.unwrap();
+            status
+                .staged_files
+                .iter()
+                .map(|file| file.path.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let diff = service.get_file_diff("[a].txt", false).await.unwrap();
+        assert!(diff.contains("-glob") && !diff.contains("+changed"));
+
+        service.stage_files(&["[a].txt".to_string()]).await.unwrap();
+        assert_eq!(staged(service.clone()).await, vec!["[a].txt"]);
+
+        service.stage_files(&["a.txt".to_string()]).await.unwrap();
+        service
+            .unstage_files(&["[a].txt".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(staged(service.clone()).await, vec!["a.txt"]);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_update_matches_full_status() {
+        let (_root, local, _url) = setup();
+        commit_file(&local, "b.txt", "b\n");
+        let service = GitService::new(local.clone());
+        let mut status = service.get_status(&StatusOptions::default()).await.unwrap();
+
+        std::fs::write(local.join("a.txt"), "a\nmore\n").unwrap();
+        std::fs::remove_file(local.join("b.txt")).unwrap();
+        std::fs::create_dir(local.join("new")).unwrap();
+        std::fs::write(local.join("new/c.txt"), "c\n").unwrap();
+
+        let paths = vec!["a.txt".to_string(), "b.txt".to_string(), "new".to_string()];
+        let (staged, unstaged) = service
+            .get_status_for_paths(&StatusOptions::default(), &paths)
+            .await
+            .unwrap();
+        status.apply_path_update(&paths, staged, unstaged);
+
+        let full = service.get_status(&StatusOptions::default()).await.unwrap();
+        let summary = |files: &[FileStatus]| -> Vec<(String, FileStatusType, usize)> {
+            files
+                .iter()
+                .map(|f| (f.path.clone(), f.status, f.lines_added))
+                .collect()
+        };
+        assert_eq!(
+            summary(&status.unstaged_files),
+            summary(&full.unstaged_files)
+        );
+        assert_eq!(summary(&status.staged_files), summary(&full.staged_files));
+        assert_eq!(full.unstaged_files.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_normal_mode_collapses_and_expands_untracked_dirs() {
+        let (_root, local, _url) = setup();
+        std::fs::create_dir_all(local.join("build/deep")).unwrap();
+        std::fs::write(local.join("build/x.o"), "x").unwrap();
+        std::fs::write(local.join("build/deep/y.o"), "y").unwrap();
+        std::fs::write(local.join("loose.txt"), "z").unwrap();
+        let service = GitService::new(local);
+
+        let untracked = |status: &GitStatus| -> Vec<String> {
+            status
+                .unstaged_files
+                .iter()
+                .map(|f| f.path.clone())
+                .collect()
+        };
+
+        let mut options = StatusOptions {
+            untracked: UntrackedMode::Normal,
+            expanded_dirs: Vec::new(),
+        };
+        let collapsed = service.get_status(&options).await.unwrap();
+        assert_eq!(untracked(&collapsed), vec!["build/", "loose.txt"]);
+        assert!(collapsed.unstaged_files[0].is_untracked_dir());
+
+        options.expanded_dirs.push("build/".to_string());
+        let expanded = service.get_status(&options).await.unwrap();
+        assert_eq!(
+            untracked(&expanded),
+            vec!["build/deep/y.o", "build/x.o", "loose.txt"]
+        );
+
+        options.untracked = UntrackedMode::No;
+        let none = service.get_status(&options).await.unwrap();
+        assert!(none.unstaged_files.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stage_and_unstage_directory_before_first_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        std::fs::create_dir_all(dir.path().join("src/ui")).unwrap();
+        for name in ["src/a.rs", "src/ui/b.rs", "top.txt"] {
+            std::fs::write(dir.path().join(name), "x\n").unwrap();
+        }
+
+        let service = GitService::new(dir.path().to_path_buf());
+        let options = StatusOptions::default();
+        service.stage_files(&["src/".to_string()]).await.unwrap();
+        let status = service.get_status(&options).await.unwrap();
+        let staged: Vec<&str> = status
+            .staged_files
+            .iter()
+            .map(|f| f.path.as_str())
+            .collect();
+        assert_eq!(staged, vec!["src/a.rs", "src/ui/b.rs"]);
+
+        // No HEAD yet, so this goes through `git rm --cached -r`
+        service.unstage_files(&["src/".to_string()]).await.unwrap();
+        let status = service.get_status(&options).await.unwrap();
+        assert!(status.staged_files.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_discard_restores_tracked_and_deletes_untracked_files() {
+        let dir = tempfile::tempdir().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        commit_file(dir.path(), "a.txt", "a\n");
+        commit_file(dir.path(), "b.txt", "b\n");
+        std::fs::write(dir.path().join("a.txt"), "changed\n").unwrap();
+        std::fs::remove_file(dir.path().join("b.txt")).unwrap();
+        std::fs::create_dir(dir.path().join("new")).unwrap();
+        std::fs::write(dir.path().join("new/c.txt"), "c\n").unwrap();
+        std::fs::write(dir.path().join("keep.txt"), "keep\n").unwrap();
+
+        let service = GitService::new(dir.path().to_path_buf());
+        service
+            .discard_files(
+                &["a.txt".to_string(), "b.txt".to_string()],
+                &["new/".to_string()],
+            )
+            .await
+            .unwrap();
+
+        let read = |name: &str| std::fs::read_to_string(dir.path().join(name)).unwrap();
+        assert_eq!(read("a.txt"), "a\n");
+        assert_eq!(read("b.txt"), "b\n");
+        assert!(!dir.path().join("new").exists());
+        assert_eq!(read("keep.txt"), "keep\n");
+    }
+
+    #[tokio::test]
+    async fn test_captured_blobs_keep_the_diff_after_files_change_again() {
+        let dir = tempfile::tempdir().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        commit_file(dir.path(), "a.txt", "a\n");
+        std::fs::write(dir.path().join("a.txt"), "first\n").unwrap();
+        std::fs::write(dir.path().join("new.txt"), "new\n").unwrap();
+        git(dir.path(), &["add", "new.txt"]);
+
+        let service = GitService::new(dir.path().to_path_buf());
+        let files = vec![
+            FileStatus::new("a.txt".to_string(), FileStatusType::Modified, false),
+            FileStatus::new("new.txt".to_string(), FileStatusType::Added, true),
+        ];
+        let blobs = service.capture_blobs(&files).await.unwrap();
+        std::fs::write(dir.path().join("a.txt"), "second\n").unwrap();
+
+        let diff = service.blob_diff("a.txt", &blobs[0]).await.unwrap();
+        assert!(diff.contains("-a\n+first"), "{}", diff);
+        assert_eq!(blobs[1].base, None);
+        let diff = service.blob_diff("new.txt", &blobs[1]).await.unwrap();
+        assert!(diff.ends_with("@@ -0,0 +1,1 @@\n+new\n"), "{}", diff);
+    }
+
+    #[tokio::test]
+    async fn test_capture_skips_submodules_and_missing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        commit_file(dir.path(), "a.txt", "a\n");
+        commit_file(dir.path(), "gone.txt", "gone\n");
+        let head = std::process::Command::new("git")
+            .args(["rev-parse", "HEAD"])
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        let cacheinfo = format!(
+            "160000,{},sub",
+            String::from_utf8_lossy(&head.stdout).trim()
+        );
+        git(
+            dir.path(),
+            &["update-index", "--add", "--cacheinfo", &cacheinfo],
+        );
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("a.txt"), "changed\n").unwrap();
+        std::fs::remove_file(dir.path().join("gone.txt")).unwrap();
+
+        // The status was taken before gone.txt was deleted
+        let service = GitService::new(dir.path().to_path_buf());
+        let files: Vec<FileStatus> = ["a.txt", "gone.txt", "sub"]
+            .iter()
+            .map(|path| FileStatus::new(path.to_string(), FileStatusType::Modified, false))
+            .collect();
+        let blobs = service.capture_blobs(&files).await.unwrap();
+
+        assert!(blobs[0].base.is_some() && blobs[0].current.is_some());
+        assert!(blobs[1].base.is_some() && blobs[1].current.is_none());
+        assert_eq!(blobs[2], FileBlobs::default());
+    }
+
+    #[tokio::test]
+    async fn test_snapshots_record_restore_and_prune_without_touching_the_index() {
+        let dir = tempfile::tempdir().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        commit_file(dir.path(), "a.txt", "a\n");
+        std::fs::write(dir.path().join("a.txt"), "snapshotted\n").unwrap();
+        std::fs::write(dir.path().join("untracked.txt"), "u\n").unwrap();
+        let index = std::fs::read(dir.path().join(".git/index")).unwrap();
+
+        let service = GitService::new(dir.path().to_path_buf());
+        let snapshot = service.create_snapshot().await.unwrap().unwrap();
+        assert!(service.create_snapshot().await.unwrap().is_none());
+        assert_eq!(
+            service.list_snapshots().await.unwrap(),
+            vec![snapshot.clone()]
+        );
+        assert_eq!(std::fs::read(dir.path().join(".git/index")).unwrap(), index);
+
+        std::fs::write(dir.path().join("a.txt"), "lost\n").unwrap();
+        std::fs::remove_file(dir.path().join("untracked.txt")).unwrap();
+        std::fs::write(dir.path().join("later.txt"), "l\n").unwrap();
+        let changes = service.snapshot_changes(&snapshot).await.unwrap();
+        let files: Vec<(&str, FileStatusType)> = changes
+            .files
+            .iter()
+            .map(|file| (file.path.as_str(), file.status))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ("a.txt", FileStatusType::Modified),
+                ("later.txt", FileStatusType::Added),
+                ("untracked.txt", FileStatusType::Deleted),
+            ]
+        );
+        let diff = service
+            .snapshot_file_diff(&snapshot, &changes, "a.txt")
+            .await
+            .unwrap();
+        assert!(diff.contains("-snapshotted\n+lost"), "{}", diff);
+
+        for path in ["a.txt", "untracked.txt", "later.txt"] {
+            service
+                .restore_from_snapshot(&snapshot, path)
+                .await
+                .unwrap();
+        }
+        let read = |name: &str| std::fs::read_to_string(dir.path().join(name)).unwrap();
+        assert_eq!(read("a.txt"), "snapshotted\n");
+        assert_eq!(read("untracked.txt"), "u\n");
+        assert!(!dir.path().join("later.txt").exists());
+
+        let pruned = service
+            .prune_snapshots(snapshot.time + Duration::from_millis(1))
+            .await
+            .unwrap();
+        assert_eq!(pruned, 1);
+        assert!(service.list_snapshots().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_push_streams_progress() {
+        let (_root, local, _url) = setup();
+        let service = GitService::new(local);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        service.push(tx).await.unwrap();
+
+        let mut lines = Vec::new();
+        while let Ok(line) = rx.try_recv() {
+            lines.push(line.text);
+        }
+        assert!(!lines.is_empty());
     }
 }
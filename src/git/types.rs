@@ -1,3 +1,5 @@
+use std::time::SystemTime;
+
 use serde::{Deserialize, Serialize};
 
 /// Type of file status in git
@@ -14,6 +16,30 @@ pub enum FileStatusType {
 }
 
 impl FileStatusType {
+    /// All status types, in the order the status filter cycles through them
+    pub const ALL: [FileStatusType; 7] = [
+        FileStatusType::Modified,
+        FileStatusType::Added,
+        FileStatusType::Deleted,
+        FileStatusType::Renamed,
+        FileStatusType::Copied,
+        FileStatusType::Untracked,
+        FileStatusType::Unmerged,
+    ];
+
+    /// Get a readable name for this status type
+    pub fn label(&self) -> &'static str {
+        match self {
+            FileStatusType::Modified => "modified",
+            FileStatusType::Added => "added",
+            FileStatusType::Deleted => "deleted",
+            FileStatusType::Renamed => "renamed",
+            FileStatusType::Copied => "copied",
+            FileStatusType::Untracked => "untracked",
+            FileStatusType::Unmerged => "unmerged",
+        }
+    }
+
     /// Get the short code for this status type
     pub fn code(&self) -> &'static str {
         match self {
@@ -42,6 +68,48 @@ impl FileStatusType {
     }
 }
 
+/// Which untracked files `git status` reports (`--untracked-files`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UntrackedMode {
+    /// Don't show untracked files
+    No,
+    /// Show untracked files, collapsing new directories into one entry
+    Normal,
+    /// Show every file inside untracked directories
+    #[default]
+    All,
+}
+
+impl UntrackedMode {
+    /// Get the value passed to `--untracked-files`
+    pub fn as_arg(&self) -> &'static str {
+        match self {
+            UntrackedMode::No => "no",
+            UntrackedMode::Normal => "normal",
+            UntrackedMode::All => "all",
+        }
+    }
+
+    /// Get the next mode when cycling through them
+    pub fn next(&self) -> Self {
+        match self {
+            UntrackedMode::No => UntrackedMode::Normal,
+            UntrackedMode::Normal => UntrackedMode::All,
+            UntrackedMode::All => UntrackedMode::No,
+        }
+    }
+}
+
+/// Options controlling how the status is collected
+#[derive(Debug, Clone, Default)]
+pub struct StatusOptions {
+    /// Which untracked files to report
+    pub untracked: UntrackedMode,
+    /// Collapsed untracked directories (with trailing `/`) to list file by file
+    pub expanded_dirs: Vec<String>,
+}
+
 /// Status of a single file in the repository
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileStatus {
@@ -57,6 +125,9 @@ pub struct FileStatus {
     pub lines_deleted: usize,
     /// Original path (for renames)
     pub old_path: Option<String>,
+    /// Modification time on disk when the status was collected (None if missing)
+    #[serde(skip)]
+    pub modified: Option<SystemTime>,
 }
 
 impl FileStatus {
@@ -69,8 +140,45 @@ impl FileStatus {
             lines_added: 0,
             lines_deleted: 0,
             old_path: None,
+            modified: None,
         }
     }
+
+    /// Whether this entry is a collapsed untracked directory (path ends with `/`)
+    pub fn is_untracked_dir(&self) -> bool {
+        self.status == FileStatusType::Untracked && self.path.ends_with('/')
+    }
+}
+
+/// Blob ids of a changed file's two sides, captured so its diff can be shown later
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileBlobs {
+    /// What the change is made against (index or HEAD), None if the file is new there
+    pub base: Option<String>,
+    /// The changed contents (worktree or index), None if the file is deleted
+    pub current: Option<String>,
+}
+
+/// A snapshot of the working tree recorded under `refs/gfm/snapshots/`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Full ref name
+    pub name: String,
+    /// Snapshot commit (its parent is HEAD at the time)
+    pub commit: String,
+    /// Tree of the working tree, untracked files included
+    pub tree: String,
+    /// When the snapshot was taken (from the ref name)
+    pub time: SystemTime,
+}
+
+/// Files that differ between a snapshot and the current working tree
+#[derive(Debug, Clone)]
+pub struct SnapshotChanges {
+    /// Tree of the current working tree the snapshot was compared to
+    pub current_tree: String,
+    /// Changes since the snapshot (added files are not in it, deleted ones only in it)
+    pub files: Vec<FileStatus>,
 }
 
 /// Information about the current branch
@@ -84,6 +192,8 @@ pub struct BranchInfo {
     pub ahead: usize,
     /// Number of commits behind upstream
     pub behind: usize,
+    /// Number of stash entries
+    pub stash_count: usize,
 }
 
 /// Information about the last commit
@@ -122,4 +232,189 @@ impl GitStatus {
     pub fn has_changes(&self) -> bool {
         !self.staged_files.is_empty() || !self.unstaged_files.is_empty()
     }
+
+    /// Get the number of untracked entries
+    pub fn untracked_count(&self) -> usize {
+        self.unstaged_files
+            .iter()
+            .filter(|f| f.status == FileStatusType::Untracked)
+            .count()
+    }
+
+    /// Keep at most `max` untracked entries, returning how many were dropped
+    pub fn cap_untracked(&mut self, max: usize) -> usize {
+        let mut kept = 0;
+        let before = self.unstaged_files.len();
+        self.unstaged_files.retain(|f| {
+            if f.status != FileStatusType::Untracked {
+                return true;
+            }
+            kept += 1;
+            kept <= max
+        });
+        before - self.unstaged_files.len()
+    }
+
+    /// Check whether a path-limited refresh of `paths` could miss part of a rename
+    ///
+    /// Renames pair two paths, so limiting `git status` to one side would
+    /// report it as an add or delete; callers should do a full refresh instead.
+    pub fn touches_rename(&self, paths: &[String]) -> bool {
+        self.staged_files.iter().any(|file| {
+            file.old_path.as_ref().is_some_and(|old_path| {
+                paths
+                    .iter()
+                    .any(|p| path_is_within(&file.path, p) || path_is_within(old_path, p))
+            })
+        })
+    }
+
+    /// Replace the entries under `paths` with freshly collected ones
+    ///
+    /// `staged` and `unstaged` must be the complete status for `paths`, as
+    /// returned by a pathspec-limited `git status`. Entries keep git's order.
+    /// Returns the replaced entries, so callers can compare just those paths.
+    pub fn apply_path_update(
+        &mut self,
+        paths: &[String],
+        staged: Vec<FileStatus>,
+        unstaged: Vec<FileStatus>,
+    ) -> GitStatus {
+        let affected = |file: &FileStatus| paths.iter().any(|p| path_is_within(&file.path, p));
+
+        let (replaced_staged, kept) = std::mem::take(&mut self.staged_files)
+            .into_iter()
+            .partition(|file| affected(file));
+        self.staged_files = kept;
+        let (replaced_unstaged, kept) = std::mem::take(&mut self.unstaged_files)
+            .into_iter()
+            .partition(|file| affected(file));
+        self.unstaged_files = kept;
+        self.staged_files.extend(staged);
+        self.unstaged_files.extend(unstaged);
+
+        // git lists tracked changes first, then untracked files, each sorted by path
+        for files in [&mut self.staged_files, &mut self.unstaged_files] {
+            files.sort_by(|a, b| {
+                (a.status == FileStatusType::Untracked, &a.path)
+                    .cmp(&(b.status == FileStatusType::Untracked, &b.path))
+            });
+        }
+
+        GitStatus {
+            staged_files: replaced_staged,
+            unstaged_files: replaced_unstaged,
+            ..Default::default()
+        }
+    }
+}
+
+/// Result of a status refresh, to be applied to the current [`GitStatus`]
+#[derive(Debug, Clone)]
+pub enum StatusUpdate {
+    /// A complete status replacing the current one
+    Full(GitStatus),
+    /// Fresh entries for a set of paths (see [`GitStatus::apply_path_update`])
+    Paths {
+        paths: Vec<String>,
+        staged: Vec<FileStatus>,
+        unstaged: Vec<FileStatus>,
+    },
+}
+
+/// Check whether `path` is `prefix` itself or lies inside the directory `prefix`
+pub fn path_is_within(path: &str, prefix: &str) -> bool {
+    path == prefix
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// A line of progress output streamed from a long-running git command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgressLine {
+    /// Text of the line (without the trailing line terminator)
+    pub text: String,
+    /// Whether the line ended with a carriage return and will be overwritten
+    /// by the next line (e.g. "Receiving objects:  42%")
+    pub transient: bool,
+}
+
+/// Remote operations that can be run against the upstream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteOperation {
+    /// Push the current branch (setting upstream if missing)
+    Push,
+    /// Pull the current branch (fast-forward only unless rebase is requested)
+    Pull { rebase: bool },
+}
+
+impl RemoteOperation {
+    /// Get a human-readable label for this operation
+    pub fn label(&self) -> &'static str {
+        match self {
+            RemoteOperation::Push => "Push",
+            RemoteOperation::Pull { rebase: false } => "Pull (fast-forward)",
+            RemoteOperation::Pull { rebase: true } => "Pull (rebase)",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, status: FileStatusType, staged: bool) -> FileStatus {
+        FileStatus::new(path.to_string(), status, staged)
+    }
+
+    #[test]
+    fn test_apply_path_update_replaces_affected_entries() {
+        let mut status = GitStatus {
+            staged_files: vec![file("b.rs", FileStatusType::Added, true)],
+            unstaged_files: vec![
+                file("a.rs", FileStatusType::Modified, false),
+                file("dir/x.rs", FileStatusType::Modified, false),
+                file("dirx.rs", FileStatusType::Modified, false),
+                file("new.txt", FileStatusType::Untracked, false),
+            ],
+            ..Default::default()
+        };
+
+        // "dir" was removed and "c.rs" was modified
+        let replaced = status.apply_path_update(
+            &["dir".to_string(), "c.rs".to_string()],
+            vec![],
+            vec![file("c.rs", FileStatusType::Modified, false)],
+        );
+
+        let paths: Vec<&str> = status
+            .unstaged_files
+            .iter()
+            .map(|f| f.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["a.rs", "c.rs", "dirx.rs", "new.txt"]);
+        assert_eq!(status.staged_files.len(), 1);
+
+        let replaced: Vec<&str> = replaced
+            .unstaged_files
+            .iter()
+            .map(|f| f.path.as_str())
+            .collect();
+        assert_eq!(replaced, vec!["dir/x.rs"]);
+    }
+
+    #[test]
+    fn test_touches_rename() {
+        let mut renamed = file("new.rs", FileStatusType::Renamed, true);
+        renamed.old_path = Some("old.rs".to_string());
+        let status = GitStatus {
+            staged_files: vec![renamed],
+            ..Default::default()
+        };
+
+        assert!(status.touches_rename(&["old.rs".to_string()]));
+        assert!(status.touches_rename(&["new.rs".to_string()]));
+        assert!(!status.touches_rename(&["other.rs".to_string()]));
+    }
 }
@@ -0,0 +1,244 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use crate::git::types::{FileStatus, GitStatus};
+
+/// How a file differs from the previous status snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// Not listed before
+    Appeared,
+    /// Listed before with a different status or line counts
+    Changed,
+}
+
+/// Fading highlights for what changed between successive statuses and diffs
+#[derive(Debug)]
+pub struct ChangeHighlights {
+    /// How long a highlight takes to fade out (zero disables highlights)
+    duration: Duration,
+    /// Files that appeared or changed, by (staged, path)
+    files: HashMap<(bool, String), (ChangeKind, Instant)>,
+    /// Files no longer listed, shown as fading ghosts
+    removed: Vec<(FileStatus, Instant)>,
+    /// Changed hunks in the diff of the file shown last
+    hunks: Option<HunkHighlight>,
+}
+
+/// Hunks that changed in the last refresh of a file's diff
+#[derive(Debug)]
+struct HunkHighlight {
+    /// File (path, staged) the diff belongs to
+    key: (String, bool),
+    /// Line ranges of the changed hunks
+    ranges: Vec<Range<usize>>,
+    /// When the diff was refreshed
+    at: Instant,
+}
+
+impl ChangeHighlights {
+    /// Create highlights that fade out over `duration`
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            files: HashMap::new(),
+            removed: Vec::new(),
+            hunks: None,
+        }
+    }
+
+    /// Highlight the files that appeared, changed or disappeared between two statuses
+    pub fn record_status(&mut self, old: &GitStatus, new: &GitStatus, now: Instant) {
+        if self.duration.is_zero() {
+            return;
+        }
+        for (old_files, new_files) in [
+            (&old.staged_files, &new.staged_files),
+            (&old.unstaged_files, &new.unstaged_files),
+        ] {
+            let before: HashMap<&str, &FileStatus> = old_files
+                .iter()
+                .map(|file| (file.path.as_str(), file))
+                .collect();
+            let after: HashSet<&str> = new_files.iter().map(|file| file.path.as_str()).collect();
+
+            for file in new_files {
+                let kind = match before.get(file.path.as_str()) {
+                    None => ChangeKind::Appeared,
+                    Some(previous) if counts_differ(previous, file) => ChangeKind::Changed,
+                    Some(_) => continue,
+                };
+                self.files
+                    .insert((file.staged, file.path.clone()), (kind, now));
+            }
+            for file in old_files {
+                if !after.contains(file.path.as_str()) {
+                    self.removed.retain(|(ghost, _)| !same_entry(ghost, file));
+                    self.removed.push((file.clone(), now));
+                }
+            }
+        }
+        // A file that came back is no longer a ghost
+        self.removed.retain(|(ghost, _)| {
+            let files = if ghost.staged {
+                &new.staged_files
+            } else {
+                &new.unstaged_files
+            };
+            !files.iter().any(|file| file.path == ghost.path)
+        });
+    }
+
+    /// Highlight the hunks of a file's diff that differ from its previous diff
+    pub fn record_diff(&mut self, key: (String, bool), old: &str, new: &str, now: Instant) {
+        if self.duration.is_zero() {
+            return;
+        }
+        let previous: HashSet<String> = hunks(old).map(|hunk| body(old, hunk)).collect();
+        let changed: Vec<Range<usize>> = hunks(new)
+            .filter(|hunk| !previous.contains(&body(new, hunk.clone())))
+            .collect();
+        self.hunks = (!changed.is_empty()).then_some(HunkHighlight {
+            key,
+            ranges: changed,
+            at: now,
+        });
+    }
+
+    /// How a listed file changed, and how far its highlight has faded (0 to 1)
+    pub fn file(&self, file: &FileStatus, now: Instant) -> Option<(ChangeKind, f32)> {
+        let (kind, at) = self.files.get(&(file.staged, file.path.clone()))?;
+        self.progress(*at, now).map(|progress| (*kind, progress))
+    }
+
+    /// Files of a section that disappeared, with how far they have faded
+    pub fn removed(&self, staged: bool, now: Instant) -> impl Iterator<Item = (&FileStatus, f32)> {
+        self.removed
+            .iter()
+            .filter(move |(file, _)| file.staged == staged)
+            .filter_map(move |(file, at)| Some((file, self.progress(*at, now)?)))
+    }
+
+    /// Changed line ranges in the diff of a file, with how far they have faded
+    pub fn changed_hunks(
+        &self,
+        key: &(String, bool),
+        now: Instant,
+    ) -> Option<(&[Range<usize>], f32)> {
+        let hunks = self.hunks.as_ref().filter(|hunks| hunks.key == *key)?;
+        Some((&hunks.ranges, self.progress(hunks.at, now)?))
+    }
+
+    /// Drop highlights that have faded out, returning whether any were dropped
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let duration = self.duration;
+        let live = |at: &Instant| now.saturating_duration_since(*at) < duration;
+        let before = self.files.len() + self.removed.len() + self.hunks.is_some() as usize;
+        self.files.retain(|_, (_, at)| live(at));
+        self.removed.retain(|(_, at)| live(at));
+        if self.hunks.as_ref().is_some_and(|hunks| !live(&hunks.at)) {
+            self.hunks = None;
+        }
+        before != self.files.len() + self.removed.len() + self.hunks.is_some() as usize
+    }
+
+    /// Whether any highlight is still fading
+    pub fn is_active(&self) -> bool {
+        !self.files.is_empty() || !self.removed.is_empty() || self.hunks.is_some()
+    }
+
+    /// Fraction of the fade elapsed since `at`, None once it is over
+    fn progress(&self, at: Instant, now: Instant) -> Option<f32> {
+        let elapsed = now.saturating_duration_since(at);
+        (elapsed < self.duration).then(|| elapsed.as_secs_f32() / self.duration.as_secs_f32())
+    }
+}
+
+/// Whether a file's status or line counts differ
+fn counts_differ(old: &FileStatus, new: &FileStatus) -> bool {
+    old.status != new.status
+        || old.lines_added != new.lines_added
+        || old.lines_deleted != new.lines_deleted
+}
+
+/// Whether two entries are the same file in the same section
+fn same_entry(a: &FileStatus, b: &FileStatus) -> bool {
+    a.staged == b.staged && a.path == b.path
+}
+
+/// Line ranges of the hunks in a diff, each starting at its `@@` header
+fn hunks(diff: &str) -> impl Iterator<Item = Range<usize>> {
+    let starts: Vec<usize> = diff
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.starts_with("@@"))
+        .map(|(index, _)| index)
+        .collect();
+    let end = diff.lines().count();
+    let ends: Vec<usize> = starts.iter().skip(1).copied().chain([end]).collect();
+    starts.into_iter().zip(ends).map(|(start, end)| start..end)
+}
+
+/// The lines of a hunk without its header (whose line numbers shift when other hunks change)
+fn body(diff: &str, hunk: Range<usize>) -> String {
+    diff.lines()
+        .skip(hunk.start + 1)
+        .take(hunk.len() - 1)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::types::FileStatusType;
+
+    fn file(path: &str, lines: usize) -> FileStatus {
+        let mut file = FileStatus::new(path.to_string(), FileStatusType::Modified, false);
+        file.lines_added = lines;
+        file
+    }
+
+    #[test]
+    fn test_highlights_track_status_and_hunk_changes_until_they_fade() {
+        let mut highlights = ChangeHighlights::new(Duration::from_secs(2));
+        let now = Instant::now();
+        let old = GitStatus {
+            unstaged_files: vec![file("a.rs", 1), file("b.rs", 1), file("c.rs", 1)],
+            ..GitStatus::default()
+        };
+        let new = GitStatus {
+            unstaged_files: vec![file("a.rs", 1), file("b.rs", 2), file("d.rs", 1)],
+            ..GitStatus::default()
+        };
+        highlights.record_status(&old, &new, now);
+
+        let kind = |path, lines| {
+            highlights
+                .file(&file(path, lines), now)
+                .map(|(kind, _)| kind)
+        };
+        assert_eq!(kind("a.rs", 1), None);
+        assert_eq!(kind("b.rs", 2), Some(ChangeKind::Changed));
+        assert_eq!(kind("d.rs", 1), Some(ChangeKind::Appeared));
+        let removed: Vec<&str> = highlights
+            .removed(false, now)
+            .map(|(file, _)| file.path.as_str())
+            .collect();
+        assert_eq!(removed, vec!["c.rs"]);
+
+        // Only the second hunk's body changed; the first one just moved
+        let key = ("a.rs".to_string(), false);
+        let old_diff = "diff\n@@ -1 +1 @@\n-a\n+b\n@@ -9 +9 @@\n-x\n+y";
+        let new_diff = "diff\n@@ -1 +1,2 @@\n-a\n+b\n@@ -9 +10 @@\n-x\n+z";
+        highlights.record_diff(key.clone(), old_diff, new_diff, now);
+        let (ranges, _) = highlights.changed_hunks(&key, now).unwrap();
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(ranges[0], 4..7);
+
+        let later = now + Duration::from_secs(3);
+        assert!(highlights.expire(later));
+        assert!(!highlights.is_active());
+    }
+}
@@ -1,8 +1,15 @@
 mod app;
 mod config;
 mod event;
+mod file_tree;
+mod filter;
 mod git;
+mod highlights;
+mod search;
+mod sort;
 mod theme;
+mod timeline;
+mod toast;
 mod tui;
 mod ui;
 mod watcher;
@@ -10,10 +17,12 @@ mod watcher;
 use std::path::PathBuf;
 
 use clap::Parser;
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{Report, Result};
+use color_eyre::{Section, SectionExt};
 
 use crate::app::App;
 use crate::config::loader::ConfigLoader;
+use crate::git::runner::GitError;
 use crate::theme::themes::Theme;
 
 /// Git File Monitor - A TUI for monitoring Git repository changes in real-time
@@ -58,8 +67,27 @@ async fn main() -> Result<()> {
     let theme = Theme::from_name(&config.ui.color_scheme);
 
     // Create and run the application
-    let mut app = App::new(repo_path, config, theme, !cli.no_watch);
-    app.run().await?;
+    let mut app = App::new(repo_path, config, theme, !cli.no_watch)
+        .with_config_path(config_loader.config_path().clone());
+    app.run().await.map_err(with_git_details)?;
 
     Ok(())
 }
+
+/// Attach the failing git command and its stderr to an error report
+fn with_git_details(report: Report) -> Report {
+    let Some(git_error) = report.downcast_ref::<GitError>() else {
+        return report;
+    };
+
+    let command = format!("git {}", git_error.args.join(" "));
+    let exit = git_error
+        .exit_code
+        .map_or_else(|| "none".to_string(), |code| code.to_string());
+    let stderr = git_error.stderr.trim().to_string();
+
+    report
+        .with_section(move || command.header("Command:"))
+        .with_section(move || exit.header("Exit code:"))
+        .with_section(move || stderr.header("Stderr:"))
+}
@@ -0,0 +1,177 @@
+use regex::{Regex, RegexBuilder};
+
+/// A match in the diff preview
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchMatch {
+    /// Line index in the diff
+    pub line: usize,
+    /// Start of the match (byte offset within the line)
+    pub start: usize,
+    /// End of the match (exclusive byte offset)
+    pub end: usize,
+}
+
+/// Search state of the diff preview
+#[derive(Debug, Clone, Default)]
+pub struct DiffSearch {
+    /// What the user typed
+    pub query: String,
+    /// Treat the query as a regular expression instead of literal text
+    pub regex: bool,
+    /// Match case exactly (otherwise case-insensitive)
+    pub case_sensitive: bool,
+    /// Matches in the current diff, in order
+    pub matches: Vec<SearchMatch>,
+    /// Index of the match the preview is showing
+    pub current: Option<usize>,
+    /// Why the query could not be used (invalid regex)
+    pub error: Option<String>,
+}
+
+impl DiffSearch {
+    /// Whether a search is in progress
+    pub fn is_active(&self) -> bool {
+        !self.query.is_empty()
+    }
+
+    /// Recompute the matches for `text`, making the first match at or after `from_line` current
+    pub fn update(&mut self, text: &str, from_line: usize) {
+        self.matches.clear();
+        self.current = None;
+        self.error = None;
+        if self.query.is_empty() {
+            return;
+        }
+
+        let pattern = match self.pattern() {
+            Ok(pattern) => pattern,
+            Err(error) => {
+                self.error = Some(error.to_string());
+                return;
+            }
+        };
+
+        for (line_index, line) in text.lines().enumerate() {
+            self.matches
+                .extend(
+                    pattern
+                        .find_iter(line)
+                        .filter(|m| !m.is_empty())
+                        .map(|m| SearchMatch {
+                            line: line_index,
+                            start: m.start(),
+                            end: m.end(),
+                        }),
+                );
+        }
+
+        if !self.matches.is_empty() {
+            let first = self.matches.iter().position(|m| m.line >= from_line);
+            self.current = Some(first.unwrap_or(0));
+        }
+    }
+
+    /// Move to the next match (wrapping around)
+    pub fn next(&mut self) -> Option<SearchMatch> {
+        let len = self.matches.len();
+        if len == 0 {
+            return None;
+        }
+        let index = self.current.map_or(0, |current| (current + 1) % len);
+        self.current = Some(index);
+        Some(self.matches[index])
+    }
+
+    /// Move to the previous match (wrapping around)
+    pub fn previous(&mut self) -> Option<SearchMatch> {
+        let len = self.matches.len();
+        if len == 0 {
+            return None;
+        }
+        let index = self
+            .current
+            .map_or(len - 1, |current| (current + len - 1) % len);
+        self.current = Some(index);
+        Some(self.matches[index])
+    }
+
+    /// The match the preview is showing
+    pub fn current_match(&self) -> Option<SearchMatch> {
+        self.current.map(|index| self.matches[index])
+    }
+
+    /// Forget the query and its matches (keeping the regex/case settings)
+    pub fn clear(&mut self) {
+        self.query.clear();
+        self.matches.clear();
+        self.current = None;
+        self.error = None;
+    }
+
+    /// Build the regex for the query
+    fn pattern(&self) -> Result<Regex, regex::Error> {
+        let source = if self.regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+        RegexBuilder::new(&source)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "@@ -1,2 +1,2 @@\n-let count = 1;\n+let Count = count + 1;\n fn main() {}";
+
+    #[test]
+    fn test_literal_search_is_case_insensitive_by_default() {
+        let mut search = DiffSearch {
+            query: "count".to_string(),
+            ..DiffSearch::default()
+        };
+        search.update(DIFF, 2);
+        assert_eq!(search.matches.len(), 3);
+        // The first match at or after line 2 is current
+        assert_eq!(
+            search.current_match(),
+            Some(SearchMatch {
+                line: 2,
+                start: 5,
+                end: 10
+            })
+        );
+
+        search.case_sensitive = true;
+        search.update(DIFF, 0);
+        assert_eq!(search.matches.len(), 2);
+        assert_eq!(search.next().map(|m| m.line), Some(2));
+        assert_eq!(search.next().map(|m| m.line), Some(1));
+        assert_eq!(search.previous().map(|m| m.line), Some(2));
+    }
+
+    #[test]
+    fn test_regex_search_and_invalid_patterns() {
+        let mut search = DiffSearch {
+            query: r"^[+-]let \w+".to_string(),
+            regex: true,
+            ..DiffSearch::default()
+        };
+        search.update(DIFF, 0);
+        assert_eq!(search.matches.len(), 2);
+
+        // Without regex mode the same query is literal text
+        search.regex = false;
+        search.update(DIFF, 0);
+        assert!(search.matches.is_empty());
+
+        search.regex = true;
+        search.query = "(".to_string();
+        search.update(DIFF, 0);
+        assert!(search.error.is_some());
+        assert_eq!(search.current_match(), None);
+    }
+}
@@ -0,0 +1,78 @@
+use std::cmp::Reverse;
+
+use crate::config::types::SortMode;
+use crate::filter::extension_of;
+use crate::git::types::{FileStatus, FileStatusType};
+
+/// Sort the files of a section, ties broken by path
+///
+/// `Modified` uses the modification times recorded with the status; files
+/// missing from disk (deleted) go last.
+pub fn sort_files(files: &mut [FileStatus], mode: SortMode) {
+    match mode {
+        SortMode::Path => files.sort_by(|a, b| a.path.cmp(&b.path)),
+        SortMode::Status => files.sort_by_key(|file| (status_rank(file.status), file.path.clone())),
+        SortMode::Lines => files.sort_by_key(|file| {
+            (
+                Reverse(file.lines_added + file.lines_deleted),
+                file.path.clone(),
+            )
+        }),
+        SortMode::Extension => files.sort_by_key(|file| {
+            (
+                extension_of(&file.path).unwrap_or_default().to_string(),
+                file.path.clone(),
+            )
+        }),
+        SortMode::Modified => files.sort_by_key(|file| (Reverse(file.modified), file.path.clone())),
+    }
+}
+
+/// Position of a status type in the status sort order
+fn status_rank(status: FileStatusType) -> usize {
+    FileStatusType::ALL
+        .iter()
+        .position(|s| *s == status)
+        .unwrap_or(usize::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+
+    fn file(path: &str, status: FileStatusType, lines: usize) -> FileStatus {
+        let mut file = FileStatus::new(path.to_string(), status, false);
+        file.lines_added = lines;
+        file
+    }
+
+    fn paths(files: &[FileStatus]) -> Vec<&str> {
+        files.iter().map(|file| file.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_sort_files_by_each_mode() {
+        let now = SystemTime::now();
+        let mut files = vec![
+            file("c.md", FileStatusType::Untracked, 1),
+            file("a.txt", FileStatusType::Added, 5),
+            file("b.rs", FileStatusType::Modified, 3),
+            file("gone.rs", FileStatusType::Deleted, 9),
+        ];
+        for (file, age) in files.iter_mut().zip([10, 20, 30]) {
+            file.modified = Some(now - Duration::from_secs(age));
+        }
+
+        let mut sorted = |mode| {
+            sort_files(&mut files, mode);
+            paths(&files).join(" ")
+        };
+        assert_eq!(sorted(SortMode::Path), "a.txt b.rs c.md gone.rs");
+        assert_eq!(sorted(SortMode::Status), "b.rs a.txt gone.rs c.md");
+        assert_eq!(sorted(SortMode::Lines), "gone.rs a.txt b.rs c.md");
+        assert_eq!(sorted(SortMode::Extension), "c.md b.rs gone.rs a.txt");
+        assert_eq!(sorted(SortMode::Modified), "c.md a.txt b.rs gone.rs");
+    }
+}
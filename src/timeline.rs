@@ -0,0 +1,222 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Instant;
+
+use crate::git::types::{FileBlobs, FileStatus, GitStatus};
+
+/// Most refreshes kept in the timeline; older ones are dropped
+const MAX_ENTRIES: usize = 500;
+
+/// A file touched by a refresh
+#[derive(Debug, Clone)]
+pub struct TimelineFile {
+    /// The file after the refresh (or before it, if it is no longer listed)
+    pub file: FileStatus,
+    /// Whether the file is no longer listed in its section
+    pub gone: bool,
+    /// Change in lines added since the previous refresh
+    pub added_delta: isize,
+    /// Change in lines deleted since the previous refresh
+    pub deleted_delta: isize,
+    /// Blobs of the file right after the refresh (None until captured)
+    pub blobs: Option<FileBlobs>,
+}
+
+/// A refresh in which the status changed
+#[derive(Debug, Clone)]
+pub struct TimelineEntry {
+    /// Identifies the entry when its blobs arrive
+    pub id: u64,
+    /// When the refresh was applied
+    pub at: Instant,
+    /// Files that appeared, changed or disappeared
+    pub files: Vec<TimelineFile>,
+}
+
+impl TimelineEntry {
+    /// Net change in lines added and deleted across the entry's files
+    pub fn deltas(&self) -> (isize, isize) {
+        self.files.iter().fold((0, 0), |(added, deleted), file| {
+            (added + file.added_delta, deleted + file.deleted_delta)
+        })
+    }
+}
+
+/// Every refresh of the session in which the status changed
+#[derive(Debug, Default)]
+pub struct Timeline {
+    /// Entries, oldest first
+    entries: VecDeque<TimelineEntry>,
+    /// Id of the next entry
+    next_id: u64,
+}
+
+impl Timeline {
+    /// Record the files touched between two statuses
+    ///
+    /// Returns the new entry's id and the files still listed (whose blobs
+    /// should be captured), or None if nothing changed.
+    pub fn record(
+        &mut self,
+        old: &GitStatus,
+        new: &GitStatus,
+        now: Instant,
+    ) -> Option<(u64, Vec<FileStatus>)> {
+        let mut files = Vec::new();
+        for (old_files, new_files) in [
+            (&old.staged_files, &new.staged_files),
+            (&old.unstaged_files, &new.unstaged_files),
+        ] {
+            let before: HashMap<&str, &FileStatus> = old_files
+                .iter()
+                .map(|file| (file.path.as_str(), file))
+                .collect();
+            let after: HashSet<&str> = new_files.iter().map(|file| file.path.as_str()).collect();
+
+            for file in new_files {
+                let previous = before.get(file.path.as_str());
+                if previous.is_some_and(|previous| !differs(previous, file)) {
+                    continue;
+                }
+                let (added, deleted) =
+                    previous.map_or((0, 0), |p| (p.lines_added, p.lines_deleted));
+                files.push(TimelineFile {
+                    file: file.clone(),
+                    gone: false,
+                    added_delta: file.lines_added as isize - added as isize,
+                    deleted_delta: file.lines_deleted as isize - deleted as isize,
+                    blobs: None,
+                });
+            }
+            for file in old_files {
+                if !after.contains(file.path.as_str()) {
+                    files.push(TimelineFile {
+                        file: file.clone(),
+                        gone: true,
+                        added_delta: -(file.lines_added as isize),
+                        deleted_delta: -(file.lines_deleted as isize),
+                        blobs: None,
+                    });
+                }
+            }
+        }
+        if files.is_empty() {
+            return None;
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let listed = files
+            .iter()
+            .filter(|entry| !entry.gone)
+            .map(|entry| entry.file.clone())
+            .collect();
+        self.entries.push_back(TimelineEntry { id, at: now, files });
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        Some((id, listed))
+    }
+
+    /// Attach captured blobs to the listed files of an entry, in the order `record` returned them
+    ///
+    /// Files past the end of `blobs` (all of them if capturing failed) get empty blobs.
+    pub fn set_blobs(&mut self, id: u64, blobs: Vec<FileBlobs>) {
+        let Some(entry) = self.entries.iter_mut().find(|entry| entry.id == id) else {
+            return;
+        };
+        let mut blobs = blobs.into_iter();
+        for file in entry.files.iter_mut().filter(|file| !file.gone) {
+            file.blobs = Some(blobs.next().unwrap_or_default());
+        }
+    }
+
+    /// One row per touched file, newest entry first
+    pub fn rows(&self) -> impl Iterator<Item = (&TimelineEntry, &TimelineFile)> {
+        self.entries
+            .iter()
+            .rev()
+            .flat_map(|entry| entry.files.iter().map(move |file| (entry, file)))
+    }
+
+    /// Number of rows
+    pub fn row_count(&self) -> usize {
+        self.entries.iter().map(|entry| entry.files.len()).sum()
+    }
+
+    /// Number of recorded refreshes
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no refresh has changed the status yet
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Whether a file's status or line counts differ
+fn differs(old: &FileStatus, new: &FileStatus) -> bool {
+    old.status != new.status
+        || old.lines_added != new.lines_added
+        || old.lines_deleted != new.lines_deleted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::types::FileStatusType;
+
+    fn file(path: &str, added: usize, staged: bool) -> FileStatus {
+        let mut file = FileStatus::new(path.to_string(), FileStatusType::Modified, staged);
+        file.lines_added = added;
+        file
+    }
+
+    #[test]
+    fn test_timeline_records_deltas_and_attaches_blobs() {
+        let mut timeline = Timeline::default();
+        let now = Instant::now();
+        let first = GitStatus {
+            unstaged_files: vec![file("a.rs", 2, false), file("b.rs", 1, false)],
+            ..GitStatus::default()
+        };
+        // Staging b.rs and editing a.rs
+        let second = GitStatus {
+            staged_files: vec![file("b.rs", 1, true)],
+            unstaged_files: vec![file("a.rs", 5, false)],
+            ..GitStatus::default()
+        };
+
+        assert!(timeline.record(&first, &first, now).is_none());
+        let (id, listed) = timeline.record(&first, &second, now).unwrap();
+        let paths: Vec<(&str, bool)> = listed
+            .iter()
+            .map(|file| (file.path.as_str(), file.staged))
+            .collect();
+        assert_eq!(paths, vec![("b.rs", true), ("a.rs", false)]);
+
+        let rows: Vec<(&str, bool, isize)> = timeline
+            .rows()
+            .map(|(_, row)| (row.file.path.as_str(), row.gone, row.added_delta))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![("b.rs", false, 1), ("a.rs", false, 3), ("b.rs", true, -1)]
+        );
+        assert_eq!(timeline.rows().next().unwrap().0.deltas(), (3, 0));
+
+        let blobs = FileBlobs {
+            base: Some("base".to_string()),
+            current: Some("current".to_string()),
+        };
+        timeline.set_blobs(id, vec![blobs.clone()]);
+        let captured: Vec<Option<FileBlobs>> =
+            timeline.rows().map(|(_, row)| row.blobs.clone()).collect();
+        assert_eq!(
+            captured,
+            vec![Some(blobs), Some(FileBlobs::default()), None]
+        );
+        assert_eq!(timeline.len(), 1);
+        assert_eq!(timeline.row_count(), 3);
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::Report;
+
+use crate::git::runner::GitError;
+
+/// Number of notifications kept in the history view
+const HISTORY_CAPACITY: usize = 100;
+
+/// Maximum number of toasts shown at once
+const MAX_ACTIVE_TOASTS: usize = 3;
+
+/// Severity of a notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// Get a short label for this severity
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Success => "ok",
+            Severity::Warning => "warn",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// A single notification
+#[derive(Debug, Clone)]
+pub struct Toast {
+    /// Severity of the notification
+    pub severity: Severity,
+    /// One-line message
+    pub message: String,
+    /// Optional detail (e.g. the stderr of a failed git command)
+    pub detail: Option<String>,
+    /// When the notification was raised
+    pub created_at: Instant,
+}
+
+impl Toast {
+    /// Create a new notification
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            detail: None,
+            created_at: Instant::now(),
+        }
+    }
+
+    /// Attach a detail to the notification
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        let detail = detail.into();
+        if !detail.trim().is_empty() {
+            self.detail = Some(detail);
+        }
+        self
+    }
+
+    /// Create an error notification from an error report
+    ///
+    /// If the report wraps a [`GitError`], its stderr becomes the detail.
+    pub fn from_report(report: &Report) -> Self {
+        let toast = Self::new(Severity::Error, report.to_string());
+        match report.downcast_ref::<GitError>() {
+            Some(git_error) => toast.with_detail(format!(
+                "git {}\n{}",
+                git_error.args.join(" "),
+                git_error.stderr.trim_end()
+            )),
+            None => toast,
+        }
+    }
+}
+
+/// Active toasts plus a history of everything that was shown
+#[derive(Debug, Clone)]
+pub struct Notifications {
+    /// Toasts currently on screen (oldest first)
+    active: Vec<Toast>,
+    /// All notifications raised this session (oldest first)
+    history: VecDeque<Toast>,
+    /// How long a toast stays on screen
+    timeout: Duration,
+}
+
+impl Notifications {
+    /// Create an empty notification store with the given auto-dismiss timeout
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            active: Vec::new(),
+            history: VecDeque::new(),
+            timeout,
+        }
+    }
+
+    /// Raise a notification
+    pub fn push(&mut self, toast: Toast) {
+        if self.history.len() >= HISTORY_CAPACITY {
+            self.history.pop_front();
+        }
+        self.history.push_back(toast.clone());
+
+        self.active.push(toast);
+        if self.active.len() > MAX_ACTIVE_TOASTS {
+            self.active.remove(0);
+        }
+    }
+
+    /// Raise an error notification for a recoverable failure
+    pub fn error(&mut self, report: &Report) {
+        self.push(Toast::from_report(report));
+    }
+
+    /// Remove toasts that have been on screen long enough
+    ///
+    /// Errors stay twice as long as other notifications.
+    /// Returns true if any toast was removed.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let before = self.active.len();
+        let timeout = self.timeout;
+        self.active.retain(|toast| {
+            let lifetime = match toast.severity {
+                Severity::Error => timeout * 2,
+                _ => timeout,
+            };
+            now.duration_since(toast.created_at) < lifetime
+        });
+        self.active.len() != before
+    }
+
+    /// Dismiss all toasts currently on screen
+    pub fn dismiss_all(&mut self) {
+        self.active.clear();
+    }
+
+    /// Toasts currently on screen
+    pub fn active(&self) -> &[Toast] {
+        &self.active
+    }
+
+    /// All notifications raised this session, oldest first
+    pub fn history(&self) -> impl DoubleEndedIterator<Item = &Toast> {
+        self.history.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toasts_expire_but_stay_in_history() {
+        let mut notifications = Notifications::new(Duration::from_secs(1));
+        notifications.push(Toast::new(Severity::Info, "info"));
+        notifications.push(Toast::new(Severity::Error, "error"));
+
+        let later = Instant::now() + Duration::from_millis(1500);
+        assert!(notifications.expire(later));
+        assert_eq!(notifications.active().len(), 1);
+        assert_eq!(notifications.active()[0].severity, Severity::Error);
+        assert_eq!(notifications.history().count(), 2);
+
+        assert!(notifications.expire(later + Duration::from_secs(1)));
+        assert!(notifications.active().is_empty());
+    }
+
+    #[test]
+    fn test_active_toasts_are_capped() {
+        let mut notifications = Notifications::new(Duration::from_secs(5));
+        for i in 0..5 {
+            notifications.push(Toast::new(Severity::Info, format!("toast {}", i)));
+        }
+
+        assert_eq!(notifications.active().len(), MAX_ACTIVE_TOASTS);
+        assert_eq!(notifications.active()[0].message, "toast 2");
+        assert_eq!(notifications.history().count(), 5);
+    }
+}
@@ -89,6 +89,14 @@ fn build_branch_info_content<'a>(status: &GitStatus, theme: &Theme) -> Vec<Line<
         }
     }
 
+    // Add stash count
+    if status.branch.stash_count > 0 {
+        branch_spans.push(Span::styled(
+            format!("  stash: {}", status.branch.stash_count),
+            Style::default().fg(theme.subtext),
+        ));
+    }
+
     lines.push(Line::from(branch_spans));
 
     // Last commit line
@@ -0,0 +1,168 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Row, Table, TableState, Wrap},
+    Frame,
+};
+
+use crate::app::App;
+use crate::git::runner::CommandRecord;
+use crate::theme::Theme;
+
+use super::utils::sanitize_text;
+
+/// Render the git command console overlay
+pub fn render_command_console(frame: &mut Frame, app: &App) {
+    let theme = &app.theme;
+    let area = frame.area();
+
+    // Take most of the screen, leaving a small margin
+    let popup_area = Rect::new(
+        area.x + 2,
+        area.y + 1,
+        area.width.saturating_sub(4),
+        area.height.saturating_sub(2),
+    );
+
+    frame.render_widget(Clear, popup_area);
+
+    // Most recent command first
+    let mut records = app.git_service.command_log().snapshot();
+    records.reverse();
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent))
+        .title(Span::styled(
+            format!(" Git Commands ({}) ", records.len()),
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD),
+        ))
+        .title_bottom(Line::from(Span::styled(
+            " ↑/↓ browse  ` or Esc to close ",
+            Style::default().fg(theme.subtext),
+        )))
+        .style(Style::default().bg(theme.overlay));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    if records.is_empty() {
+        let paragraph =
+            Paragraph::new(" No git commands run yet").style(Style::default().fg(theme.subtext));
+        frame.render_widget(paragraph, inner);
+        return;
+    }
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(60), Constraint::Min(4)])
+        .split(inner);
+
+    let rows: Vec<Row> = records
+        .iter()
+        .map(|record| create_record_row(record, theme))
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(2),
+            Constraint::Length(8),
+            Constraint::Length(9),
+            Constraint::Min(20),
+        ],
+    )
+    .row_highlight_style(Style::default().bg(theme.selection));
+
+    let selected = app.console_selected.min(records.len() - 1);
+    let mut state = TableState::default();
+    state.select(Some(selected));
+    frame.render_stateful_widget(table, layout[0], &mut state);
+
+    render_record_details(frame, layout[1], &records[selected], theme);
+}
+
+/// Create a table row summarizing a command
+fn create_record_row<'a>(record: &CommandRecord, theme: &Theme) -> Row<'a> {
+    let (icon, color) = if record.success() {
+        ("✓", theme.success)
+    } else {
+        ("✗", theme.error)
+    };
+
+    Row::new(vec![
+        Span::styled(icon, Style::default().fg(color)),
+        Span::styled(
+            format!("{}s ago", record.started_at.elapsed().as_secs()),
+            Style::default().fg(theme.subtext),
+        ),
+        Span::styled(
+            format!("{}ms", record.duration.as_millis()),
+            Style::default().fg(theme.warning),
+        ),
+        Span::styled(
+            sanitize_text(&record.command_line()),
+            Style::default().fg(theme.text),
+        ),
+    ])
+}
+
+/// Render the argv, exit code and stderr of the selected command
+fn render_record_details(frame: &mut Frame, area: Rect, record: &CommandRecord, theme: &Theme) {
+    let exit = match record.exit_code {
+        Some(code) => code.to_string(),
+        None => "none (not started or killed)".to_string(),
+    };
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled(" Command: ", Style::default().fg(theme.subtext)),
+            Span::styled(
+                sanitize_text(&record.command_line()),
+                Style::default().fg(theme.text),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled(" Exit: ", Style::default().fg(theme.subtext)),
+            Span::styled(
+                exit,
+                Style::default().fg(if record.success() {
+                    theme.success
+                } else {
+                    theme.error
+                }),
+            ),
+            Span::styled("  Duration: ", Style::default().fg(theme.subtext)),
+            Span::styled(
+                format!("{}ms", record.duration.as_millis()),
+                Style::default().fg(theme.text),
+            ),
+        ]),
+    ];
+
+    if record.stderr.trim().is_empty() {
+        lines.push(Line::from(Span::styled(
+            " (no stderr)",
+            Style::default().fg(theme.subtext),
+        )));
+    } else {
+        for line in record.stderr.lines() {
+            lines.push(Line::from(Span::styled(
+                format!(" {}", sanitize_text(line)),
+                Style::default().fg(theme.error),
+            )));
+        }
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .border_style(Style::default().fg(theme.border)),
+        )
+        .wrap(Wrap { trim: false });
+    frame.render_widget(paragraph, area);
+}
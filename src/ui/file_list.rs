@@ -1,73 +1,112 @@
+use std::time::Instant;
+
 use ratatui::{
     layout::Rect,
     style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, ListState},
+    widgets::{Block, List, ListItem, ListState},
     Frame,
 };
 
-use crate::app::{App, Section};
-use crate::git::types::{FileStatus, FileStatusType};
+use crate::app::{App, FileListLine, Focus, Section};
+use crate::file_tree::{DirRow, FileRow, FileView};
+use crate::git::types::{FileStatus, FileStatusType, UntrackedMode};
+use crate::highlights::ChangeKind;
 use crate::theme::Theme;
 
-use super::utils::sanitize_text;
+use super::utils::{fading_background, sanitize_text};
 
 /// Render the file list section
 pub fn render_file_list(frame: &mut Frame, area: Rect, app: &mut App) {
+    app.file_list_area = area;
+    // What each list item is, for mouse clicks
+    let mut lines = Vec::new();
     let theme = &app.theme;
+    let now = Instant::now();
 
     if let Some(status) = &app.git_status {
         let mut items: Vec<ListItem> = Vec::new();
-        let mut current_list_index = 0;
         let mut selected_list_index = None;
 
-        // Staged files section
-        if !status.staged_files.is_empty() {
-            // Section header
-            items.push(ListItem::new(Line::from(vec![Span::styled(
-                format!("  STAGED CHANGES ({}) ", status.staged_files.len()),
-                Style::default()
-                    .fg(theme.staged)
-                    .add_modifier(Modifier::BOLD),
-            )])));
-            current_list_index += 1;
-
-            // Staged files
-            for (i, file) in status.staged_files.iter().enumerate() {
-                let is_selected =
-                    app.selected_section == Section::Staged && app.selected_index == i;
-                if is_selected {
-                    selected_list_index = Some(current_list_index);
-                }
-                items.push(create_file_item(file, is_selected, true, theme));
-                current_list_index += 1;
+        for section in [Section::Staged, Section::Unstaged] {
+            let files = app.section_files(section);
+            if files.is_empty() {
+                continue;
             }
 
             // Empty line separator
-            items.push(ListItem::new(Line::from("")));
-            current_list_index += 1;
-        }
+            if !items.is_empty() {
+                items.push(ListItem::new(Line::from("")));
+                lines.push(FileListLine::Blank);
+            }
 
-        // Unstaged files section
-        if !status.unstaged_files.is_empty() {
             // Section header
+            let (label, color) = match section {
+                Section::Staged => ("STAGED", theme.staged),
+                Section::Unstaged => ("UNSTAGED", theme.unstaged),
+            };
+            let count = if app.filter.is_active() {
+                format!("{}/{}", app.visible_files(section).count(), files.len())
+            } else {
+                files.len().to_string()
+            };
+            let marker = if app.collapsed_sections.contains(&section) {
+                "▸"
+            } else {
+                " "
+            };
             items.push(ListItem::new(Line::from(vec![Span::styled(
-                format!("  UNSTAGED CHANGES ({}) ", status.unstaged_files.len()),
-                Style::default()
-                    .fg(theme.unstaged)
-                    .add_modifier(Modifier::BOLD),
+                format!(" {}{} CHANGES ({}) ", marker, label, count),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
             )])));
-            current_list_index += 1;
+            lines.push(FileListLine::Header(section));
 
-            // Unstaged files
-            for (i, file) in status.unstaged_files.iter().enumerate() {
-                let is_selected =
-                    app.selected_section == Section::Unstaged && app.selected_index == i;
+            for (i, row) in app.section_rows(section).into_iter().enumerate() {
+                let is_selected = app.selected_section == section && app.selected_index == i;
                 if is_selected {
-                    selected_list_index = Some(current_list_index);
+                    selected_list_index = Some(items.len());
+                }
+                lines.push(FileListLine::Row(section, i));
+                items.push(match row {
+                    FileRow::File { index, depth, name } => {
+                        let file = &files[index];
+                        let matched = label_matches(app, file, &name);
+                        let indicator =
+                            file_indicator(section, app.is_marked(section, &file.path), theme);
+                        let item = create_file_item(
+                            file,
+                            &name,
+                            &matched,
+                            depth,
+                            is_selected,
+                            indicator,
+                            theme,
+                        );
+                        match app.highlights.file(file, now) {
+                            Some((kind, progress)) if !is_selected => {
+                                let color = match kind {
+                                    ChangeKind::Appeared => theme.success,
+                                    ChangeKind::Changed => theme.warning,
+                                };
+                                item.style(
+                                    Style::default()
+                                        .bg(fading_background(color, theme.base, progress)),
+                                )
+                            }
+                            _ => item,
+                        }
+                    }
+                    FileRow::Dir(dir) => create_dir_item(&dir, is_selected, theme),
+                });
+            }
+
+            // Files that just disappeared fade out below the section
+            if !app.collapsed_sections.contains(&section) {
+                let staged = section == Section::Staged;
+                for (file, progress) in app.highlights.removed(staged, now) {
+                    items.push(create_removed_item(file, progress, theme));
+                    lines.push(FileListLine::Blank);
                 }
-                items.push(create_file_item(file, is_selected, false, theme));
-                current_list_index += 1;
             }
         }
 
@@ -77,64 +116,122 @@ pub fn render_file_list(frame: &mut Frame, area: Rect, app: &mut App) {
                 "  No changes detected",
                 Style::default().fg(theme.subtext),
             )])));
+        } else if app.section_rows(Section::Staged).is_empty()
+            && app.section_rows(Section::Unstaged).is_empty()
+        {
+            items.push(ListItem::new(Line::from(vec![Span::styled(
+                "  No files match the filter",
+                Style::default().fg(theme.subtext),
+            )])));
         }
 
-        let block = Block::default()
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(theme.border))
+        let mut title = format!(" Files (sort: {}) ", app.sort_mode.label());
+        if app.file_view == FileView::Tree {
+            title.push_str("(tree) ");
+        }
+        if app.untracked_mode != UntrackedMode::All {
+            title.push_str(&format!("(untracked: {}) ", app.untracked_mode.as_arg()));
+        }
+        if !app.marks.is_empty() {
+            title.push_str(&format!("({} marked) ", app.marks.len()));
+        }
+        let border_color = if app.focus == Focus::Files {
+            theme.accent
+        } else {
+            theme.border
+        };
+        let mut block = Block::default()
+            .borders(app.pane_layout.list_borders())
+            .border_style(Style::default().fg(border_color))
             .title(Span::styled(
-                " Files ",
+                title,
                 Style::default()
                     .fg(theme.accent)
                     .add_modifier(Modifier::BOLD),
             ));
+        if app.filter_input || app.filter.is_active() {
+            block = block.title_bottom(filter_line(app, theme));
+        }
 
         let list = List::new(items).block(block);
 
         // Create list state for scrolling
-        let mut list_state = ListState::default();
+        let mut list_state = ListState::default().with_offset(app.file_list_offset);
         list_state.select(selected_list_index);
 
         frame.render_stateful_widget(list, area, &mut list_state);
+        app.file_list_offset = list_state.offset();
     } else {
         let block = Block::default()
-            .borders(Borders::ALL)
+            .borders(app.pane_layout.list_borders())
             .border_style(Style::default().fg(theme.border))
             .title(" Files ");
 
         let list = List::new(vec![ListItem::new("Loading...")]).block(block);
         frame.render_widget(list, area);
     }
+    app.file_list_lines = lines;
 }
 
-/// Create a list item for a file
+/// The filter prompt and active quick filters, shown below the list
+fn filter_line<'a>(app: &App, theme: &Theme) -> Line<'a> {
+    let mut spans = vec![
+        Span::styled(" /", Style::default().fg(theme.accent)),
+        Span::styled(
+            sanitize_text(&app.filter.query),
+            Style::default().fg(theme.text),
+        ),
+    ];
+    if app.filter_input {
+        spans.push(Span::styled("▏", Style::default().fg(theme.accent)));
+    }
+    if let Some(status) = app.filter.status {
+        spans.push(Span::styled(
+            format!(" · {}", status.label()),
+            Style::default().fg(get_status_color(status, theme)),
+        ));
+    }
+    if let Some(extension) = &app.filter.extension {
+        spans.push(Span::styled(
+            format!(" · *.{}", sanitize_text(extension)),
+            Style::default().fg(theme.info),
+        ));
+    }
+    spans.push(Span::raw(" "));
+    Line::from(spans)
+}
+
+/// Positions in a file's label (char indices) matched by the filter query
+fn label_matches(app: &App, file: &FileStatus, label: &str) -> Vec<usize> {
+    let Some(matched) = app.filter.matches(file) else {
+        return Vec::new();
+    };
+    // In the tree the label is the end of the path
+    let offset = file.path.chars().count() - label.chars().count();
+    matched
+        .into_iter()
+        .filter_map(|position| position.checked_sub(offset))
+        .collect()
+}
+
+/// Create a list item for a file, labelled with its full path or (in the tree) its name
+///
+/// `matched` lists char indices of the label to highlight.
 fn create_file_item<'a>(
     file: &FileStatus,
+    label: &str,
+    matched: &[usize],
+    depth: usize,
     is_selected: bool,
-    is_staged: bool,
+    indicator: Span<'a>,
     theme: &Theme,
 ) -> ListItem<'a> {
-    let mut spans = Vec::new();
-
-    // Selection indicator
-    if is_selected {
-        spans.push(Span::styled(" ▌", Style::default().fg(theme.accent)));
-    } else {
-        spans.push(Span::raw("  "));
-    }
-
-    // Staged/unstaged indicator
-    let indicator = if is_staged { "●" } else { "○" };
-    let indicator_color = if is_staged {
-        theme.staged
-    } else {
-        theme.unstaged
-    };
-    spans.push(Span::styled(
+    let mut spans = vec![
+        selection_indicator(is_selected, theme),
+        indent(depth),
         indicator,
-        Style::default().fg(indicator_color),
-    ));
-    spans.push(Span::raw(" "));
+        Span::raw(" "),
+    ];
 
     // Status code
     let status_color = get_status_color(file.status, theme);
@@ -154,39 +251,151 @@ fn create_file_item<'a>(
     } else {
         Style::default().fg(theme.text)
     };
-    spans.push(Span::styled(sanitize_text(&file.path), path_style));
+    let match_style = Style::default()
+        .fg(theme.accent)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    push_highlighted(&mut spans, label, matched, path_style, match_style);
+
+    // Collapsed untracked directory (Enter expands it)
+    if file.is_untracked_dir() {
+        spans.push(Span::styled(" ▸", Style::default().fg(theme.subtext)));
+    }
+
+    push_line_changes(&mut spans, file.lines_added, file.lines_deleted, theme);
+
+    let mut item = ListItem::new(Line::from(spans));
 
-    // Line changes (if any)
-    if file.lines_added > 0 || file.lines_deleted > 0 {
+    if is_selected {
+        item = item.style(Style::default().bg(theme.selection));
+    }
+
+    item
+}
+
+/// Create a list item for a directory node with its rolled-up counts
+fn create_dir_item<'a>(dir: &DirRow, is_selected: bool, theme: &Theme) -> ListItem<'a> {
+    let mut spans = vec![selection_indicator(is_selected, theme), indent(dir.depth)];
+
+    let marker = if dir.collapsed { "▸ " } else { "▾ " };
+    spans.push(Span::styled(marker, Style::default().fg(theme.subtext)));
+
+    let name_style = if is_selected {
+        Style::default()
+            .fg(theme.selection_text)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(theme.info).add_modifier(Modifier::BOLD)
+    };
+    spans.push(Span::styled(sanitize_text(&dir.name), name_style));
+    spans.push(Span::styled(
+        format!(" ({})", dir.files),
+        Style::default().fg(theme.subtext),
+    ));
+
+    push_line_changes(&mut spans, dir.lines_added, dir.lines_deleted, theme);
+
+    let mut item = ListItem::new(Line::from(spans));
+
+    if is_selected {
+        item = item.style(Style::default().bg(theme.selection));
+    }
+
+    item
+}
+
+/// Append a label, styling the chars at the `matched` indices differently
+fn push_highlighted(
+    spans: &mut Vec<Span>,
+    label: &str,
+    matched: &[usize],
+    style: Style,
+    match_style: Style,
+) {
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (i, c) in label.chars().enumerate() {
+        let is_matched = matched.contains(&i);
+        if is_matched != run_matched && !run.is_empty() {
+            let style = if run_matched { match_style } else { style };
+            spans.push(Span::styled(sanitize_text(&run), style));
+            run.clear();
+        }
+        run_matched = is_matched;
+        run.push(c);
+    }
+    if !run.is_empty() {
+        let style = if run_matched { match_style } else { style };
+        spans.push(Span::styled(sanitize_text(&run), style));
+    }
+}
+
+/// Marker in front of the selected row
+fn selection_indicator<'a>(is_selected: bool, theme: &Theme) -> Span<'a> {
+    if is_selected {
+        Span::styled(" ▌", Style::default().fg(theme.accent))
+    } else {
+        Span::raw("  ")
+    }
+}
+
+/// Create a fading list item for a file that is no longer listed
+fn create_removed_item<'a>(file: &FileStatus, progress: f32, theme: &Theme) -> ListItem<'a> {
+    let line = Line::from(vec![
+        Span::raw("  "),
+        Span::styled("− ", Style::default().fg(theme.error)),
+        Span::styled(
+            sanitize_text(&file.path),
+            Style::default()
+                .fg(theme.subtext)
+                .add_modifier(Modifier::CROSSED_OUT),
+        ),
+    ]);
+    ListItem::new(line).style(Style::default().bg(fading_background(
+        theme.error,
+        theme.base,
+        progress,
+    )))
+}
+
+/// Staged/unstaged indicator, or a check mark for marked files
+fn file_indicator<'a>(section: Section, is_marked: bool, theme: &Theme) -> Span<'a> {
+    let (indicator, color) = match section {
+        _ if is_marked => ("✓", theme.accent),
+        Section::Staged => ("●", theme.staged),
+        Section::Unstaged => ("○", theme.unstaged),
+    };
+    Span::styled(indicator, Style::default().fg(color))
+}
+
+/// Indentation for a tree row
+fn indent<'a>(depth: usize) -> Span<'a> {
+    Span::raw("  ".repeat(depth))
+}
+
+/// Append `+added -deleted` (each only if non-zero)
+fn push_line_changes(spans: &mut Vec<Span>, added: usize, deleted: usize, theme: &Theme) {
+    if added > 0 || deleted > 0 {
         spans.push(Span::raw(" "));
-        if file.lines_added > 0 {
+        if added > 0 {
             spans.push(Span::styled(
-                format!("+{}", file.lines_added),
+                format!("+{}", added),
                 Style::default().fg(theme.success),
             ));
         }
-        if file.lines_deleted > 0 {
-            if file.lines_added > 0 {
+        if deleted > 0 {
+            if added > 0 {
                 spans.push(Span::raw(" "));
             }
             spans.push(Span::styled(
-                format!("-{}", file.lines_deleted),
+                format!("-{}", deleted),
                 Style::default().fg(theme.error),
             ));
         }
     }
-
-    let mut item = ListItem::new(Line::from(spans));
-
-    if is_selected {
-        item = item.style(Style::default().bg(theme.selection));
-    }
-
-    item
 }
 
 /// Get the color for a file status type
-fn get_status_color(status: FileStatusType, theme: &Theme) -> ratatui::style::Color {
+pub fn get_status_color(status: FileStatusType, theme: &Theme) -> ratatui::style::Color {
     match status {
         FileStatusType::Modified => theme.warning,
         FileStatusType::Added => theme.success,
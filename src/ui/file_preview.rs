@@ -1,95 +1,168 @@
+use std::ops::Range;
+use std::time::Instant;
+
 use ratatui::{
-    layout::Rect,
+    layout::{Margin, Rect},
     style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
     Frame,
 };
 
-use crate::app::App;
+use crate::app::{App, Focus};
+use crate::file_tree::FileRow;
+use crate::search::DiffSearch;
 use crate::theme::Theme;
 
+use super::utils::fading_background;
+
 /// Render the file preview section (diff view)
-pub fn render_file_preview(frame: &mut Frame, area: Rect, app: &App) {
+pub fn render_file_preview(frame: &mut Frame, area: Rect, app: &mut App) {
+    app.preview_area = area;
     let theme = &app.theme;
 
-    let title = if let Some(path) = app.get_selected_file_path() {
+    let selected_dir = match app.selected_row() {
+        Some(FileRow::Dir(dir)) => Some(dir),
+        _ => None,
+    };
+
+    let mut title = if let Some(path) = app.get_selected_file_path() {
         format!(" Preview: {} ", path)
+    } else if let Some(dir) = &selected_dir {
+        format!(" Preview: {} ", dir.path)
     } else {
         " Preview ".to_string()
     };
 
-    // Use TOP | RIGHT | BOTTOM (no LEFT border) since file_list's right border is adjacent
-    let block = Block::default()
-        .borders(Borders::TOP | Borders::RIGHT | Borders::BOTTOM)
-        .border_style(Style::default().fg(theme.border))
+    // Match counter
+    let search = &app.diff_search;
+    if search.is_active() && search.error.is_none() && app.selected_diff().is_some() {
+        match search.current {
+            Some(current) => {
+                title.push_str(&format!("[{}/{}] ", current + 1, search.matches.len()))
+            }
+            None => title.push_str("[no matches] "),
+        }
+    }
+
+    let border_color = if app.focus == Focus::Preview {
+        theme.accent
+    } else {
+        theme.border
+    };
+
+    let mut block = Block::default()
+        .borders(app.pane_layout.preview_borders())
+        .border_style(Style::default().fg(border_color))
         .title(Span::styled(
             title,
             Style::default()
                 .fg(theme.accent)
                 .add_modifier(Modifier::BOLD),
         ));
+    if app.search_input || search.is_active() {
+        block = block.title_bottom(search_line(app, theme));
+    }
 
-    if let Some(diff) = &app.diff_content {
-        let lines = parse_diff_content(diff, theme);
+    if let Some(diff) = app.selected_diff() {
+        let changed = app.changed_hunks(Instant::now());
+        let lines = parse_diff_content(diff, search, changed, theme);
+        let line_count = lines.len();
 
         let paragraph = Paragraph::new(lines)
             .block(block)
             .scroll((app.preview_scroll, 0));
 
         frame.render_widget(paragraph, area);
+
+        // Scrollbar on the right border when the diff does not fit
+        let viewport = area.height.saturating_sub(2) as usize;
+        if line_count > viewport {
+            // Positions run from 0 to the last scroll offset
+            let mut state = ScrollbarState::new(line_count - viewport + 1)
+                .position(app.preview_scroll as usize)
+                .viewport_content_length(viewport);
+            let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None)
+                .track_style(Style::default().fg(theme.border))
+                .thumb_style(Style::default().fg(border_color));
+            frame.render_stateful_widget(
+                scrollbar,
+                area.inner(Margin {
+                    horizontal: 0,
+                    vertical: 1,
+                }),
+                &mut state,
+            );
+        }
     } else {
-        let paragraph = Paragraph::new(" Select a file to view diff")
+        let message = if app.get_selected_file().is_some() {
+            " Loading diff...".to_string()
+        } else if let Some(dir) = &selected_dir {
+            let noun = if dir.files == 1 { "file" } else { "files" };
+            format!(
+                " {} changed {} (+{} -{})",
+                dir.files, noun, dir.lines_added, dir.lines_deleted
+            )
+        } else {
+            " Select a file to view diff".to_string()
+        };
+        let paragraph = Paragraph::new(message)
             .style(Style::default().fg(theme.subtext))
             .block(block);
         frame.render_widget(paragraph, area);
     }
 }
 
-/// Parse diff content into styled lines
-fn parse_diff_content<'a>(diff: &str, theme: &Theme) -> Vec<Line<'a>> {
+/// Parse diff content into styled lines, highlighting search matches and
+/// hunks that just changed (line ranges, with how far their highlight faded)
+fn parse_diff_content<'a>(
+    diff: &str,
+    search: &DiffSearch,
+    changed: Option<(&[Range<usize>], f32)>,
+    theme: &Theme,
+) -> Vec<Line<'a>> {
     let mut lines = Vec::new();
+    let current = search.current_match();
+    let mut matches = search.matches.iter().peekable();
 
-    for line in diff.lines() {
-        let styled_line = if line.starts_with("+++") || line.starts_with("---") {
-            // File headers
-            Line::from(Span::styled(
-                line.to_string(),
-                Style::default().fg(theme.info).add_modifier(Modifier::BOLD),
-            ))
-        } else if line.starts_with("@@") {
-            // Hunk headers
-            Line::from(Span::styled(
-                line.to_string(),
-                Style::default().fg(theme.accent),
-            ))
-        } else if line.starts_with('+') {
-            // Added lines
-            Line::from(Span::styled(
-                line.to_string(),
-                Style::default().fg(theme.success),
-            ))
-        } else if line.starts_with('-') {
-            // Removed lines
-            Line::from(Span::styled(
-                line.to_string(),
-                Style::default().fg(theme.error),
-            ))
-        } else if line.starts_with("diff ") || line.starts_with("index ") {
-            // Diff metadata
-            Line::from(Span::styled(
-                line.to_string(),
-                Style::default().fg(theme.subtext),
-            ))
-        } else {
-            // Context lines
-            Line::from(Span::styled(
-                line.to_string(),
-                Style::default().fg(theme.text),
-            ))
-        };
+    for (index, line) in diff.lines().enumerate() {
+        let style = diff_line_style(line, theme);
 
-        lines.push(styled_line);
+        // Split the line around its matches
+        let mut spans = Vec::new();
+        let mut position = 0;
+        while let Some(found) = matches.next_if(|m| m.line == index) {
+            if found.start > position {
+                spans.push(Span::styled(line[position..found.start].to_string(), style));
+            }
+            let highlight = if current == Some(*found) {
+                theme.accent
+            } else {
+                theme.warning
+            };
+            spans.push(Span::styled(
+                line[found.start..found.end].to_string(),
+                Style::default().fg(theme.base).bg(highlight),
+            ));
+            position = found.end;
+        }
+        if position < line.len() || spans.is_empty() {
+            spans.push(Span::styled(line[position..].to_string(), style));
+        }
+
+        let mut line = Line::from(spans);
+        if let Some((ranges, progress)) = changed {
+            if ranges.iter().any(|range| range.contains(&index)) {
+                line = line.style(Style::default().bg(fading_background(
+                    theme.warning,
+                    theme.base,
+                    progress,
+                )));
+            }
+        }
+        lines.push(line);
     }
 
     if lines.is_empty() {
@@ -101,3 +174,62 @@ fn parse_diff_content<'a>(diff: &str, theme: &Theme) -> Vec<Line<'a>> {
 
     lines
 }
+
+/// Style of a diff line by its kind
+pub fn diff_line_style(line: &str, theme: &Theme) -> Style {
+    if line.starts_with("+++") || line.starts_with("---") {
+        // File headers
+        Style::default().fg(theme.info).add_modifier(Modifier::BOLD)
+    } else if line.starts_with("@@") {
+        // Hunk headers
+        Style::default().fg(theme.accent)
+    } else if line.starts_with('+') {
+        // Added lines
+        Style::default().fg(theme.success)
+    } else if line.starts_with('-') {
+        // Removed lines
+        Style::default().fg(theme.error)
+    } else if line.starts_with("diff ") || line.starts_with("index ") {
+        // Diff metadata
+        Style::default().fg(theme.subtext)
+    } else {
+        // Context lines
+        Style::default().fg(theme.text)
+    }
+}
+
+/// The search prompt with its mode flags and any error, shown below the preview
+fn search_line<'a>(app: &App, theme: &Theme) -> Line<'a> {
+    let search = &app.diff_search;
+    let flag = |on: bool| {
+        if on {
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.subtext)
+        }
+    };
+
+    let mut spans = vec![
+        Span::styled(" /", Style::default().fg(theme.accent)),
+        Span::styled(search.query.clone(), Style::default().fg(theme.text)),
+    ];
+    if app.search_input {
+        spans.push(Span::styled("▏", Style::default().fg(theme.accent)));
+    }
+    spans.push(Span::raw(" "));
+    spans.push(Span::styled(".*", flag(search.regex)));
+    spans.push(Span::raw(" "));
+    spans.push(Span::styled("Aa", flag(search.case_sensitive)));
+    if let Some(error) = &search.error {
+        // Regex errors span several lines; the first one says what is wrong
+        let first = error.lines().last().unwrap_or_default().trim().to_string();
+        spans.push(Span::styled(
+            format!(" {}", first),
+            Style::default().fg(theme.error),
+        ));
+    }
+    spans.push(Span::raw(" "));
+    Line::from(spans)
+}
@@ -14,14 +14,7 @@ pub fn render_help_menu(frame: &mut Frame, app: &App) {
     let area = frame.area();
 
     // Calculate centered popup area
-    let popup_width = 50.min(area.width.saturating_sub(4));
-    let popup_height = 17.min(area.height.saturating_sub(4));
-
-    let popup_area = centered_rect(popup_width, popup_height, area);
-
-    // Clear the area behind the popup
-    frame.render_widget(Clear, popup_area);
-
+    let popup_width = 60.min(area.width.saturating_sub(4));
     // Create the help content
     let keybindings = vec![
         ("q / Esc", "Quit application"),
@@ -33,10 +26,40 @@ pub fn render_help_menu(frame: &mut Frame, app: &App) {
         ("PageUp", "Page up"),
         ("PageDown", "Page down"),
         ("Tab", "Switch between staged/unstaged"),
-        ("s", "Stage/Unstage selected file"),
+        ("s", "Stage/Unstage selected or marked files"),
+        ("Space / v / a", "Mark file / range / whole section"),
+        ("S / U", "Stage all / unstage all (or marked)"),
+        ("D", "Discard changes (asks to confirm)"),
+        ("P", "Push (sets upstream if missing)"),
+        ("f", "Pull (ff-only or rebase)"),
+        ("`", "Toggle git command console"),
+        ("T", "Toggle session activity timeline"),
+        ("B", "Browse working-tree snapshots"),
+        ("n", "Toggle notification history"),
+        ("u", "Cycle untracked-file mode"),
+        ("Enter", "Expand/collapse directory"),
+        ("t", "Toggle tree/flat file list"),
+        ("o", "Cycle sort: path/status/lines/ext/mtime"),
+        ("/", "Filter files (Enter keeps, Esc clears)"),
+        ("F / E", "Filter by status / extension"),
+        ("→ / l", "Focus preview (← / h / Esc: back)"),
+        ("j/k ^u/^d g/G", "Scroll preview (focused)"),
+        ("[ / ]", "Previous/next hunk (preview)"),
+        ("/ (preview)", "Search diff (Alt+r regex, Alt+c case)"),
+        ("n / N", "Next/previous match (preview)"),
+        ("Z", "Zoom the focused pane"),
+        ("w", "Follow the latest changed file"),
+        ("z", "Pause/resume live updates"),
         ("Ctrl+C", "Force quit"),
     ];
 
+    let popup_height = (keybindings.len() as u16 + 6).min(area.height.saturating_sub(4));
+
+    let popup_area = centered_rect(popup_width, popup_height, area);
+
+    // Clear the area behind the popup
+    frame.render_widget(Clear, popup_area);
+
     let rows: Vec<Row> = keybindings
         .iter()
         .map(|(key, desc)| {
@@ -0,0 +1,163 @@
+use ratatui::layout::{Constraint, Direction, Layout, Position, Rect};
+use ratatui::widgets::Borders;
+
+/// Where the file list and the preview were placed within the content area
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PaneLayout {
+    /// Area shared by both panes
+    pub area: Rect,
+    /// File list (empty when hidden)
+    pub list: Rect,
+    /// Preview (empty when hidden)
+    pub preview: Rect,
+    /// Panes are stacked instead of side by side
+    pub vertical: bool,
+    /// The preview comes before (left of or above) the file list
+    pub preview_first: bool,
+}
+
+impl PaneLayout {
+    /// Place the visible panes, giving the list `list_percent` of the area when both are shown
+    pub fn new(
+        area: Rect,
+        show_list: bool,
+        show_preview: bool,
+        list_percent: u16,
+        vertical: bool,
+        preview_first: bool,
+    ) -> Self {
+        let mut layout = Self {
+            area,
+            vertical,
+            preview_first,
+            ..Self::default()
+        };
+        match (show_list, show_preview) {
+            (true, true) => {
+                let total = if vertical { area.height } else { area.width };
+                let list_size = (total as u32 * list_percent as u32 / 100) as u16;
+                let (first, second) = if preview_first {
+                    (total.saturating_sub(list_size), list_size)
+                } else {
+                    (list_size, total.saturating_sub(list_size))
+                };
+                let direction = if vertical {
+                    Direction::Vertical
+                } else {
+                    Direction::Horizontal
+                };
+                let panes = Layout::default()
+                    .direction(direction)
+                    .constraints([Constraint::Length(first), Constraint::Length(second)])
+                    .split(area);
+                if preview_first {
+                    (layout.preview, layout.list) = (panes[0], panes[1]);
+                } else {
+                    (layout.list, layout.preview) = (panes[0], panes[1]);
+                }
+            }
+            (true, false) => layout.list = area,
+            (false, true) => layout.preview = area,
+            (false, false) => {}
+        }
+        layout
+    }
+
+    /// Whether both panes are shown
+    fn is_split(&self) -> bool {
+        !self.list.is_empty() && !self.preview.is_empty()
+    }
+
+    /// The pane that comes first (left or top)
+    fn first(&self) -> Rect {
+        if self.preview_first {
+            self.preview
+        } else {
+            self.list
+        }
+    }
+
+    /// Borders of a pane: side by side, the second pane leaves out its left
+    /// border since the first pane's right border is adjacent (stacked panes
+    /// keep their top borders for the titles)
+    fn borders(&self, pane: Rect) -> Borders {
+        if self.is_split() && !self.vertical && pane != self.first() {
+            Borders::TOP | Borders::RIGHT | Borders::BOTTOM
+        } else {
+            Borders::ALL
+        }
+    }
+
+    /// Borders of the file list
+    pub fn list_borders(&self) -> Borders {
+        self.borders(self.list)
+    }
+
+    /// Borders of the preview
+    pub fn preview_borders(&self) -> Borders {
+        self.borders(self.preview)
+    }
+
+    /// Whether a screen position is on the divider (the first pane's right or bottom border)
+    pub fn is_on_divider(&self, position: Position) -> bool {
+        let first = self.first();
+        if !self.is_split() || !first.contains(position) {
+            return false;
+        }
+        if self.vertical {
+            position.y + 1 == first.bottom()
+        } else {
+            position.x + 1 == first.right()
+        }
+    }
+
+    /// The list's share of the area (in percent) with the divider moved to a screen position
+    pub fn list_percent_at(&self, position: Position) -> u16 {
+        let (offset, total) = if self.vertical {
+            (position.y.saturating_sub(self.area.y), self.area.height)
+        } else {
+            (position.x.saturating_sub(self.area.x), self.area.width)
+        };
+        if total == 0 {
+            return 0;
+        }
+        // The divider is the first pane's last row or column
+        let first_size = (offset + 1).min(total);
+        let first_percent = (first_size as u32 * 100 / total as u32) as u16;
+        if self.preview_first {
+            100 - first_percent
+        } else {
+            first_percent
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pane_layout_orders_panes_and_finds_the_divider() {
+        let area = Rect::new(0, 4, 100, 20);
+
+        let layout = PaneLayout::new(area, true, true, 30, false, true);
+        assert_eq!(layout.preview, Rect::new(0, 4, 70, 20));
+        assert_eq!(layout.list, Rect::new(70, 4, 30, 20));
+        assert_eq!(layout.preview_borders(), Borders::ALL);
+        assert!(layout.is_on_divider(Position::new(69, 10)));
+        assert_eq!(layout.list_percent_at(Position::new(59, 10)), 40);
+
+        let layout = PaneLayout::new(area, true, true, 25, true, false);
+        assert_eq!(layout.list, Rect::new(0, 4, 100, 5));
+        assert_eq!(layout.preview, Rect::new(0, 9, 100, 15));
+        assert_eq!(layout.preview_borders(), Borders::ALL);
+        assert!(layout.is_on_divider(Position::new(50, 8)));
+        assert!(!layout.is_on_divider(Position::new(50, 9)));
+        assert_eq!(layout.list_percent_at(Position::new(50, 13)), 50);
+
+        // A zoomed pane takes the whole area
+        let layout = PaneLayout::new(area, false, true, 25, true, false);
+        assert_eq!((layout.list, layout.preview), (Rect::default(), area));
+        assert!(!layout.is_on_divider(Position::new(50, 8)));
+    }
+}
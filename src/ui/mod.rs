@@ -1,9 +1,16 @@
 mod branch_info;
+mod command_console;
 mod file_list;
 mod file_preview;
 mod help_menu;
+mod layout;
+mod notifications;
+mod remote_panel;
 mod render;
+mod snapshots;
 mod status_bar;
+mod timeline;
 mod utils;
 
+pub use layout::PaneLayout;
 pub use render::render;
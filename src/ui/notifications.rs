@@ -0,0 +1,133 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::app::App;
+use crate::theme::Theme;
+use crate::toast::Severity;
+
+use super::utils::sanitize_text;
+
+/// Render active toasts stacked in the top-right corner of the given area
+pub fn render_toasts(frame: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let width = 50.min(area.width);
+    let mut y = area.y;
+
+    // Newest toast on top
+    for toast in app.notifications.active().iter().rev() {
+        let color = severity_color(toast.severity, theme);
+        let message = sanitize_text(&toast.message);
+
+        // Borders plus wrapped message lines
+        let inner_width = width.saturating_sub(2).max(1) as usize;
+        let lines = message.chars().count().div_ceil(inner_width).max(1) as u16;
+        let height = (lines + 2).min(5);
+
+        if y + height > area.y + area.height {
+            break;
+        }
+
+        let toast_area = Rect::new(area.x + area.width - width, y, width, height);
+        frame.render_widget(Clear, toast_area);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(color))
+            .title(Span::styled(
+                format!(" {} ", toast.severity.label()),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ));
+
+        let paragraph = Paragraph::new(message)
+            .style(Style::default().fg(theme.text).bg(theme.overlay))
+            .wrap(Wrap { trim: true })
+            .block(block);
+        frame.render_widget(paragraph, toast_area);
+
+        y += height;
+    }
+}
+
+/// Render the notification history overlay
+pub fn render_notification_history(frame: &mut Frame, app: &App) {
+    let theme = &app.theme;
+    let area = frame.area();
+
+    let popup_area = Rect::new(
+        area.x + 2,
+        area.y + 1,
+        area.width.saturating_sub(4),
+        area.height.saturating_sub(2),
+    );
+
+    frame.render_widget(Clear, popup_area);
+
+    let mut lines = Vec::new();
+    for toast in app.notifications.history().rev() {
+        let color = severity_color(toast.severity, theme);
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!(" {:>5} ", toast.severity.label()),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("{:>5}s ago  ", toast.created_at.elapsed().as_secs()),
+                Style::default().fg(theme.subtext),
+            ),
+            Span::styled(
+                sanitize_text(&toast.message),
+                Style::default().fg(theme.text),
+            ),
+        ]));
+
+        if let Some(detail) = &toast.detail {
+            for line in detail.lines() {
+                lines.push(Line::from(Span::styled(
+                    format!("        {}", sanitize_text(line)),
+                    Style::default().fg(theme.subtext),
+                )));
+            }
+        }
+    }
+
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            " No notifications yet",
+            Style::default().fg(theme.subtext),
+        )));
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent))
+        .title(Span::styled(
+            " Notifications ",
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD),
+        ))
+        .title_bottom(Line::from(Span::styled(
+            " Press n or Esc to close ",
+            Style::default().fg(theme.subtext),
+        )));
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .style(Style::default().bg(theme.overlay));
+    frame.render_widget(paragraph, popup_area);
+}
+
+/// Get the color for a notification severity
+fn severity_color(severity: Severity, theme: &Theme) -> Color {
+    match severity {
+        Severity::Info => theme.info,
+        Severity::Success => theme.success,
+        Severity::Warning => theme.warning,
+        Severity::Error => theme.error,
+    }
+}
@@ -0,0 +1,92 @@
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::app::App;
+
+use super::utils::sanitize_text;
+
+/// Render the push/pull progress panel at the bottom of the given area
+pub fn render_remote_panel(frame: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+
+    let Some(panel) = &app.remote_panel else {
+        return;
+    };
+
+    let height = 10.min(area.height);
+    let panel_area = Rect::new(
+        area.x,
+        area.y + area.height.saturating_sub(height),
+        area.width,
+        height,
+    );
+
+    // Clear the area behind the panel
+    frame.render_widget(Clear, panel_area);
+
+    let (status, status_color) = match &panel.outcome {
+        None => (format!("{} running...", app.spinner()), theme.warning),
+        Some(Ok(_)) => ("done".to_string(), theme.success),
+        Some(Err(_)) => ("failed".to_string(), theme.error),
+    };
+
+    let visible = panel_area.height.saturating_sub(2) as usize;
+    let mut lines: Vec<Line> = Vec::new();
+
+    match &panel.outcome {
+        Some(Err(message)) => {
+            // Show the readable error first, followed by the tail of git's output
+            lines.push(Line::from(Span::styled(
+                format!(" {}", sanitize_text(message)),
+                Style::default()
+                    .fg(theme.error)
+                    .add_modifier(Modifier::BOLD),
+            )));
+        }
+        Some(Ok(summary)) => {
+            lines.push(Line::from(Span::styled(
+                format!(" {}", sanitize_text(summary)),
+                Style::default()
+                    .fg(theme.success)
+                    .add_modifier(Modifier::BOLD),
+            )));
+        }
+        None => {}
+    }
+
+    let remaining = visible.saturating_sub(lines.len());
+    let skip = panel.lines.len().saturating_sub(remaining);
+    for line in panel.lines.iter().skip(skip) {
+        lines.push(Line::from(Span::styled(
+            format!(" {}", sanitize_text(&line.text)),
+            Style::default().fg(theme.subtext),
+        )));
+    }
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(status_color))
+        .title(Span::styled(
+            format!(" {} - {} ", panel.operation.label(), status),
+            Style::default()
+                .fg(status_color)
+                .add_modifier(Modifier::BOLD),
+        ));
+
+    if !panel.is_running() {
+        block = block.title_bottom(Line::from(Span::styled(
+            " Press Esc to close ",
+            Style::default().fg(theme.subtext),
+        )));
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .style(Style::default().bg(theme.overlay));
+    frame.render_widget(paragraph, panel_area);
+}
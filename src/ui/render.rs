@@ -1,56 +1,102 @@
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     Frame,
 };
 
-use crate::app::App;
+use crate::app::{App, Focus};
+use crate::config::types::PanelOrder;
 
 use super::branch_info::render_branch_info;
+use super::command_console::render_command_console;
 use super::file_list::render_file_list;
 use super::file_preview::render_file_preview;
 use super::help_menu::render_help_menu;
+use super::layout::PaneLayout;
+use super::notifications::{render_notification_history, render_toasts};
+use super::remote_panel::render_remote_panel;
+use super::snapshots::render_snapshots;
 use super::status_bar::render_status_bar;
+use super::timeline::render_timeline;
+
+/// Below this terminal height the branch info header is hidden
+const MIN_HEIGHT_FOR_HEADER: u16 = 20;
 
 /// Main render function that composes all UI components
 pub fn render(frame: &mut Frame, app: &mut App) {
     let area = frame.area();
 
-    // Main layout: header, content, footer
+    // Main layout: header, content, footer (the header gives way on short terminals)
+    let show_header = app.config.display.show_branch_info && area.height >= MIN_HEIGHT_FOR_HEADER;
     let main_layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(4), // Branch info (2 lines + borders)
-            Constraint::Min(10),   // Main content
-            Constraint::Length(2), // Status bar
+            Constraint::Length(if show_header { 4 } else { 0 }), // Branch info (2 lines + borders)
+            Constraint::Min(0),                                  // Main content
+            Constraint::Length(2),                               // Status bar
         ])
         .split(area);
 
-    // Render branch info if enabled
-    if app.config.display.show_branch_info {
+    if show_header {
         render_branch_info(frame, main_layout[0], app);
     }
 
-    // Content layout: file list and preview (side by side if preview is shown)
-    if app.show_preview && app.config.display.show_file_preview {
-        // Calculate half width, giving any odd pixel to the left panel
-        let half = main_layout[1].width / 2;
-        let content_layout = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Length(half),
-                Constraint::Min(0), // Takes remaining space
-            ])
-            .split(main_layout[1]);
-
-        render_file_list(frame, content_layout[0], app);
-        render_file_preview(frame, content_layout[1], app);
+    // Content layout: file list and preview, side by side or stacked
+    let preview_enabled = app.show_preview && app.config.display.show_file_preview;
+    let zoomed_preview = app.zoomed && preview_enabled && app.focus == Focus::Preview;
+    let show_list = !zoomed_preview;
+    let show_preview = preview_enabled && (!app.zoomed || zoomed_preview);
+    app.pane_layout = PaneLayout::new(
+        main_layout[1],
+        show_list,
+        show_preview,
+        app.split_percent,
+        app.config.ui.stacks_vertically(area.width),
+        app.config.ui.panel_order == PanelOrder::PreviewFirst,
+    );
+
+    let PaneLayout { list, preview, .. } = app.pane_layout;
+    if show_list {
+        render_file_list(frame, list, app);
+    } else {
+        app.file_list_area = Rect::default();
+    }
+    if show_preview {
+        render_file_preview(frame, preview, app);
     } else {
-        render_file_list(frame, main_layout[1], app);
+        app.preview_area = Rect::default();
+    }
+
+    // Render push/pull progress over the bottom of the content area
+    if app.remote_panel.is_some() {
+        render_remote_panel(frame, main_layout[1], app);
     }
 
     // Render status bar
     render_status_bar(frame, main_layout[2], app);
 
+    // Render the git command console overlay if visible
+    if app.show_console {
+        render_command_console(frame, app);
+    }
+
+    // Render the session timeline overlay if visible
+    if app.show_timeline {
+        render_timeline(frame, app);
+    }
+
+    // Render the snapshot browser overlay if open
+    if app.snapshot_browser.is_some() {
+        render_snapshots(frame, app);
+    }
+
+    // Render the notification history overlay if visible
+    if app.show_notification_history {
+        render_notification_history(frame, app);
+    }
+
+    // Render toasts over the content area
+    render_toasts(frame, main_layout[1], app);
+
     // Render help menu overlay if visible
     if app.show_help {
         render_help_menu(frame, app);
@@ -0,0 +1,236 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use crate::app::{App, SnapshotAction, SnapshotBrowser};
+use crate::config::types::KeybindingsConfig;
+use crate::theme::Theme;
+
+use super::file_list::get_status_color;
+use super::file_preview::diff_line_style;
+use super::utils::{format_age, sanitize_text};
+
+/// Render the snapshot browser overlay
+pub fn render_snapshots(frame: &mut Frame, app: &App) {
+    let Some(browser) = &app.snapshot_browser else {
+        return;
+    };
+    let theme = &app.theme;
+    let area = frame.area();
+
+    // Take most of the screen, leaving a small margin
+    let popup_area = Rect::new(
+        area.x + 2,
+        area.y + 1,
+        area.width.saturating_sub(4),
+        area.height.saturating_sub(2),
+    );
+
+    frame.render_widget(Clear, popup_area);
+
+    let count = browser.snapshots.as_ref().map_or(0, Vec::len);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent))
+        .title(Span::styled(
+            format!(" Snapshots ({}) ", count),
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD),
+        ))
+        .title_bottom(hints(browser, &app.config.keybindings, theme))
+        .style(Style::default().bg(theme.overlay));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let message = match &browser.snapshots {
+        None => Some(" Loading snapshots..."),
+        Some(snapshots) if snapshots.is_empty() && app.config.snapshots.enabled => {
+            Some(" No snapshots yet; one is taken once changed files settle")
+        }
+        Some(snapshots) if snapshots.is_empty() => {
+            Some(" No snapshots; set snapshots.enabled in the config to record them")
+        }
+        Some(_) => None,
+    };
+    if let Some(message) = message {
+        let paragraph = Paragraph::new(message).style(Style::default().fg(theme.subtext));
+        frame.render_widget(paragraph, inner);
+        return;
+    }
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(40), Constraint::Min(4)])
+        .split(inner);
+    let lists = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(layout[0]);
+
+    render_snapshot_list(frame, lists[0], browser, theme);
+    render_changes(frame, lists[1], browser, theme);
+    render_diff(frame, layout[1], browser, theme);
+}
+
+/// Key hints, or the pending confirmation prompt
+fn hints<'a>(browser: &SnapshotBrowser, keys: &KeybindingsConfig, theme: &Theme) -> Line<'a> {
+    let prompt = match browser.confirm {
+        Some(SnapshotAction::Restore) => browser.file().map(|file| {
+            format!(
+                " Restore {} from this snapshot? {} to confirm, any other key cancels ",
+                sanitize_text(&file.path),
+                keys.confirm
+            )
+        }),
+        Some(SnapshotAction::Prune) => {
+            let count = browser.snapshots.as_ref().map_or(0, Vec::len);
+            let older = count.saturating_sub(browser.selected + 1);
+            let noun = if older == 1 { "snapshot" } else { "snapshots" };
+            Some(format!(
+                " Delete {} {} older than this one? {} to confirm, any other key cancels ",
+                older, noun, keys.confirm
+            ))
+        }
+        None => None,
+    };
+    if let Some(prompt) = prompt {
+        return Line::from(Span::styled(
+            prompt,
+            Style::default()
+                .fg(theme.error)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    let text = if browser.file_selected.is_some() {
+        format!(
+            " ↑/↓ browse files  {} restore file  Esc back ",
+            keys.restore_snapshot
+        )
+    } else {
+        format!(
+            " ↑/↓ browse  Enter files  {} prune older  {} or Esc to close ",
+            keys.prune_snapshots, keys.snapshots
+        )
+    };
+    Line::from(Span::styled(text, Style::default().fg(theme.subtext)))
+}
+
+/// Render the snapshots, newest first
+fn render_snapshot_list(frame: &mut Frame, area: Rect, browser: &SnapshotBrowser, theme: &Theme) {
+    let snapshots = browser.snapshots.as_deref().unwrap_or_default();
+    let rows: Vec<Row> = snapshots
+        .iter()
+        .map(|snapshot| {
+            let age = snapshot.time.elapsed().unwrap_or_default().as_secs();
+            Row::new(vec![
+                Span::styled(format_age(age), Style::default().fg(theme.text)),
+                Span::styled(
+                    snapshot.commit.chars().take(7).collect::<String>(),
+                    Style::default().fg(theme.subtext),
+                ),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(rows, [Constraint::Min(10), Constraint::Length(7)])
+        .block(
+            Block::default()
+                .borders(Borders::RIGHT)
+                .border_style(Style::default().fg(theme.border)),
+        )
+        .row_highlight_style(list_highlight(browser.file_selected.is_none(), theme));
+
+    let mut state = TableState::default();
+    state.select(Some(browser.selected));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+/// Render the files changed since the selected snapshot
+fn render_changes(frame: &mut Frame, area: Rect, browser: &SnapshotBrowser, theme: &Theme) {
+    let Some(changes) = &browser.changes else {
+        let paragraph = Paragraph::new(" Comparing with the working tree...")
+            .style(Style::default().fg(theme.subtext));
+        frame.render_widget(paragraph, area);
+        return;
+    };
+    if changes.files.is_empty() {
+        let paragraph = Paragraph::new(" The working tree matches this snapshot")
+            .style(Style::default().fg(theme.subtext));
+        frame.render_widget(paragraph, area);
+        return;
+    }
+
+    let rows: Vec<Row> = changes
+        .files
+        .iter()
+        .map(|file| {
+            Row::new(vec![
+                Span::styled(
+                    file.status.code(),
+                    Style::default().fg(get_status_color(file.status, theme)),
+                ),
+                Span::styled(sanitize_text(&file.path), Style::default().fg(theme.text)),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(rows, [Constraint::Length(2), Constraint::Min(10)])
+        .row_highlight_style(list_highlight(browser.file_selected.is_some(), theme));
+
+    let mut state = TableState::default();
+    state.select(browser.file_selected);
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+/// Render the diff of the selected file since the snapshot
+fn render_diff(frame: &mut Frame, area: Rect, browser: &SnapshotBrowser, theme: &Theme) {
+    let title = match browser.file() {
+        Some(file) => format!(" {} since the snapshot ", sanitize_text(&file.path)),
+        None => " Diff ".to_string(),
+    };
+    let block = Block::default()
+        .borders(Borders::TOP)
+        .border_style(Style::default().fg(theme.border))
+        .title(Span::styled(
+            title,
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+        ));
+
+    let lines: Vec<Line> = match (&browser.diff, browser.file_selected) {
+        (Some(diff), Some(_)) => diff
+            .lines()
+            .map(|line| {
+                let style = diff_line_style(line, theme);
+                Line::from(Span::styled(line.to_string(), style))
+            })
+            .collect(),
+        (None, Some(_)) => vec![Line::from(Span::styled(
+            " Loading diff...",
+            Style::default().fg(theme.subtext),
+        ))],
+        (_, None) => vec![Line::from(Span::styled(
+            " Enter to browse the changed files and their diffs",
+            Style::default().fg(theme.subtext),
+        ))],
+    };
+
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// Highlight of the selected row, dimmed in the list that is not being browsed
+fn list_highlight(active: bool, theme: &Theme) -> Style {
+    if active {
+        Style::default().bg(theme.selection)
+    } else {
+        Style::default()
+            .fg(theme.accent)
+            .add_modifier(Modifier::BOLD)
+    }
+}
@@ -7,6 +7,7 @@ use ratatui::{
 };
 
 use crate::app::App;
+use crate::watcher::service::WatcherStatus;
 
 /// Render the status bar at the bottom
 pub fn render_status_bar(frame: &mut Frame, area: Rect, app: &App) {
@@ -44,11 +45,82 @@ pub fn render_status_bar(frame: &mut Frame, area: Rect, app: &App) {
         ));
     }
 
-    // Watch mode indicator
+    // Watch mode indicator with the watcher's health
     if app.watch_mode {
+        let (label, color) = match &app.watcher_status {
+            None | Some(WatcherStatus::Native) => ("Watching".to_string(), theme.success),
+            Some(WatcherStatus::Polling { .. }) => (
+                format!(
+                    "Watching (polling {}s)",
+                    app.config.watcher.poll_interval_ms as f32 / 1000.0
+                ),
+                theme.warning,
+            ),
+            Some(WatcherStatus::Degraded(_)) => ("Watching (degraded)".to_string(), theme.warning),
+            Some(WatcherStatus::Failed(_)) => ("Not watching".to_string(), theme.error),
+        };
         spans.push(Span::styled("  │  ", Style::default().fg(theme.border)));
-        spans.push(Span::styled("● ", Style::default().fg(theme.success)));
-        spans.push(Span::styled("Watching", Style::default().fg(theme.success)));
+        spans.push(Span::styled("● ", Style::default().fg(color)));
+        spans.push(Span::styled(label, Style::default().fg(color)));
+    }
+
+    // Follow mode badge
+    if app.follow {
+        spans.push(Span::styled("  │  ", Style::default().fg(theme.border)));
+        spans.push(Span::styled(
+            "◎ Following",
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    // Discard confirmation prompt
+    if let Some(files) = &app.pending_discard {
+        let noun = if files.len() == 1 { "file" } else { "files" };
+        spans.push(Span::styled("  │  ", Style::default().fg(theme.border)));
+        spans.push(Span::styled(
+            format!(
+                "Discard changes to {} {}? {} to confirm, any other key cancels",
+                files.len(),
+                noun,
+                app.config.keybindings.confirm
+            ),
+            Style::default()
+                .fg(theme.error)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    // Paused badge with the number of queued changes
+    if app.paused {
+        spans.push(Span::styled("  │  ", Style::default().fg(theme.border)));
+        spans.push(Span::styled(
+            "⏸ Paused",
+            Style::default()
+                .fg(theme.warning)
+                .add_modifier(Modifier::BOLD),
+        ));
+        let pending = app.pending_change_count();
+        if pending > 0 {
+            let noun = if pending == 1 { "change" } else { "changes" };
+            spans.push(Span::styled(
+                format!(" · {} {} pending", pending, noun),
+                Style::default().fg(theme.warning),
+            ));
+        }
+    }
+
+    // Git activity indicator (only while commands are running or queued)
+    let pool = app.git_service.pool();
+    let (running, queued) = (pool.running(), pool.queued());
+    if running > 0 || queued > 0 {
+        spans.push(Span::styled("  │  ", Style::default().fg(theme.border)));
+        let mut text = format!("{} git: {} running", app.spinner(), running);
+        if queued > 0 {
+            text.push_str(&format!(", {} queued", queued));
+        }
+        spans.push(Span::styled(text, Style::default().fg(theme.warning)));
     }
 
     let block = Block::default()
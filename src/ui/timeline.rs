@@ -0,0 +1,213 @@
+use std::time::Instant;
+
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use crate::app::App;
+use crate::theme::Theme;
+use crate::timeline::{TimelineEntry, TimelineFile};
+
+use super::file_list::get_status_color;
+use super::file_preview::diff_line_style;
+use super::utils::{format_age, sanitize_text};
+
+/// Render the session timeline overlay
+pub fn render_timeline(frame: &mut Frame, app: &App) {
+    let theme = &app.theme;
+    let area = frame.area();
+
+    // Take most of the screen, leaving a small margin
+    let popup_area = Rect::new(
+        area.x + 2,
+        area.y + 1,
+        area.width.saturating_sub(4),
+        area.height.saturating_sub(2),
+    );
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent))
+        .title(Span::styled(
+            format!(
+                " Timeline ({} {}) ",
+                app.timeline.len(),
+                plural(app.timeline.len(), "refresh", "refreshes")
+            ),
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD),
+        ))
+        .title_bottom(Line::from(Span::styled(
+            " ↑/↓ browse  T or Esc to close ",
+            Style::default().fg(theme.subtext),
+        )))
+        .style(Style::default().bg(theme.overlay));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    if app.timeline.is_empty() {
+        let paragraph = Paragraph::new(" No changes seen yet this session")
+            .style(Style::default().fg(theme.subtext));
+        frame.render_widget(paragraph, inner);
+        return;
+    }
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(40), Constraint::Min(4)])
+        .split(inner);
+
+    // The time and totals are shown on the first row of each refresh
+    let now = Instant::now();
+    let mut previous = None;
+    let rows: Vec<Row> = app
+        .timeline
+        .rows()
+        .map(|(entry, file)| {
+            let first = previous != Some(entry.id);
+            previous = Some(entry.id);
+            create_timeline_row(entry, file, first, now, theme)
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(10),
+            Constraint::Length(14),
+            Constraint::Length(2),
+            Constraint::Min(20),
+            Constraint::Length(12),
+        ],
+    )
+    .row_highlight_style(Style::default().bg(theme.selection));
+
+    let selected = app.timeline_selected.min(app.timeline.row_count() - 1);
+    let mut state = TableState::default();
+    state.select(Some(selected));
+    frame.render_stateful_widget(table, layout[0], &mut state);
+
+    if let Some((_, file)) = app.timeline.rows().nth(selected) {
+        render_timeline_diff(frame, layout[1], app, file, theme);
+    }
+}
+
+/// Create a table row for a file touched by a refresh
+fn create_timeline_row<'a>(
+    entry: &TimelineEntry,
+    file: &TimelineFile,
+    first: bool,
+    now: Instant,
+    theme: &Theme,
+) -> Row<'a> {
+    let (when, totals) = if first {
+        let (added, deleted) = entry.deltas();
+        (
+            format_age(now.saturating_duration_since(entry.at).as_secs()),
+            format!(
+                "{} {} {:+}/{:+}",
+                entry.files.len(),
+                plural(entry.files.len(), "file", "files"),
+                added,
+                deleted
+            ),
+        )
+    } else {
+        (String::new(), String::new())
+    };
+
+    let (code, color) = if file.gone {
+        ("−", theme.subtext)
+    } else {
+        (
+            file.file.status.code(),
+            get_status_color(file.file.status, theme),
+        )
+    };
+    let section = if file.file.staged { "staged" } else { "" };
+    let path_style = if file.gone {
+        Style::default()
+            .fg(theme.subtext)
+            .add_modifier(Modifier::CROSSED_OUT)
+    } else {
+        Style::default().fg(theme.text)
+    };
+
+    Row::new(vec![
+        Span::styled(when, Style::default().fg(theme.subtext)),
+        Span::styled(totals, Style::default().fg(theme.subtext)),
+        Span::styled(code, Style::default().fg(color)),
+        Span::styled(
+            format!("{} {}", sanitize_text(&file.file.path), section),
+            path_style,
+        ),
+        Span::styled(
+            format!("{:+}/{:+}", file.added_delta, file.deleted_delta),
+            Style::default().fg(theme.warning),
+        ),
+    ])
+}
+
+/// Render the diff of the selected file as it was at that point
+fn render_timeline_diff(
+    frame: &mut Frame,
+    area: Rect,
+    app: &App,
+    file: &TimelineFile,
+    theme: &Theme,
+) {
+    let block = Block::default()
+        .borders(Borders::TOP)
+        .border_style(Style::default().fg(theme.border))
+        .title(Span::styled(
+            format!(" {} ", sanitize_text(&file.file.path)),
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+        ));
+
+    let message = if file.gone {
+        Some(" No longer listed in this section (staged, committed or reverted)")
+    } else if file.blobs.is_none() {
+        Some(" Capturing contents...")
+    } else if file
+        .blobs
+        .as_ref()
+        .is_some_and(|blobs| blobs.base.is_none() && blobs.current.is_none())
+    {
+        Some(" Contents were not captured")
+    } else {
+        None
+    };
+
+    let lines: Vec<Line> = match (message, &app.timeline_diff) {
+        (None, Some(diff)) => diff
+            .lines()
+            .map(|line| {
+                let style = diff_line_style(line, theme);
+                Line::from(Span::styled(line.to_string(), style))
+            })
+            .collect(),
+        (message, _) => vec![Line::from(Span::styled(
+            message.unwrap_or(" Loading diff..."),
+            Style::default().fg(theme.subtext),
+        ))],
+    };
+
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// Pick the singular or plural form of a noun for a count
+fn plural(count: usize, one: &'static str, many: &'static str) -> &'static str {
+    if count == 1 {
+        one
+    } else {
+        many
+    }
+}
@@ -1,3 +1,5 @@
+use ratatui::style::Color;
+
 /// Sanitize text by removing emojis and other wide Unicode characters
 /// that can cause terminal rendering issues.
 pub fn sanitize_text(text: &str) -> String {
@@ -30,6 +32,36 @@ pub fn sanitize_text(text: &str) -> String {
         .collect()
 }
 
+/// Mix two colors: `amount` 0 gives `from`, 1 gives `to`
+///
+/// Only RGB colors can be mixed; others switch over halfway.
+pub fn blend(from: Color, to: Color, amount: f32) -> Color {
+    let amount = amount.clamp(0.0, 1.0);
+    match (from, to) {
+        (Color::Rgb(r1, g1, b1), Color::Rgb(r2, g2, b2)) => {
+            let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * amount).round() as u8;
+            Color::Rgb(mix(r1, r2), mix(g1, g2), mix(b1, b2))
+        }
+        _ if amount < 0.5 => from,
+        _ => to,
+    }
+}
+
+/// Background of a change highlight in `color` that has faded by `progress` (0 to 1)
+pub fn fading_background(color: Color, base: Color, progress: f32) -> Color {
+    // A tint rather than the full color, so text stays readable
+    blend(base, color, 0.35 * (1.0 - progress))
+}
+
+/// How long ago something happened, in the largest whole unit
+pub fn format_age(seconds: u64) -> String {
+    match seconds {
+        0..60 => format!("{}s ago", seconds),
+        60..3600 => format!("{}m ago", seconds / 60),
+        _ => format!("{}h {}m ago", seconds / 3600, seconds / 60 % 60),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
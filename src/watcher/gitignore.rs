@@ -0,0 +1,228 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+
+/// Decides which worktree paths git ignores
+///
+/// Follows git's precedence: a `.gitignore` in a deeper directory overrides
+/// one closer to the root, then `.git/info/exclude`, then `core.excludesFile`.
+/// Files in the index are never ignored, even when a pattern matches them.
+#[derive(Debug)]
+pub struct IgnoreFilter {
+    /// Absolute path of the repository root
+    root: PathBuf,
+    /// `.gitignore` matchers keyed by the directory containing them (relative, `""` for the root)
+    per_dir: HashMap<PathBuf, Gitignore>,
+    /// Patterns from `.git/info/exclude`
+    exclude: Gitignore,
+    /// Patterns from `core.excludesFile`
+    global: Gitignore,
+    /// Tracked files that patterns match, and the directories above them
+    tracked: HashSet<PathBuf>,
+}
+
+impl IgnoreFilter {
+    /// Create a filter for the repository at `root` (absolute)
+    ///
+    /// `.gitignore` files are loaded as directories are scanned with [`Self::scan`].
+    pub fn new(root: &Path) -> Self {
+        let mut exclude = GitignoreBuilder::new(root);
+        exclude.add(root.join(".git").join("info").join("exclude"));
+
+        Self {
+            root: root.to_path_buf(),
+            per_dir: HashMap::new(),
+            exclude: exclude.build().unwrap_or_else(|_| Gitignore::empty()),
+            global: GitignoreBuilder::new(root).build_global().0,
+            tracked: HashSet::new(),
+        }
+    }
+
+    /// (Re)load the tracked files that ignore patterns match from the index
+    ///
+    /// Returns whether they changed. If git fails, no file is treated as tracked.
+    pub fn load_tracked(&mut self) -> bool {
+        let output = Command::new("git")
+            .args([
+                "ls-files",
+                "-z",
+                "--cached",
+                "--ignored",
+                "--exclude-standard",
+            ])
+            .current_dir(&self.root)
+            .output();
+        let listed = match output {
+            Ok(output) if output.status.success() => output.stdout,
+            _ => Vec::new(),
+        };
+
+        let mut tracked = HashSet::new();
+        for path in String::from_utf8_lossy(&listed).split('\0') {
+            if path.is_empty() {
+                continue;
+            }
+            for ancestor in Path::new(path).ancestors() {
+                if ancestor.as_os_str().is_empty() || !tracked.insert(ancestor.to_path_buf()) {
+                    break;
+                }
+            }
+        }
+
+        let changed = tracked != self.tracked;
+        self.tracked = tracked;
+        changed
+    }
+
+    /// (Re)load the `.gitignore` of a directory (relative to the root)
+    pub fn load_dir(&mut self, dir: &Path) {
+        let path = self.root.join(dir).join(".gitignore");
+        let (matcher, _) = Gitignore::new(path);
+        if matcher.is_empty() {
+            self.per_dir.remove(dir);
+        } else {
+            self.per_dir.insert(dir.to_path_buf(), matcher);
+        }
+    }
+
+    /// Whether git ignores the path (relative to the root)
+    pub fn is_ignored(&self, relative: &Path, is_dir: bool) -> bool {
+        if self.tracked.contains(relative) {
+            return false;
+        }
+        let absolute = self.root.join(relative);
+
+        // A directory's own .gitignore applies to its contents, not to itself
+        let per_dir = relative
+            .ancestors()
+            .skip(1)
+            .filter_map(|dir| self.per_dir.get(dir));
+
+        for matcher in per_dir.chain([&self.exclude, &self.global]) {
+            match matcher.matched_path_or_any_parents(&absolute, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+
+        false
+    }
+
+    /// Collect `start` and every directory below it that is not ignored
+    ///
+    /// Loads the `.gitignore` of each directory on the way. Nested `.git`
+    /// directories and symlinks are skipped. Paths are relative to the root.
+    pub fn scan(&mut self, start: &Path) -> Vec<PathBuf> {
+        let mut dirs = Vec::new();
+        let mut pending = vec![start.to_path_buf()];
+
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(self.root.join(&dir)) else {
+                continue;
+            };
+            self.load_dir(&dir);
+
+            for entry in entries.flatten() {
+                if !entry.file_type().is_ok_and(|t| t.is_dir()) || entry.file_name() == ".git" {
+                    continue;
+                }
+                let child = dir.join(entry.file_name());
+                if !self.is_ignored(&child, true) {
+                    pending.push(child);
+                }
+            }
+
+            dirs.push(dir);
+        }
+
+        dirs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_ignore_files_and_info_exclude() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for sub in [".git/info", "target/debug", "src/gen", "src/keep", "local"] {
+            std::fs::create_dir_all(root.join(sub)).unwrap();
+        }
+        std::fs::write(root.join(".gitignore"), "target/\n*.log\n").unwrap();
+        std::fs::write(root.join("src/.gitignore"), "gen/\n!important.log\n").unwrap();
+        std::fs::write(root.join(".git/info/exclude"), "local/\n").unwrap();
+
+        let mut filter = IgnoreFilter::new(root);
+        let mut dirs = filter.scan(Path::new(""));
+        dirs.sort();
+
+        assert_eq!(
+            dirs,
+            vec![
+                PathBuf::from(""),
+                PathBuf::from("src"),
+                PathBuf::from("src/keep")
+            ]
+        );
+        assert!(filter.is_ignored(Path::new("target/debug/gfm"), false));
+        assert!(filter.is_ignored(Path::new("a.log"), false));
+        assert!(filter.is_ignored(Path::new("local/notes.txt"), false));
+        assert!(!filter.is_ignored(Path::new("src/important.log"), false));
+        assert!(!filter.is_ignored(Path::new("src/main.rs"), false));
+    }
+
+    #[test]
+    fn test_tracked_files_are_not_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(args)
+                .current_dir(root)
+                .status()
+                .unwrap();
+            assert!(status.success(), "git {:?} failed", args);
+        };
+        git(&["init", "-q"]);
+        std::fs::create_dir_all(root.join("build/out")).unwrap();
+        std::fs::write(root.join(".gitignore"), "*.log\nbuild/\n").unwrap();
+        for file in [
+            "keep.log",
+            "other.log",
+            "build/out/keep.txt",
+            "build/other.txt",
+        ] {
+            std::fs::write(root.join(file), "x").unwrap();
+        }
+        git(&["add", "-f", "keep.log", "build/out/keep.txt"]);
+
+        let mut filter = IgnoreFilter::new(root);
+        assert_eq!(filter.scan(Path::new("")), vec![PathBuf::from("")]);
+        assert!(filter.is_ignored(Path::new("keep.log"), false));
+        assert!(filter.load_tracked());
+        assert!(!filter.load_tracked());
+
+        assert!(!filter.is_ignored(Path::new("keep.log"), false));
+        assert!(filter.is_ignored(Path::new("other.log"), false));
+        assert!(!filter.is_ignored(Path::new("build/out/keep.txt"), false));
+        assert!(filter.is_ignored(Path::new("build/other.txt"), false));
+
+        // Directories holding tracked files are watched
+        let mut dirs = filter.scan(Path::new(""));
+        dirs.sort();
+        assert_eq!(
+            dirs,
+            vec![
+                PathBuf::from(""),
+                PathBuf::from("build"),
+                PathBuf::from("build/out")
+            ]
+        );
+    }
+}
@@ -1 +1,3 @@
+pub mod gitignore;
 pub mod service;
+pub mod throttle;
@@ -1,21 +1,87 @@
-use std::path::PathBuf;
-use std::time::Duration;
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
 
 use color_eyre::eyre::Result;
-use notify::{Config, RecommendedWatcher, RecursiveMode};
-use notify_debouncer_mini::{new_debouncer, DebouncedEventKind, Debouncer};
+use notify::{
+    Config, ErrorKind, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher,
+};
 use tokio::sync::mpsc;
 
-use crate::event::Event;
+use super::gitignore::IgnoreFilter;
+use super::throttle::Throttle;
+use crate::config::types::{WatcherBackend, WatcherConfig};
+use crate::event::{ChangeScope, Event};
+
+/// How often the worker checks whether its watcher was dropped while idle
+const WORKER_IDLE_CHECK: Duration = Duration::from_secs(1);
+
+/// Health of the file watcher, shown next to "Watching" in the status bar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatcherStatus {
+    /// OS-native notifications (inotify, FSEvents, ReadDirectoryChangesW)
+    Native,
+    /// Polling the filesystem, with the reason when it was a fallback
+    Polling { reason: Option<String> },
+    /// Watching, but some events or directories are being missed
+    Degraded(String),
+    /// Not watching at all
+    Failed(String),
+}
+
+impl fmt::Display for WatcherStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatcherStatus::Native => write!(f, "native"),
+            WatcherStatus::Polling { .. } => write!(f, "polling"),
+            WatcherStatus::Degraded(_) => write!(f, "degraded"),
+            WatcherStatus::Failed(_) => write!(f, "failed"),
+        }
+    }
+}
+
+/// Event sender shared by all watcher backends
+type RawEvents = Sender<notify::Result<notify::Event>>;
+
+/// Create a worktree watcher for the given backend (`Auto` means native)
+fn create_watcher(
+    backend: WatcherBackend,
+    events: RawEvents,
+    poll_interval: Duration,
+) -> notify::Result<Box<dyn Watcher + Send>> {
+    let handler = move |result| {
+        let _ = events.send(result);
+    };
+    match backend {
+        WatcherBackend::Poll => Ok(Box::new(PollWatcher::new(
+            handler,
+            Config::default().with_poll_interval(poll_interval),
+        )?)),
+        WatcherBackend::Auto | WatcherBackend::Native => Ok(Box::new(RecommendedWatcher::new(
+            handler,
+            Config::default(),
+        )?)),
+    }
+}
 
 /// File watcher service that monitors the repository for changes
+///
+/// The worktree is watched one directory at a time so that ignored
+/// directories (`target/`, `node_modules/`, ...) are never watched at all.
+/// Git metadata (HEAD, index, refs) is watched by the same backend. A worker
+/// thread throttles both kinds of events into refreshes, watches directories
+/// as they are created and rescans when a `.gitignore` changes.
+///
+/// With the `auto` backend, native notifications are used until they fail
+/// (e.g. the inotify watch limit is hit), after which polling takes over.
+/// Status changes are reported as [`Event::WatcherStatus`].
 pub struct FileWatcher {
-    /// The debouncer wrapping the file watcher
-    #[allow(dead_code)]
-    debouncer: Debouncer<RecommendedWatcher>,
-    /// Separate debouncer for git internal files (HEAD, refs)
+    /// The watcher backend (shared with the watch worker)
     #[allow(dead_code)]
-    git_debouncer: Debouncer<RecommendedWatcher>,
+    watcher: Arc<Mutex<Box<dyn Watcher + Send>>>,
 }
 
 impl FileWatcher {
@@ -23,110 +89,516 @@ impl FileWatcher {
     pub fn new(
         repo_path: PathBuf,
         event_tx: mpsc::UnboundedSender<Event>,
-        debounce_duration: Duration,
+        config: &WatcherConfig,
     ) -> Result<Self> {
-        let tx = event_tx.clone();
-
-        let mut debouncer = new_debouncer(
-            debounce_duration,
-            move |result: Result<Vec<notify_debouncer_mini::DebouncedEvent>, notify::Error>| {
-                match result {
-                    Ok(events) => {
-                        // Check if any event is relevant (not just metadata changes)
-                        let has_relevant_event = events
-                            .iter()
-                            .any(|e| matches!(e.kind, DebouncedEventKind::Any));
-
-                        if has_relevant_event {
-                            // Send file change event
-                            let _ = tx.send(Event::FileChange);
+        let poll_interval = Duration::from_millis(config.poll_interval_ms);
+        let (raw_tx, raw_rx) = std::sync::mpsc::channel();
+
+        // Watch the repository root and git metadata right away; the worker
+        // adds the rest of the worktree
+        let watch_roots = |mut watcher: Box<dyn Watcher + Send>| {
+            watcher.watch(&repo_path, RecursiveMode::NonRecursive)?;
+            watch_git_metadata(watcher.as_mut(), &repo_path);
+            Ok::<_, notify::Error>(watcher)
+        };
+
+        let native =
+            create_watcher(config.backend, raw_tx.clone(), poll_interval).and_then(watch_roots);
+        let (watcher, status) = match (native, config.backend) {
+            (Ok(watcher), WatcherBackend::Poll) => {
+                (watcher, WatcherStatus::Polling { reason: None })
+            }
+            (Ok(watcher), _) => (watcher, WatcherStatus::Native),
+            (Err(e), WatcherBackend::Auto) => {
+                let watcher = create_watcher(WatcherBackend::Poll, raw_tx.clone(), poll_interval)
+                    .and_then(watch_roots)?;
+                let reason = format!("native file watching failed: {}", e);
+                (
+                    watcher,
+                    WatcherStatus::Polling {
+                        reason: Some(reason),
+                    },
+                )
+            }
+            (Err(e), _) => return Err(e.into()),
+        };
+        let _ = event_tx.send(Event::WatcherStatus(status.clone()));
+
+        let watcher = Arc::new(Mutex::new(watcher));
+        let worker = WatchWorker {
+            canonical_root: repo_path.canonicalize().ok(),
+            filter: IgnoreFilter::new(&repo_path),
+            root: repo_path,
+            watcher: Arc::downgrade(&watcher),
+            raw_tx,
+            fallback: config.backend == WatcherBackend::Auto && status == WatcherStatus::Native,
+            poll_interval,
+            watched: HashSet::from([PathBuf::new()]),
+            status,
+            event_tx,
+        };
+        let (worktree, git_metadata) = (
+            Throttle::new(&config.worktree),
+            Throttle::new(&config.git_metadata),
+        );
+        std::thread::spawn(move || worker.run(raw_rx, worktree, git_metadata));
+
+        Ok(Self { watcher })
+    }
+}
+
+/// Watch git metadata: the `.git` directory itself (HEAD, index, packed-refs)
+/// and everything under `refs`
+///
+/// Directories are watched rather than files, since git replaces files like
+/// the index by renaming a new one over them.
+fn watch_git_metadata(watcher: &mut dyn Watcher, repo_path: &Path) {
+    let git_dir = repo_path.join(".git");
+    if !git_dir.is_dir() {
+        return;
+    }
+    let _ = watcher.watch(&git_dir, RecursiveMode::NonRecursive);
+    let _ = watcher.watch(&git_dir.join("refs"), RecursiveMode::Recursive);
+}
+
+/// Background worker turning raw worktree events into debounced refreshes
+///
+/// Also keeps the set of watched directories in sync with the worktree and
+/// switches to polling when native watching runs out of resources.
+struct WatchWorker {
+    /// Absolute path of the repository root
+    root: PathBuf,
+    /// The root with symlinks resolved (event paths may use either form)
+    canonical_root: Option<PathBuf>,
+    /// Ignore rules for the worktree
+    filter: IgnoreFilter,
+    /// The worktree watcher (weak, so dropping the [`FileWatcher`] stops the worker)
+    watcher: Weak<Mutex<Box<dyn Watcher + Send>>>,
+    /// Sender handed to a replacement watcher
+    raw_tx: RawEvents,
+    /// Whether to switch to polling when native watching fails
+    fallback: bool,
+    /// Poll interval for the polling backend
+    poll_interval: Duration,
+    /// Watched directories, relative to the root
+    watched: HashSet<PathBuf>,
+    /// Last reported watcher status
+    status: WatcherStatus,
+    /// Sender for file change and watcher status events
+    event_tx: mpsc::UnboundedSender<Event>,
+}
+
+impl WatchWorker {
+    /// Scan the worktree, then handle events until the watcher is dropped
+    ///
+    /// Worktree and git metadata events are throttled separately; whatever
+    /// is due is reported together as one change.
+    fn run(
+        mut self,
+        events: Receiver<notify::Result<notify::Event>>,
+        mut worktree: Throttle,
+        mut git_metadata: Throttle,
+    ) {
+        self.filter.load_tracked();
+        self.scan(Path::new(""));
+
+        let mut worktree_paths = BTreeSet::new();
+        let mut git_paths = BTreeSet::new();
+
+        loop {
+            let timeout = [worktree.deadline(), git_metadata.deadline()]
+                .into_iter()
+                .flatten()
+                .min()
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+                .unwrap_or(WORKER_IDLE_CHECK);
+
+            let (fire_worktree, fire_git) = match events.recv_timeout(timeout) {
+                Ok(Ok(event)) => {
+                    // Reads (including git's own) are not changes
+                    if matches!(event.kind, EventKind::Access(_)) {
+                        continue;
+                    }
+
+                    let (mut in_worktree, mut in_git) = (false, false);
+                    for path in event.paths {
+                        if worktree_path(&self.roots(), &path).is_some() {
+                            worktree_paths.insert(path);
+                            in_worktree = true;
+                        } else {
+                            git_paths.insert(path);
+                            in_git = true;
                         }
                     }
-                    Err(_e) => {
-                        // Ignore watch errors silently
+
+                    let now = Instant::now();
+                    (
+                        in_worktree && worktree.event(now),
+                        in_git && git_metadata.event(now),
+                    )
+                }
+                Ok(Err(e)) => {
+                    self.report_error(e);
+                    continue;
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    let now = Instant::now();
+                    let due = (worktree.is_due(now), git_metadata.is_due(now));
+                    // We hold a sender ourselves, so check whether the watcher is gone
+                    if due == (false, false) && self.watcher.strong_count() == 0 {
+                        break;
                     }
+                    due
                 }
-            },
-        )?;
-
-        // Watch the repository directory recursively
-        debouncer
-            .watcher()
-            .configure(Config::default().with_poll_interval(Duration::from_millis(100)))?;
-
-        debouncer
-            .watcher()
-            .watch(&repo_path, RecursiveMode::Recursive)?;
-
-        // Create a separate watcher for git internal files (branch/commit changes)
-        let git_tx = event_tx.clone();
-        let mut git_debouncer = new_debouncer(
-            debounce_duration,
-            move |result: Result<Vec<notify_debouncer_mini::DebouncedEvent>, notify::Error>| {
-                match result {
-                    Ok(events) => {
-                        let has_relevant_event = events
-                            .iter()
-                            .any(|e| matches!(e.kind, DebouncedEventKind::Any));
-
-                        if has_relevant_event {
-                            let _ = git_tx.send(Event::FileChange);
-                        }
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            let now = Instant::now();
+            let mut paths = BTreeSet::new();
+            if fire_worktree {
+                worktree.fire(now);
+                paths.append(&mut worktree_paths);
+            }
+            if fire_git {
+                git_metadata.fire(now);
+                paths.append(&mut git_paths);
+            }
+            if !paths.is_empty() {
+                self.flush(paths);
+            }
+        }
+    }
+
+    /// The root as given and with symlinks resolved (event paths may use either form)
+    fn roots(&self) -> [Option<&Path>; 2] {
+        [Some(self.root.as_path()), self.canonical_root.as_deref()]
+    }
+
+    /// Report a watch error, falling back to polling if native watching is exhausted
+    fn report_error(&mut self, error: notify::Error) {
+        if self.fallback && matches!(error.kind, ErrorKind::MaxFilesWatch) {
+            self.switch_to_polling(error);
+        } else {
+            self.set_status(WatcherStatus::Degraded(error.to_string()));
+        }
+    }
+
+    /// Replace the native watcher with a polling one and watch everything again
+    fn switch_to_polling(&mut self, error: notify::Error) {
+        self.fallback = false;
+        let Some(watcher) = self.watcher.upgrade() else {
+            return;
+        };
+
+        let replacement = create_watcher(
+            WatcherBackend::Poll,
+            self.raw_tx.clone(),
+            self.poll_interval,
+        )
+        .and_then(|mut replacement| {
+            replacement.watch(&self.root, RecursiveMode::NonRecursive)?;
+            Ok(replacement)
+        });
+        match replacement {
+            Ok(mut replacement) => {
+                watch_git_metadata(replacement.as_mut(), &self.root);
+                if let Ok(mut watcher) = watcher.lock() {
+                    *watcher = replacement;
+                }
+                self.watched = HashSet::from([PathBuf::new()]);
+                self.set_status(WatcherStatus::Polling {
+                    reason: Some(format!("native file watching failed: {}", error)),
+                });
+                self.scan(Path::new(""));
+            }
+            Err(e) => self.set_status(WatcherStatus::Failed(e.to_string())),
+        }
+    }
+
+    /// Record and report a new watcher status
+    fn set_status(&mut self, status: WatcherStatus) {
+        if self.status != status {
+            self.status = status.clone();
+            let _ = self.event_tx.send(Event::WatcherStatus(status));
+        }
+    }
+
+    /// Update the watched directories for a batch of changed paths, then report them
+    fn flush(&mut self, paths: BTreeSet<PathBuf>) {
+        let (root, canonical_root) = (self.root.clone(), self.canonical_root.clone());
+        let roots = [Some(root.as_path()), canonical_root.as_deref()];
+        let worktree: Vec<PathBuf> = paths
+            .iter()
+            .filter_map(|path| worktree_path(&roots, path))
+            .map(Path::to_path_buf)
+            .collect();
+
+        // Tracked files are never ignored, so before dropping a path the
+        // patterns match, check the index for files added since it was read
+        let gitignore_changed = worktree
+            .iter()
+            .any(|path| path.file_name().is_some_and(|name| name == ".gitignore"));
+        let ignored_changed = worktree
+            .iter()
+            .any(|path| self.filter.is_ignored(path, root.join(path).is_dir()));
+        let tracked_changed = (gitignore_changed || ignored_changed) && self.filter.load_tracked();
+
+        // Rescanning reloads every .gitignore and watches newly un-ignored directories
+        if gitignore_changed || tracked_changed {
+            self.scan(Path::new(""));
+        }
+
+        for path in worktree {
+            let absolute = self.root.join(&path);
+            if absolute.is_dir() {
+                if !self.watched.contains(&path) && !self.filter.is_ignored(&path, true) {
+                    self.scan(&path);
+                }
+            } else if !absolute.exists() {
+                // Watches on deleted directories are gone; forget them so they
+                // are watched again if the directory is recreated
+                self.watched.retain(|dir| !dir.starts_with(&path));
+            }
+        }
+
+        // Reported after new directories are watched, so the refresh also
+        // covers files written into them before their watch existed
+        let filter = &self.filter;
+        let is_ignored =
+            |relative: &Path| filter.is_ignored(relative, root.join(relative).is_dir());
+        if let Some(scope) =
+            classify_changes(&roots, paths.iter().map(PathBuf::as_path), is_ignored)
+        {
+            let _ = self.event_tx.send(Event::FileChange(scope));
+        }
+    }
+
+    /// Watch `start` and every non-ignored directory below it
+    fn scan(&mut self, start: &Path) {
+        let dirs = self.filter.scan(start);
+        let Some(watcher) = self.watcher.upgrade() else {
+            return;
+        };
+
+        let mut failure = None;
+        if let Ok(mut watcher) = watcher.lock() {
+            for dir in dirs {
+                if self.watched.contains(&dir) {
+                    continue;
+                }
+                match watcher.watch(&self.root.join(&dir), RecursiveMode::NonRecursive) {
+                    Ok(()) => {
+                        self.watched.insert(dir);
                     }
-                    Err(_e) => {
-                        // Ignore watch errors silently
+                    // The directory may have been removed since the scan
+                    Err(e) if matches!(e.kind, ErrorKind::PathNotFound) => {}
+                    Err(e) => {
+                        failure = Some(e);
+                        break;
                     }
                 }
-            },
-        )?;
-
-        git_debouncer
-            .watcher()
-            .configure(Config::default().with_poll_interval(Duration::from_millis(100)))?;
-
-        // Watch .git/HEAD for branch switches
-        let git_head = repo_path.join(".git").join("HEAD");
-        if git_head.exists() {
-            let _ = git_debouncer
-                .watcher()
-                .watch(&git_head, RecursiveMode::NonRecursive);
+            }
         }
 
-        // Watch .git/refs/heads for new commits on local branches
-        let git_refs_heads = repo_path.join(".git").join("refs").join("heads");
-        if git_refs_heads.exists() {
-            let _ = git_debouncer
-                .watcher()
-                .watch(&git_refs_heads, RecursiveMode::Recursive);
+        if let Some(error) = failure {
+            self.report_error(error);
         }
+    }
+}
+
+/// Get a path relative to the repository root, unless it is the root itself or
+/// inside `.git`
+fn worktree_path<'a>(roots: &[Option<&Path>], path: &'a Path) -> Option<&'a Path> {
+    let relative = roots
+        .iter()
+        .flatten()
+        .find_map(|root| path.strip_prefix(root).ok())?;
+
+    match relative.components().next() {
+        Some(Component::Normal(first)) if first != ".git" => Some(relative),
+        _ => None,
+    }
+}
+
+/// Work out what a batch of changed paths affects
+///
+/// Worktree paths are returned relative to the repository root; paths for
+/// which `is_ignored` returns true are dropped. Changes to git metadata
+/// (index, HEAD, refs, ...) and to `.gitignore` files require a full refresh,
+/// while object database, reflog and lock file writes are ignored since they
+/// are always accompanied by a metadata change. Writes under
+/// `refs/gfm/snapshots` are gfm's own snapshots and are ignored as well.
+/// Returns None if nothing relevant changed.
+fn classify_changes<'a>(
+    roots: &[Option<&Path>],
+    paths: impl Iterator<Item = &'a Path>,
+    is_ignored: impl Fn(&Path) -> bool,
+) -> Option<ChangeScope> {
+    let mut changed = BTreeSet::new();
+
+    for path in paths {
+        let Some(relative) = roots
+            .iter()
+            .flatten()
+            .find_map(|root| path.strip_prefix(root).ok())
+        else {
+            // Outside the repository as we know it (e.g. through a symlink)
+            return Some(ChangeScope::Full);
+        };
 
-        // Watch .git/refs/remotes for remote tracking branch updates (after fetch/pull)
-        let git_refs_remotes = repo_path.join(".git").join("refs").join("remotes");
-        if git_refs_remotes.exists() {
-            let _ = git_debouncer
-                .watcher()
-                .watch(&git_refs_remotes, RecursiveMode::Recursive);
+        let mut components = relative.components();
+        match components.next() {
+            // The repository root itself changed
+            None => return Some(ChangeScope::Full),
+            Some(Component::Normal(first)) if first == ".git" => match components.next() {
+                // The .git directory itself (seen from the root's watch)
+                None => continue,
+                Some(Component::Normal(dir)) if dir == "objects" || dir == "logs" => continue,
+                // Lock files are renamed over the real file, which is reported too
+                _ if relative.extension().is_some_and(|ext| ext == "lock") => continue,
+                // Snapshot refs and the temporary snapshot index are gfm's own writes
+                _ if relative.starts_with(".git/refs/gfm") => continue,
+                Some(Component::Normal(name)) if name.to_string_lossy().starts_with("gfm-") => {
+                    continue
+                }
+                _ => return Some(ChangeScope::Full),
+            },
+            Some(_) if is_ignored(relative) => continue,
+            Some(_)
+                if relative
+                    .file_name()
+                    .is_some_and(|name| name == ".gitignore") =>
+            {
+                return Some(ChangeScope::Full)
+            }
+            Some(_) => {
+                let Some(relative) = relative.to_str() else {
+                    return Some(ChangeScope::Full);
+                };
+                changed.insert(relative.replace('\\', "/"));
+            }
         }
+    }
 
-        // Watch .git/index for staging area changes
-        let git_index = repo_path.join(".git").join("index");
-        if git_index.exists() {
-            let _ = git_debouncer
-                .watcher()
-                .watch(&git_index, RecursiveMode::NonRecursive);
+    if changed.is_empty() {
+        None
+    } else {
+        Some(ChangeScope::Paths(changed.into_iter().collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(paths: &[&str]) -> Option<ChangeScope> {
+        let root = Path::new("/repo");
+        classify_changes(&[Some(root)], paths.iter().map(Path::new), |relative| {
+            relative.starts_with("target")
+        })
+    }
+
+    #[test]
+    fn test_worktree_changes_are_relative_paths() {
+        assert_eq!(
+            classify(&["/repo/src/b.rs", "/repo/a.rs", "/repo/src/b.rs"]),
+            Some(ChangeScope::Paths(vec![
+                "a.rs".to_string(),
+                "src/b.rs".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_git_metadata_changes_need_full_refresh() {
+        assert_eq!(
+            classify(&["/repo/a.rs", "/repo/.git/index"]),
+            Some(ChangeScope::Full)
+        );
+        assert_eq!(
+            classify(&["/repo/.git/refs/heads/main"]),
+            Some(ChangeScope::Full)
+        );
+        assert_eq!(classify(&["/elsewhere/a.rs"]), Some(ChangeScope::Full));
+    }
+
+    #[test]
+    fn test_ignored_paths_are_dropped() {
+        assert_eq!(classify(&["/repo/target/debug/gfm"]), None);
+        assert_eq!(
+            classify(&["/repo/target/x", "/repo/a.rs"]),
+            Some(ChangeScope::Paths(vec!["a.rs".to_string()]))
+        );
+        assert_eq!(classify(&["/repo/src/.gitignore"]), Some(ChangeScope::Full));
+    }
+
+    #[tokio::test]
+    async fn test_native_backend_watches_new_directories_but_not_ignored_ones() {
+        assert_watches_new_directories_but_not_ignored_ones(WatcherBackend::Native).await;
+    }
+
+    #[tokio::test]
+    async fn test_poll_backend_watches_new_directories_but_not_ignored_ones() {
+        assert_watches_new_directories_but_not_ignored_ones(WatcherBackend::Poll).await;
+    }
+
+    async fn assert_watches_new_directories_but_not_ignored_ones(backend: WatcherBackend) {
+        let config = WatcherConfig {
+            backend,
+            poll_interval_ms: 50,
+            ..WatcherConfig::default()
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n").unwrap();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let _watcher = FileWatcher::new(root.clone(), tx, &config).unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        while let Ok(event) = rx.try_recv() {
+            assert!(matches!(event, Event::WatcherStatus(_)), "{:?}", event);
         }
 
-        // Watch .git/packed-refs for packed reference updates
-        let git_packed_refs = repo_path.join(".git").join("packed-refs");
-        if git_packed_refs.exists() {
-            let _ = git_debouncer
-                .watcher()
-                .watch(&git_packed_refs, RecursiveMode::NonRecursive);
+        // Writes into ignored directories never reach the app
+        std::fs::write(root.join("target/out.o"), "x").unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(rx.try_recv().is_err());
+
+        // A new directory is picked up, and files written into it are reported
+        std::fs::create_dir(root.join("new")).unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        std::fs::write(root.join("new/file.txt"), "x").unwrap();
+
+        let mut seen = Vec::new();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(3);
+        while !seen.contains(&"new/file.txt".to_string()) {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(Event::FileChange(ChangeScope::Paths(paths)))) => seen.extend(paths),
+                Ok(Some(_)) => {}
+                _ => panic!("new/file.txt was not reported, saw {:?}", seen),
+            }
         }
+        assert!(seen.iter().all(|p| !p.starts_with("target")));
 
-        Ok(Self {
-            debouncer,
-            git_debouncer,
-        })
+        // Git metadata changes (seen through the same watcher) need a full refresh
+        std::fs::write(root.join(".git/HEAD"), "ref: refs/heads/other\n").unwrap();
+        loop {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(Event::FileChange(ChangeScope::Full))) => break,
+                Ok(Some(_)) => {}
+                _ => panic!("HEAD change was not reported"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_object_writes_are_ignored() {
+        assert_eq!(classify(&["/repo/.git/objects/ab/cdef"]), None);
+        assert_eq!(classify(&["/repo/.git/logs/HEAD"]), None);
+        assert_eq!(classify(&["/repo/.git/index.lock", "/repo/.git"]), None);
+        assert_eq!(classify(&["/repo/.git/refs/gfm/snapshots/1"]), None);
+        assert_eq!(classify(&["/repo/.git/gfm-snapshot-index"]), None);
     }
 }
@@ -0,0 +1,125 @@
+use std::time::{Duration, Instant};
+
+use crate::config::types::ThrottleConfig;
+
+/// Leading/trailing throttle with a maximum wait
+///
+/// The first event after a quiet period fires right away (if `leading` is
+/// set). Later events are held until nothing happened for `quiet`, but never
+/// longer than `max_wait`, so continuous writes still produce regular updates.
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    /// How long events must stop before pending ones fire
+    quiet: Duration,
+    /// Longest time an event may stay pending
+    max_wait: Duration,
+    /// Whether the first event after a quiet period fires immediately
+    leading: bool,
+    /// When the oldest pending event arrived
+    first_pending: Option<Instant>,
+    /// When the newest pending event arrived
+    last_pending: Option<Instant>,
+    /// When events last fired
+    last_fire: Option<Instant>,
+}
+
+impl Throttle {
+    /// Create a throttle from its settings
+    pub fn new(config: &ThrottleConfig) -> Self {
+        let quiet = Duration::from_millis(config.debounce_ms);
+        Self {
+            quiet,
+            max_wait: Duration::from_millis(config.max_wait_ms).max(quiet),
+            leading: config.leading,
+            first_pending: None,
+            last_pending: None,
+            last_fire: None,
+        }
+    }
+
+    /// Record an event, returning true if it should be handled right away
+    pub fn event(&mut self, now: Instant) -> bool {
+        let idle = self.first_pending.is_none()
+            && self
+                .last_fire
+                .is_none_or(|fired| now.duration_since(fired) >= self.quiet);
+        if self.leading && idle {
+            self.last_fire = Some(now);
+            return true;
+        }
+
+        self.first_pending.get_or_insert(now);
+        self.last_pending = Some(now);
+        false
+    }
+
+    /// When the pending events are due, if there are any
+    pub fn deadline(&self) -> Option<Instant> {
+        let first = self.first_pending?;
+        let last = self.last_pending?;
+        Some((last + self.quiet).min(first + self.max_wait))
+    }
+
+    /// Whether pending events are due at `now`
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.deadline().is_some_and(|deadline| deadline <= now)
+    }
+
+    /// Mark the pending events as handled
+    pub fn fire(&mut self, now: Instant) {
+        self.first_pending = None;
+        self.last_pending = None;
+        self.last_fire = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttle(leading: bool) -> Throttle {
+        Throttle::new(&ThrottleConfig {
+            debounce_ms: 100,
+            max_wait_ms: 500,
+            leading,
+        })
+    }
+
+    #[test]
+    fn test_leading_edge_then_trailing() {
+        let start = Instant::now();
+        let ms = |n| start + Duration::from_millis(n);
+        let mut throttle = throttle(true);
+
+        assert!(throttle.event(ms(0)));
+        assert_eq!(throttle.deadline(), None);
+
+        // Events right after the leading edge wait for a quiet period
+        assert!(!throttle.event(ms(30)));
+        assert!(!throttle.event(ms(60)));
+        assert_eq!(throttle.deadline(), Some(ms(160)));
+        assert!(!throttle.is_due(ms(159)));
+        assert!(throttle.is_due(ms(160)));
+        throttle.fire(ms(160));
+
+        // Still too soon after the trailing fire for another leading edge
+        assert!(!throttle.event(ms(200)));
+        throttle.fire(ms(300));
+        assert!(throttle.event(ms(450)));
+    }
+
+    #[test]
+    fn test_continuous_events_fire_after_max_wait() {
+        let start = Instant::now();
+        let mut throttle = throttle(false);
+
+        for n in 0..20 {
+            assert!(!throttle.event(start + Duration::from_millis(n * 50)));
+        }
+        // Never quiet for 100ms, so only the max wait bounds the delay
+        assert_eq!(
+            throttle.deadline(),
+            Some(start + Duration::from_millis(500))
+        );
+    }
+}